@@ -53,6 +53,26 @@ defmodule Tokenizers.AddedToken do
   """
   @spec info(added_token :: t()) :: map()
   defdelegate info(model), to: Tokenizers.Native, as: :added_token_info
+
+  @doc """
+  Serializes the added token to JSON.
+
+  The JSON has the same format as the corresponding entry in a
+  `tokenizer.json` file, so it can be versioned and shared on its own.
+
+  ## Options
+
+    * `:pretty` - whether to pretty print the JSON. Defaults to `false`
+
+  """
+  @spec to_json(t(), keyword()) :: {:ok, String.t()} | {:error, term()}
+  defdelegate to_json(added_token, opts \\ []), to: Tokenizers.Native, as: :added_token_to_json
+
+  @doc """
+  Loads a added token from JSON, as returned by `to_json/2`.
+  """
+  @spec from_json(String.t()) :: {:ok, t()} | {:error, term()}
+  defdelegate from_json(json), to: Tokenizers.Native, as: :added_token_from_json
 end
 
 defimpl Inspect, for: Tokenizers.AddedToken do
@@ -0,0 +1,71 @@
+defmodule Tokenizers.DecodeStream do
+  @moduledoc """
+  Incremental decoding of generated tokens.
+
+  When generating text one token at a time, decoding the whole sequence
+  after each new token is wasteful, while decoding each token on its
+  own gives wrong results: decoders look at the surrounding tokens to
+  place spaces, and a single character may be split across several
+  byte-level or byte fallback tokens.
+
+  A decode stream is fed one id at a time with `step/2` and returns only
+  the newly completed text, holding back incomplete characters until
+  the following ids complete them. It works with any decoder configured
+  on the tokenizer. Once the last id is added, `finish/1` returns the
+  text still held back.
+
+  The stream keeps state between calls, so it should be used by a
+  single process.
+
+  ## Examples
+
+      iex> {:ok, tokenizer} = Tokenizers.Tokenizer.from_file("test/fixtures/bert-base-cased.json")
+      iex> {:ok, encoding} = Tokenizers.Tokenizer.encode(tokenizer, "Hello world!")
+      iex> stream = Tokenizers.DecodeStream.new(tokenizer)
+      iex> for id <- Tokenizers.Encoding.get_ids(encoding) do
+      ...>   {:ok, text} = Tokenizers.DecodeStream.step(stream, id)
+      ...>   text
+      ...> end
+      [nil, "Hello", " world", "!", nil]
+
+  """
+
+  defstruct [:resource]
+
+  @type t() :: %__MODULE__{resource: reference()}
+
+  @doc """
+  Creates a decode stream for the given tokenizer.
+
+  ## Options
+
+    * `:skip_special_tokens` - whether to exclude special tokens from
+      the decoded text. Defaults to `true`
+
+  """
+  @spec new(Tokenizers.Tokenizer.t(), keyword()) :: t()
+  def new(tokenizer, opts \\ []) do
+    opts = Keyword.validate!(opts, skip_special_tokens: true)
+    Tokenizers.Native.decode_stream_new(tokenizer, opts)
+  end
+
+  @doc """
+  Adds the next id to the stream.
+
+  Returns the text completed by this id, or `nil` when it doesn't
+  complete any text yet.
+  """
+  @spec step(t(), non_neg_integer()) :: {:ok, String.t() | nil} | {:error, term()}
+  defdelegate step(stream, id), to: Tokenizers.Native, as: :decode_stream_step
+
+  @doc """
+  Ends the stream.
+
+  Returns the text held back by `step/2`, such as the bytes of an
+  incomplete character decoded as replacement characters, or `nil` when
+  there is none. The stream is then reset, so it can decode a new
+  sequence.
+  """
+  @spec finish(t()) :: {:ok, String.t() | nil} | {:error, term()}
+  defdelegate finish(stream), to: Tokenizers.Native, as: :decode_stream_finish
+end
@@ -122,6 +122,26 @@ defmodule Tokenizers.Decoder do
   defdelegate word_piece(opts \\ []),
     to: Tokenizers.Native,
     as: :decoders_wordpiece
+
+  @doc """
+  Serializes the decoder to JSON.
+
+  The JSON has the same format as the corresponding entry in a
+  `tokenizer.json` file, so it can be versioned and shared on its own.
+
+  ## Options
+
+    * `:pretty` - whether to pretty print the JSON. Defaults to `false`
+
+  """
+  @spec to_json(t(), keyword()) :: {:ok, String.t()} | {:error, term()}
+  defdelegate to_json(decoder, opts \\ []), to: Tokenizers.Native, as: :decoders_to_json
+
+  @doc """
+  Loads a decoder from JSON, as returned by `to_json/2`.
+  """
+  @spec from_json(String.t()) :: {:ok, t()} | {:error, term()}
+  defdelegate from_json(json), to: Tokenizers.Native, as: :decoders_from_json
 end
 
 defimpl Inspect, for: Tokenizers.Decoder do
@@ -88,6 +88,66 @@ defmodule Tokenizers.Encoding do
     to: Tokenizers.Native,
     as: :encoding_get_u32_special_tokens_mask
 
+  @typedoc """
+  Integer type of exported binaries, using the same notation as Nx.
+  """
+  @type binary_type :: {:u, 8} | {:u, 16} | {:u, 32} | {:s, 32} | {:s, 64}
+
+  @doc """
+  Same as `get_ids/1`, but returns a binary with values of the given
+  type.
+
+  Returns an error if any of the values doesn't fit in the requested
+  type.
+
+  ## Options
+
+    * `:type` - the integer type of the values, one of `{:u, 8}`,
+      `{:u, 16}`, `{:u, 32}`, `{:s, 32}` or `{:s, 64}`. Defaults to
+      `{:u, 32}`
+
+    * `:endianness` - the byte order of the values, either `:native`,
+      `:little` or `:big`. Defaults to `:native`
+
+  """
+  @spec get_ids_binary(t(), keyword()) :: {:ok, binary()} | {:error, term()}
+  defdelegate get_ids_binary(encoding, opts \\ []),
+    to: Tokenizers.Native,
+    as: :encoding_get_ids_binary
+
+  @doc """
+  Same as `get_type_ids/1`, but returns a binary with values of the
+  given type.
+
+  Accepts the same options as `get_ids_binary/2`.
+  """
+  @spec get_type_ids_binary(t(), keyword()) :: {:ok, binary()} | {:error, term()}
+  defdelegate get_type_ids_binary(encoding, opts \\ []),
+    to: Tokenizers.Native,
+    as: :encoding_get_type_ids_binary
+
+  @doc """
+  Same as `get_attention_mask/1`, but returns a binary with values of
+  the given type.
+
+  Accepts the same options as `get_ids_binary/2`.
+  """
+  @spec get_attention_mask_binary(t(), keyword()) :: {:ok, binary()} | {:error, term()}
+  defdelegate get_attention_mask_binary(encoding, opts \\ []),
+    to: Tokenizers.Native,
+    as: :encoding_get_attention_mask_binary
+
+  @doc """
+  Same as `get_special_tokens_mask/1`, but returns a binary with values
+  of the given type.
+
+  Accepts the same options as `get_ids_binary/2`.
+  """
+  @spec get_special_tokens_mask_binary(t(), keyword()) :: {:ok, binary()} | {:error, term()}
+  defdelegate get_special_tokens_mask_binary(encoding, opts \\ []),
+    to: Tokenizers.Native,
+    as: :encoding_get_special_tokens_mask_binary
+
   @doc """
   Returns the tokens from `encoding`.
   """
@@ -109,7 +169,11 @@ defmodule Tokenizers.Encoding do
   @doc """
   Returns offsets from `encoding`.
 
-  The offsets are expressed in terms of UTF-8 bytes.
+  The offsets are expressed in terms of UTF-8 bytes, unless the
+  encoding was created with `offset_type: :chars` (see
+  `Tokenizers.Tokenizer.encode/3`). The same unit applies to all the
+  char-based functions in this module, such as `token_to_chars/2` and
+  `char_to_token/3`.
   """
   @spec get_offsets(t()) :: [{integer(), integer()}]
   defdelegate get_offsets(encoding), to: Tokenizers.Native, as: :encoding_get_offsets
@@ -164,6 +228,8 @@ defmodule Tokenizers.Encoding do
 
   @doc """
   Returns the token that contains the given char.
+
+  The position is expressed in the same unit as `get_offsets/1`.
   """
   @spec char_to_token(t(), non_neg_integer(), non_neg_integer()) ::
           non_neg_integer() | nil
@@ -173,6 +239,8 @@ defmodule Tokenizers.Encoding do
 
   @doc """
   Returns the word that contains the given char.
+
+  The position is expressed in the same unit as `get_offsets/1`.
   """
   @spec char_to_word(t(), non_neg_integer(), non_neg_integer()) ::
           non_neg_integer() | nil
@@ -0,0 +1,53 @@
+defmodule Tokenizers.Error do
+  @moduledoc """
+  An error returned or raised by the native tokenizers library.
+
+  Functions that can fail at runtime, such as `Tokenizers.Tokenizer.encode/3`
+  or `Tokenizers.Tokenizer.from_file/2`, return `{:error, %Tokenizers.Error{}}`.
+  Builders that return the created struct directly, such as
+  `Tokenizers.PreTokenizer.split_regex/3`, raise it instead.
+
+  The `:kind` field can be used to pattern match on the failure:
+
+    * `:invalid_char` - an integer is not a valid Unicode codepoint
+
+    * `:invalid_regex` - a regular expression could not be compiled
+
+    * `:invalid_template` - a post-processor template could not be parsed,
+      or a chat template could not be parsed or rendered, including errors
+      raised by the template itself
+
+    * `:unknown_token` - a token is missing from the vocabulary, for
+      example the unknown token or a special token used in a template
+
+    * `:invalid_model_file` - a model file in a non-JSON format, such as
+      a SentencePiece model, is malformed or unsupported
+
+    * `:io` - reading or writing a file failed
+
+    * `:json_parse` - JSON could not be parsed, `:details` contains
+      the `:line` and `:column` of the failure
+
+    * `:tokenizer` - any other failure reported by the tokenizers library
+
+    * `:internal`, `:other` and `:unknown` - unexpected failures
+
+  """
+
+  defexception [:kind, :message, details: %{}]
+
+  @type kind ::
+          :invalid_char
+          | :invalid_regex
+          | :invalid_template
+          | :unknown_token
+          | :invalid_model_file
+          | :io
+          | :json_parse
+          | :tokenizer
+          | :internal
+          | :other
+          | :unknown
+
+  @type t :: %__MODULE__{kind: kind(), message: String.t(), details: map()}
+end
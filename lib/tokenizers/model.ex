@@ -18,6 +18,44 @@ defmodule Tokenizers.Model do
   @spec info(t()) :: map()
   defdelegate info(model), to: Tokenizers.Native, as: :models_info
 
+  @doc """
+  Runs the model on a single pre-tokenized word.
+
+  This bypasses the rest of the tokenizer pipeline, which is useful
+  to debug the model separately from normalization and pre-tokenization.
+
+  Returns a list of `{id, token, {start, end}}` tuples, where the offsets
+  are byte offsets into `word`.
+  """
+  @spec tokenize(t(), String.t()) ::
+          {:ok, [{non_neg_integer(), String.t(), {non_neg_integer(), non_neg_integer()}}]}
+          | {:error, term()}
+  defdelegate tokenize(model, word), to: Tokenizers.Native, as: :models_tokenize
+
+  @doc """
+  Convert a given token into its id, according to the model vocabulary.
+  """
+  @spec token_to_id(t(), String.t()) :: non_neg_integer() | nil
+  defdelegate token_to_id(model, token), to: Tokenizers.Native, as: :models_token_to_id
+
+  @doc """
+  Convert a given id into its token, according to the model vocabulary.
+  """
+  @spec id_to_token(t(), non_neg_integer()) :: String.t() | nil
+  defdelegate id_to_token(model, id), to: Tokenizers.Native, as: :models_id_to_token
+
+  @doc """
+  Get the model's vocabulary as a map of token to id.
+  """
+  @spec get_vocab(t()) :: %{String.t() => integer()}
+  defdelegate get_vocab(model), to: Tokenizers.Native, as: :models_get_vocab
+
+  @doc """
+  Get the number of tokens in the model's vocabulary.
+  """
+  @spec get_vocab_size(t()) :: non_neg_integer()
+  defdelegate get_vocab_size(model), to: Tokenizers.Native, as: :models_get_vocab_size
+
   @doc """
   Saves the given model in the given directory.
 
@@ -33,6 +71,26 @@ defmodule Tokenizers.Model do
   """
   @spec save(t(), String.t(), keyword()) :: {:ok, file_paths :: [String.t()]} | {:error, any()}
   defdelegate save(model, directory, opts \\ []), to: Tokenizers.Native, as: :models_save
+
+  @doc """
+  Serializes the model to JSON.
+
+  The JSON has the same format as the corresponding entry in a
+  `tokenizer.json` file, so it can be versioned and shared on its own.
+
+  ## Options
+
+    * `:pretty` - whether to pretty print the JSON. Defaults to `false`
+
+  """
+  @spec to_json(t(), keyword()) :: {:ok, String.t()} | {:error, term()}
+  defdelegate to_json(model, opts \\ []), to: Tokenizers.Native, as: :models_to_json
+
+  @doc """
+  Loads a model from JSON, as returned by `to_json/2`.
+  """
+  @spec from_json(String.t()) :: {:ok, t()} | {:error, term()}
+  defdelegate from_json(json), to: Tokenizers.Native, as: :models_from_json
 end
 
 defimpl Inspect, for: Tokenizers.Model do
@@ -54,4 +54,19 @@ defmodule Tokenizers.Model.BPE do
   defdelegate from_file(vocab_path, merges_path, options \\ []),
     to: Tokenizers.Native,
     as: :models_bpe_from_file
+
+  @doc """
+  Returns the merges of a BPE model, ordered by rank.
+
+  The position of each merge in the list is its rank, with the merge
+  learned first at the head. The result has the same format as the
+  `merges` argument of `init/3`.
+
+  Merges are read back from the serialized model, where each pair is
+  stored as a single space-separated string. When tokens contain spaces,
+  the pair is split so that both tokens and their merge are in the
+  vocabulary, which may still be ambiguous for unusual vocabularies.
+  """
+  @spec merges(Tokenizers.Model.t()) :: {:ok, [{String.t(), String.t()}]} | {:error, term()}
+  defdelegate merges(model), to: Tokenizers.Native, as: :models_bpe_merges
 end
@@ -24,4 +24,13 @@ defmodule Tokenizers.Model.Unigram do
   """
   @spec empty() :: {:ok, Tokenizers.Model.t()}
   defdelegate empty(), to: Tokenizers.Native, as: :models_unigram_empty
+
+  @doc """
+  Returns the pieces of a Unigram model along with their scores.
+
+  The pieces are listed in id order and the result has the same format
+  as the `vocab` argument of `init/2`.
+  """
+  @spec vocab(Tokenizers.Model.t()) :: {:ok, [{String.t(), float()}]} | {:error, term()}
+  defdelegate vocab(model), to: Tokenizers.Native, as: :models_unigram_vocab
 end
@@ -16,11 +16,15 @@ defmodule Tokenizers.Native do
   def added_token_new(_token, _opts), do: err()
   #
   def added_token_info(_added_token), do: err()
+  def added_token_to_json(_added_token, _opts), do: err()
+  def added_token_from_json(_json), do: err()
 
   # Decoders
   def decoders_decode(_decoder, _tokens), do: err()
   #
   def decoders_info(_decoder), do: err()
+  def decoders_to_json(_decoder, _opts), do: err()
+  def decoders_from_json(_json), do: err()
   #
   def decoders_byte_level(), do: err()
   def decoders_replace(_pattern, _content), do: err()
@@ -45,6 +49,10 @@ defmodule Tokenizers.Native do
   def encoding_get_u32_attention_mask(_encoding), do: err()
   def encoding_get_special_tokens_mask(_encoding), do: err()
   def encoding_get_u32_special_tokens_mask(_encoding), do: err()
+  def encoding_get_ids_binary(_encoding, _opts), do: err()
+  def encoding_get_type_ids_binary(_encoding, _opts), do: err()
+  def encoding_get_attention_mask_binary(_encoding, _opts), do: err()
+  def encoding_get_special_tokens_mask_binary(_encoding, _opts), do: err()
   def encoding_get_tokens(_encoding), do: err()
   def encoding_get_word_ids(_encoding), do: err()
   def encoding_get_sequence_ids(_encoding), do: err()
@@ -65,11 +73,20 @@ defmodule Tokenizers.Native do
   # Models
   def models_save(_model, _folder, _opts), do: err()
   #
+  def models_tokenize(_model, _word), do: err()
+  def models_token_to_id(_model, _token), do: err()
+  def models_id_to_token(_model, _id), do: err()
+  def models_get_vocab(_model), do: err()
+  def models_get_vocab_size(_model), do: err()
+  #
   def models_info(_model), do: err()
+  def models_to_json(_model, _opts), do: err()
+  def models_from_json(_json), do: err()
   #
   def models_bpe_init(_vocab, _merges, _options), do: err()
   def models_bpe_empty(), do: err()
   def models_bpe_from_file(_vocab, _merges, _options), do: err()
+  def models_bpe_merges(_model), do: err()
   #
   def models_wordpiece_init(_vocab, _options), do: err()
   def models_wordpiece_empty(), do: err()
@@ -81,11 +98,14 @@ defmodule Tokenizers.Native do
   #
   def models_unigram_init(_vocab, _options), do: err()
   def models_unigram_empty(), do: err()
+  def models_unigram_vocab(_model), do: err()
 
   # Normalizers
   def normalizers_normalize(_normalizer, _input), do: err()
   #
   def normalizers_info(_normalizer), do: err()
+  def normalizers_to_json(_normalizer, _opts), do: err()
+  def normalizers_from_json(_json), do: err()
   #
   def normalizers_bert_normalizer(_opts), do: err()
   def normalizers_nfd(), do: err()
@@ -102,9 +122,11 @@ defmodule Tokenizers.Native do
   def normalizers_precompiled(_data), do: err()
 
   # PreTokenizers
-  def pre_tokenizers_pre_tokenize(_pre_tokenizer, _input), do: err()
+  def pre_tokenizers_pre_tokenize(_pre_tokenizer, _input, _opts), do: err()
   #
   def pre_tokenizers_info(_pre_tokenizer), do: err()
+  def pre_tokenizers_to_json(_pre_tokenizer, _opts), do: err()
+  def pre_tokenizers_from_json(_json), do: err()
   #
   def pre_tokenizers_byte_level(_opts), do: err()
   def pre_tokenizers_byte_level_alphabet(), do: err()
@@ -117,9 +139,12 @@ defmodule Tokenizers.Native do
   def pre_tokenizers_punctuation(_behavior), do: err()
   def pre_tokenizers_sequence(_pre_tokenizers), do: err()
   def pre_tokenizers_digits(_options), do: err()
+  def pre_tokenizers_unicode_scripts(), do: err()
 
   # PostProcessors
   def post_processors_info(_post_processor), do: err()
+  def post_processors_to_json(_post_processor, _opts), do: err()
+  def post_processors_from_json(_json), do: err()
   #
   def post_processors_bert(_sep, _cls), do: err()
   def post_processors_roberta(_sep, _cls, _opts), do: err()
@@ -135,11 +160,29 @@ defmodule Tokenizers.Native do
   def trainers_wordlevel_trainer(_options), do: err()
   def trainers_unigram_trainer(_options), do: err()
 
+  # Decode streams
+  def decode_stream_new(_tokenizer, _options), do: err()
+  def decode_stream_step(_stream, _id), do: err()
+  def decode_stream_finish(_stream), do: err()
+
+  # Training jobs
+  def training_job_start_from_files(_tokenizer, _files, _trainer, _progress), do: err()
+  def training_job_start_from_iterator(_tokenizer, _trainer, _progress), do: err()
+  def training_job_feed(_job, _sequences), do: err()
+  def training_job_close_input(_job), do: err()
+  def training_job_status(_job), do: err()
+  def training_job_cancel(_job), do: err()
+
   # Tokenizer
   def tokenizer_init(_model), do: err()
   def tokenizer_from_file(_path, _options), do: err()
   def tokenizer_from_buffer(_buffer, _options), do: err()
+  def tokenizer_from_sentencepiece_file(_path, _options), do: err()
+  def tokenizer_from_sentencepiece_buffer(_buffer, _options), do: err()
+  def tokenizer_from_tiktoken_file(_path, _pattern, _options), do: err()
+  def tokenizer_from_tiktoken_buffer(_buffer, _pattern, _options), do: err()
   def tokenizer_save(_tokenizer, _folder, _options), do: err()
+  def tokenizer_to_buffer(_tokenizer, _options), do: err()
   #
   def tokenizer_get_model(_tokenizer), do: err()
   def tokenizer_set_model(_tokenizer, _model), do: err()
@@ -151,6 +194,7 @@ defmodule Tokenizers.Native do
   def tokenizer_set_post_processor(_tokenizer, _post_processor), do: err()
   def tokenizer_get_decoder(_tokenizer), do: err()
   def tokenizer_set_decoder(_tokenizer, _decoder), do: err()
+  def tokenizer_get_special_tokens(_tokenizer), do: err()
   def tokenizer_get_vocab(_tokenizer, _with_added_tokens), do: err()
   def tokenizer_get_vocab_size(_tokenizer, _with_added_tokens), do: err()
   def tokenizer_add_tokens(_tokenizer, _tokens), do: err()
@@ -162,13 +206,25 @@ defmodule Tokenizers.Native do
   #
   def tokenizer_encode(_tokenizer, _input, _options), do: err()
   def tokenizer_encode_batch(_tokenizer, _inputs, _options), do: err()
+  def tokenizer_encode_batch_binaries(_tokenizer, _inputs, _options, _binary_options),
+    do: err()
+  def tokenizer_encode_windows(_tokenizer, _input, _max_length, _options), do: err()
+  def tokenizer_encode_windows_batch(_tokenizer, _inputs, _max_length, _options), do: err()
+  def tokenizer_count_tokens(_tokenizer, _input, _options), do: err()
+  def tokenizer_count_tokens_batch(_tokenizer, _inputs, _options), do: err()
+  def tokenizer_apply_chat_template(_tokenizer, _messages, _options), do: err()
   def tokenizer_decode(_tokenizer, _ids, _options), do: err()
   def tokenizer_decode_batch(_tokenizer, _ids, _options), do: err()
+  def tokenizer_decode_with_spans(_tokenizer, _ids, _options), do: err()
   def tokenizer_token_to_id(_tokenizer, _token), do: err()
   def tokenizer_id_to_token(_tokenizer, _id), do: err()
   def tokenizer_post_processing(_tokenizer, _encoding, _pair, _add_special_tokens), do: err()
   #
-  def tokenizer_train_from_files(_tokenizer, _files, _trainer), do: err()
+  def tokenizer_train_from_files(_tokenizer, _files, _trainer, _progress), do: err()
+  def tokenizer_train_from_iterator_start(_tokenizer, _trainer, _progress), do: err()
+  def tokenizer_train_from_iterator_feed(_training, _sequences), do: err()
+  def tokenizer_train_from_iterator_cancel(_training), do: err()
+  def tokenizer_train_from_iterator(_training), do: err()
 
   defp err(), do: :erlang.nif_error(:nif_not_loaded)
 end
@@ -16,6 +16,23 @@ defmodule Tokenizers.Normalizer do
 
   @type t() :: %__MODULE__{resource: reference()}
 
+  @doc """
+  Retrieves information about the normalizer.
+
+  The information includes `normalizer_type` and every configured
+  option of the given normalizer. For a Sequence normalizer, the
+  information of each child is listed under `normalizers`.
+
+  ## Example
+
+      iex> normalizer = Tokenizers.Normalizer.strip(right: false)
+      iex> Tokenizers.Normalizer.info(normalizer)
+      %{"left" => true, "normalizer_type" => "StripNormalizer", "right" => false}
+
+  """
+  @spec info(t()) :: map()
+  defdelegate info(normalizer), to: Tokenizers.Native, as: :normalizers_info
+
   @doc """
   Normalizes the given text input.
   """
@@ -148,6 +165,26 @@ defmodule Tokenizers.Normalizer do
   """
   @spec precompiled(binary()) :: {:ok, t()} | {:error, any()}
   defdelegate precompiled(data), to: Tokenizers.Native, as: :normalizers_precompiled
+
+  @doc """
+  Serializes the normalizer to JSON.
+
+  The JSON has the same format as the corresponding entry in a
+  `tokenizer.json` file, so it can be versioned and shared on its own.
+
+  ## Options
+
+    * `:pretty` - whether to pretty print the JSON. Defaults to `false`
+
+  """
+  @spec to_json(t(), keyword()) :: {:ok, String.t()} | {:error, term()}
+  defdelegate to_json(normalizer, opts \\ []), to: Tokenizers.Native, as: :normalizers_to_json
+
+  @doc """
+  Loads a normalizer from JSON, as returned by `to_json/2`.
+  """
+  @spec from_json(String.t()) :: {:ok, t()} | {:error, term()}
+  defdelegate from_json(json), to: Tokenizers.Native, as: :normalizers_from_json
 end
 
 defimpl Inspect, for: Tokenizers.Normalizer do
@@ -11,6 +11,26 @@ defmodule Tokenizers.PostProcessor do
 
   @type t() :: %__MODULE__{resource: reference()}
 
+  @doc """
+  Retrieves information about the post-processor.
+
+  The information includes `post_processor_type` and every configured
+  option of the given post-processor. Templates are returned in the
+  notation accepted by `template/1`, with explicit type ids, and special
+  tokens map to their `"ids"` and `"tokens"`. For a Sequence
+  post-processor, the information of each child is listed under
+  `processors`.
+
+  ## Example
+
+      iex> post_processor = Tokenizers.PostProcessor.bert({"[SEP]", 102}, {"[CLS]", 101})
+      iex> Tokenizers.PostProcessor.info(post_processor)
+      %{"cls" => {"[CLS]", 101}, "post_processor_type" => "bert", "sep" => {"[SEP]", 102}}
+
+  """
+  @spec info(t()) :: map()
+  defdelegate info(post_processor), to: Tokenizers.Native, as: :post_processors_info
+
   @doc """
   Creates a Bert post-processor with the given tokens.
   """
@@ -83,6 +103,28 @@ defmodule Tokenizers.PostProcessor do
   """
   @spec sequence(post_processors :: [t()]) :: t()
   defdelegate sequence(post_processors), to: Tokenizers.Native, as: :post_processors_sequence
+
+  @doc """
+  Serializes the post-processor to JSON.
+
+  The JSON has the same format as the corresponding entry in a
+  `tokenizer.json` file, so it can be versioned and shared on its own.
+
+  ## Options
+
+    * `:pretty` - whether to pretty print the JSON. Defaults to `false`
+
+  """
+  @spec to_json(t(), keyword()) :: {:ok, String.t()} | {:error, term()}
+  defdelegate to_json(post_processor, opts \\ []),
+    to: Tokenizers.Native,
+    as: :post_processors_to_json
+
+  @doc """
+  Loads a post-processor from JSON, as returned by `to_json/2`.
+  """
+  @spec from_json(String.t()) :: {:ok, t()} | {:error, term()}
+  defdelegate from_json(json), to: Tokenizers.Native, as: :post_processors_from_json
 end
 
 defimpl Inspect, for: Tokenizers.PostProcessor do
@@ -20,11 +20,39 @@ defmodule Tokenizers.PreTokenizer do
 
   @type t() :: %__MODULE__{resource: reference()}
 
+  @doc """
+  Retrieves information about the pre-tokenizer.
+
+  The information includes `pre_tokenizer_type` and every configured
+  option of the given pre-tokenizer. For a Sequence pre-tokenizer, the
+  information of each child is listed under `pre_tokenizers`.
+
+  ## Example
+
+      iex> pre_tokenizer = Tokenizers.PreTokenizer.digits(individual_digits: true)
+      iex> Tokenizers.PreTokenizer.info(pre_tokenizer)
+      %{"individual_digits" => true, "pre_tokenizer_type" => "Digits"}
+
+  """
+  @spec info(t()) :: map()
+  defdelegate info(pre_tokenizer), to: Tokenizers.Native, as: :pre_tokenizers_info
+
   @doc """
   Converts a string into a sequence of pre-tokens.
+
+  ## Options
+
+    * `:offset_type` - the unit of the returned offsets, either `:bytes`
+      or `:chars`. Byte offsets can be used directly with `binary_part/3`.
+      Defaults to `:chars`
+
+    * `:offset_referential` - whether offsets refer to the `:original`
+      input or to the `:normalized` one. Defaults to `:original`
+
   """
-  @spec pre_tokenize(t(), String.t()) :: {:ok, [{String.t(), {integer(), integer()}}]}
-  defdelegate pre_tokenize(pre_tokenizer, input),
+  @spec pre_tokenize(t(), String.t(), keyword()) ::
+          {:ok, [{String.t(), {integer(), integer()}}]} | {:error, term()}
+  defdelegate pre_tokenize(pre_tokenizer, input, opts \\ []),
     to: Tokenizers.Native,
     as: :pre_tokenizers_pre_tokenize
 
@@ -166,8 +194,9 @@ defmodule Tokenizers.PreTokenizer do
 
   ## Example
 
-      iex> Tokenizers.PreTokenizer.split_regex(~S(\?\d{2}\?), :removed)
-      #Tokenizers.PreTokenizer<[pre_tokenizer_type: "Split"]>
+      iex> pre_tokenizer = Tokenizers.PreTokenizer.split_regex(~S(\?\d{2}\?), :removed)
+      iex> Tokenizers.PreTokenizer.info(pre_tokenizer)["pattern"]
+      {:regex, "\\?\\d{2}\\?"}
 
   """
   @spec split_regex(String.t(), split_delimiter_behaviour(), keyword()) :: t()
@@ -207,6 +236,38 @@ defmodule Tokenizers.PreTokenizer do
   defdelegate digits(opts \\ []),
     to: Tokenizers.Native,
     as: :pre_tokenizers_digits
+
+  @doc """
+  Creates a UnicodeScripts pre-tokenizer.
+
+  Splits the text wherever the Unicode script changes, for example
+  between Latin, Han and Cyrillic characters. Whitespace and punctuation
+  don't start a new split. This mimics the SentencePiece behavior.
+  """
+  @spec unicode_scripts() :: t()
+  defdelegate unicode_scripts(), to: Tokenizers.Native, as: :pre_tokenizers_unicode_scripts
+
+  @doc """
+  Serializes the pre-tokenizer to JSON.
+
+  The JSON has the same format as the corresponding entry in a
+  `tokenizer.json` file, so it can be versioned and shared on its own.
+
+  ## Options
+
+    * `:pretty` - whether to pretty print the JSON. Defaults to `false`
+
+  """
+  @spec to_json(t(), keyword()) :: {:ok, String.t()} | {:error, term()}
+  defdelegate to_json(pre_tokenizer, opts \\ []),
+    to: Tokenizers.Native,
+    as: :pre_tokenizers_to_json
+
+  @doc """
+  Loads a pre-tokenizer from JSON, as returned by `to_json/2`.
+  """
+  @spec from_json(String.t()) :: {:ok, t()} | {:error, term()}
+  defdelegate from_json(json), to: Tokenizers.Native, as: :pre_tokenizers_from_json
 end
 
 defimpl Inspect, for: Tokenizers.PreTokenizer do
@@ -31,9 +31,18 @@ defmodule Tokenizers.Tokenizer do
   @typedoc """
   An input being a subject to tokenization.
 
-  Can be either a single sequence, or a pair of sequences.
+  Can be either a single sequence, or a pair of sequences. Each sequence
+  is either a string or a list of words that were already split, in
+  which case each given word keeps its own word id (see
+  `Tokenizers.Encoding.get_word_ids/1`) and is then pre-tokenized, so
+  a word may still be split into several pieces sharing that id.
   """
-  @type encode_input :: String.t() | {String.t(), String.t()}
+  @type encode_input :: sequence() | {sequence(), sequence()}
+
+  @typedoc """
+  A single sequence, either a string or a list of pre-split words.
+  """
+  @type sequence :: String.t() | [String.t()]
 
   @doc """
   Loads a new tokenizer from a repository on Hugging Face Hub.
@@ -67,6 +76,10 @@ defmodule Tokenizers.Tokenizer do
       it uses `:filename.basedir/3` to get a cache dir based in the
       "tokenizers_elixir" application name
 
+    * `:load_config` - whether to also download `tokenizer_config.json`
+      and `special_tokens_map.json`, when the repository has them, and
+      apply them as described in `from_file/2`. Defaults to `false`
+
   """
   @spec from_pretrained(String.t(), Keyword.t()) :: {:ok, t()} | {:error, term()}
   @doc type: :loading
@@ -77,19 +90,48 @@ defmodule Tokenizers.Tokenizer do
         [
           :additional_special_tokens,
           revision: "main",
+          load_config: false,
           use_cache: true,
           cache_dir: :filename.basedir(:user_cache, "tokenizers_elixir"),
           http_client: {Tokenizers.HTTPClient, []}
         ]
       )
 
+    load_opts = Keyword.take(opts, [:additional_special_tokens])
+
+    with {:ok, path} <- download(identifier, "tokenizer.json", opts),
+         {:ok, config_opts} <- download_config(identifier, opts) do
+      from_file(path, load_opts ++ config_opts)
+    end
+  end
+
+  @config_files [
+    tokenizer_config: "tokenizer_config.json",
+    special_tokens_map: "special_tokens_map.json"
+  ]
+
+  defp download_config(identifier, opts) do
+    if opts[:load_config] do
+      Enum.reduce_while(@config_files, {:ok, []}, fn {key, filename}, {:ok, acc} ->
+        case download(identifier, filename, opts) do
+          {:ok, path} -> {:cont, {:ok, [{key, path} | acc]}}
+          {:error, :not_found} -> {:cont, {:ok, acc}}
+          {:error, _} = error -> {:halt, error}
+        end
+      end)
+    else
+      {:ok, []}
+    end
+  end
+
+  defp download(identifier, filename, opts) do
     {http_client, http_opts} = opts[:http_client]
 
     {:ok, app_version} = :application.get_key(:tokenizers, :vsn)
     app_version = List.to_string(app_version)
 
     headers = [{"user-agent", "tokenizers-elixir/#{app_version}"}]
-    url = "/#{identifier}/resolve/#{opts[:revision]}/tokenizer.json"
+    url = "/#{identifier}/resolve/#{opts[:revision]}/#{filename}"
 
     http_opts =
       http_opts
@@ -104,21 +146,19 @@ defmodule Tokenizers.Tokenizer do
       Path.join(cache_dir, entry_filename(url, etag))
     end
 
-    load_opts = Keyword.take(opts, [:additional_special_tokens])
-
     if opts[:use_cache] do
       with {:ok, response} <- request(http_client, Keyword.put(http_opts, :method, :head)) do
         etag = fetch_etag(response.headers)
         file_path = file_path_fun.(etag)
 
         if File.exists?(file_path) do
-          from_file(file_path, load_opts)
+          {:ok, file_path}
         else
           with {:ok, response} <- request(http_client, http_opts) do
             File.mkdir_p!(cache_dir)
             File.write!(file_path, response.body)
 
-            from_file(file_path, load_opts)
+            {:ok, file_path}
           end
         end
       end
@@ -130,7 +170,7 @@ defmodule Tokenizers.Tokenizer do
         File.mkdir_p!(cache_dir)
         File.write!(file_path, response.body)
 
-        from_file(file_path, load_opts)
+        {:ok, file_path}
       end
     end
   end
@@ -175,6 +215,38 @@ defmodule Tokenizers.Tokenizer do
 
   @doc """
   Instantiate a new tokenizer from the file at the given path.
+
+  ## Options
+
+    * `:padding` - set to `:none` to disable the padding configured in
+      the file
+
+    * `:truncation` - set to `:none` to disable the truncation
+      configured in the file
+
+    * `:tokenizer_config` - path to a `tokenizer_config.json` file, as
+      found in Hugging Face Hub repositories
+
+    * `:special_tokens_map` - path to a `special_tokens_map.json` file,
+      as found in Hugging Face Hub repositories
+
+  When given, the config files are applied as `transformers` does:
+
+    * the special tokens are added under their roles, such as
+      `"bos_token"` or `"pad_token"`, see `get_special_tokens/1`.
+      `tokenizer_config.json` takes precedence over
+      `special_tokens_map.json`
+
+    * `"pad_token"` and `"padding_side"` enable padding
+
+    * `"model_max_length"` and `"truncation_side"` enable truncation
+
+    * `"add_bos_token"` and `"add_eos_token"` add or remove the BOS
+      and EOS tokens. Each one only changes its side of the
+      post-processor, which is kept when it already matches them
+
+    * `"chat_template"` is used by `apply_chat_template/3`
+
   """
   @doc type: :loading
   @spec from_file(path :: String.t(), keyword()) :: {:ok, t()} | {:error, term()}
@@ -190,6 +262,8 @@ defmodule Tokenizers.Tokenizer do
 
   @doc """
   Instantiate a new tokenizer from the buffer.
+
+  See `from_file/2` for the available options.
   """
   @doc type: :loading
   @spec from_buffer(data :: String.t(), keyword()) :: {:ok, t()} | {:error, term()}
@@ -203,6 +277,110 @@ defmodule Tokenizers.Tokenizer do
     Tokenizers.Native.tokenizer_from_buffer(data, opts)
   end
 
+  @doc """
+  Instantiate a new tokenizer from a SentencePiece `.model` file.
+
+  The SentencePiece model is converted into an equivalent pipeline:
+
+    * a Unigram or BPE model, depending on the SentencePiece model type,
+      including the byte fallback setting
+
+    * a normalizer applying the precompiled normalization rules and
+      collapsing extra whitespace
+
+    * Metaspace pre-tokenizer and decoder, prepending a space according
+      to the `add_dummy_prefix` setting
+
+    * control pieces (such as `<s>` and `</s>`) and the unknown piece as
+      special tokens, and user defined pieces as added tokens
+
+  Only Unigram and BPE models are supported.
+
+  ## Options
+
+    * `:add_bos_token` - whether to add the BOS token at the beginning
+      of each sequence. Defaults to `true` for BPE models, matching the
+      Llama tokenizers in transformers, and to `false` for Unigram models
+
+    * `:add_eos_token` - whether to add the EOS token at the end of each
+      sequence, as done by T5 models. Defaults to `false`
+
+  """
+  @doc type: :loading
+  @spec from_sentencepiece_file(path :: String.t(), keyword()) :: {:ok, t()} | {:error, term()}
+  def from_sentencepiece_file(path, opts \\ []) do
+    opts = Keyword.validate!(opts, [:add_bos_token, :add_eos_token])
+    Tokenizers.Native.tokenizer_from_sentencepiece_file(path, opts)
+  end
+
+  @doc """
+  Instantiate a new tokenizer from the contents of a SentencePiece
+  `.model` file.
+
+  See `from_sentencepiece_file/2` for more details and options.
+  """
+  @doc type: :loading
+  @spec from_sentencepiece_buffer(data :: binary(), keyword()) :: {:ok, t()} | {:error, term()}
+  def from_sentencepiece_buffer(data, opts \\ []) do
+    opts = Keyword.validate!(opts, [:add_bos_token, :add_eos_token])
+    Tokenizers.Native.tokenizer_from_sentencepiece_buffer(data, opts)
+  end
+
+  @tiktoken_encodings [:r50k_base, :p50k_base, :cl100k_base, :o200k_base]
+
+  @doc """
+  Instantiate a new tokenizer from a tiktoken `.tiktoken` rank file.
+
+  Each line of the file holds a base64 encoded token and its rank. The
+  ranks are converted into a byte-level BPE model whose ids match the
+  ones returned by tiktoken.
+
+  `pattern` is the regular expression used to split the text before
+  applying BPE. It can be the name of one of the tiktoken encodings,
+  `:r50k_base`, `:p50k_base`, `:cl100k_base` or `:o200k_base`, or a
+  custom `{:regex, pattern}`.
+
+  ## Options
+
+    * `:special_tokens` - a list of `{token, id}` special tokens. Special
+      tokens are not part of the rank file, for `cl100k_base` these are
+      `[{"<|endoftext|>", 100257}, {"<|fim_prefix|>", 100258}, ...]`.
+      Their ids must not be taken by any rank. Defaults to `[]`
+
+  """
+  @doc type: :loading
+  @spec from_tiktoken_file(path :: String.t(), atom() | {:regex, String.t()}, keyword()) ::
+          {:ok, t()} | {:error, term()}
+  def from_tiktoken_file(path, pattern, opts \\ []) do
+    opts = Keyword.validate!(opts, special_tokens: [])
+    Tokenizers.Native.tokenizer_from_tiktoken_file(path, tiktoken_pattern(pattern), opts)
+  end
+
+  @doc """
+  Instantiate a new tokenizer from the contents of a tiktoken rank file.
+
+  See `from_tiktoken_file/3` for more details and options.
+  """
+  @doc type: :loading
+  @spec from_tiktoken_buffer(data :: binary(), atom() | {:regex, String.t()}, keyword()) ::
+          {:ok, t()} | {:error, term()}
+  def from_tiktoken_buffer(data, pattern, opts \\ []) do
+    opts = Keyword.validate!(opts, special_tokens: [])
+    Tokenizers.Native.tokenizer_from_tiktoken_buffer(data, tiktoken_pattern(pattern), opts)
+  end
+
+  defp tiktoken_pattern(name) when name in @tiktoken_encodings do
+    {:named, Atom.to_string(name)}
+  end
+
+  defp tiktoken_pattern({:regex, pattern}) when is_binary(pattern), do: {:regex, pattern}
+
+  defp tiktoken_pattern(other) do
+    raise ArgumentError,
+          "expected pattern to be one of #{inspect(@tiktoken_encodings)} " <>
+            "or {:regex, pattern}, got: #{inspect(other)}"
+  end
+
   @doc """
   Save the tokenizer to the provided path.
 
@@ -215,6 +393,22 @@ defmodule Tokenizers.Tokenizer do
   @spec save(t(), keyword()) :: {:ok, String.t()} | {:error, term()}
   defdelegate save(tokenizer, path, opts \\ []), to: Tokenizers.Native, as: :tokenizer_save
 
+  @doc """
+  Serializes the tokenizer to a JSON binary.
+
+  This is the in-memory counterpart of `save/3`, the result has the
+  same contents as a `tokenizer.json` file and can be loaded back with
+  `from_buffer/2`.
+
+  ## Options
+
+    * `:pretty` - whether to pretty print the JSON. Defaults to `false`
+
+  """
+  @doc type: :loading
+  @spec to_buffer(t(), keyword()) :: {:ok, String.t()} | {:error, term()}
+  defdelegate to_buffer(tokenizer, opts \\ []), to: Tokenizers.Native, as: :tokenizer_to_buffer
+
   @doc """
   Instantiate a new tokenizer from an existing model.
   """
@@ -302,6 +496,26 @@ defmodule Tokenizers.Tokenizer do
   @spec set_decoder(t(), Decoder.t()) :: t()
   defdelegate set_decoder(tokenizer, decoder), to: Tokenizers.Native, as: :tokenizer_set_decoder
 
+  @doc """
+  Get the tokenizer's special tokens by role.
+
+  Roles, such as `"bos_token"`, `"eos_token"` or `"pad_token"`, are
+  loaded from the `:tokenizer_config` and `:special_tokens_map` files
+  given to `from_file/2`.
+
+  ## Examples
+
+      iex> {:ok, tokenizer} = Tokenizers.Tokenizer.from_file("test/fixtures/bert-base-cased.json")
+      iex> Tokenizers.Tokenizer.get_special_tokens(tokenizer)
+      %{}
+
+  """
+  @spec get_special_tokens(t()) :: %{String.t() => String.t()}
+  @doc type: :configuration
+  defdelegate get_special_tokens(tokenizer),
+    to: Tokenizers.Native,
+    as: :tokenizer_get_special_tokens
+
   @doc """
   Get the tokenizer's vocabulary as a map of token to id.
 
@@ -376,6 +590,9 @@ defmodule Tokenizers.Tokenizer do
     * `:direction` (default: `:right`) - the direction to use when
       overflowing the model's input
 
+  Raises `Tokenizers.Error` if `:max_length` can't fit the special
+  tokens added by the post-processor together with `:stride`.
+
   """
   @doc type: :configuration
   @spec set_truncation(t(), opts) :: t()
@@ -456,6 +673,19 @@ defmodule Tokenizers.Tokenizer do
       to apply to the encoding. Check `Tokenizers.Encoding.transform/2`
       for more information. Defaults to `[]`
 
+    * `:offset_type` - the unit of the encoding offsets, either `:bytes`
+      or `:chars`. Byte offsets can be used directly with `binary_part/3`.
+      This also determines the unit of positions taken and returned by
+      `Tokenizers.Encoding.char_to_token/3`, `Tokenizers.Encoding.token_to_chars/2`
+      and related functions. Defaults to `:bytes`
+
+    * `:offset_referential` - whether offsets refer to the `:original`
+      input or to the `:normalized` one. The normalized input is the
+      concatenation of the normalized pre-tokens, as returned by
+      `Tokenizers.PreTokenizer.pre_tokenize/3`, so any text removed by
+      the pre-tokenizer (such as whitespace) is not part of it. Defaults
+      to `:original`
+
   """
   @doc type: :inference
   @spec encode(t(), encode_input(), keyword()) :: {:ok, Encoding.t()} | {:error, term()}
@@ -472,6 +702,231 @@ defmodule Tokenizers.Tokenizer do
     to: Tokenizers.Native,
     as: :tokenizer_encode_batch
 
+  @typedoc """
+  Batch of encodings exported as binaries.
+
+  Each field is a single binary holding the values of all encodings in
+  the batch, row after row, as native-endian unsigned 32-bit integers
+  unless configured otherwise. `:shape` is `{batch_size, sequence_length}`,
+  offsets hold a `{start, end}` pair per token, so their shape is
+  `{batch_size, sequence_length, 2}`.
+  """
+  @type batch_binaries :: %{
+          ids: binary(),
+          type_ids: binary(),
+          attention_mask: binary(),
+          special_tokens_mask: binary(),
+          offsets: binary(),
+          shape: {non_neg_integer(), non_neg_integer()}
+        }
+
+  @doc """
+  Encodes a batch of inputs and returns each field as one contiguous
+  binary.
+
+  This is equivalent to calling `encode_batch/3` and then concatenating
+  `Tokenizers.Encoding.get_u32_ids/1` and friends for all encodings,
+  but without building intermediate encodings and per-row binaries.
+  The binaries can be wrapped into tensors directly:
+
+      {:ok, batch} = Tokenizers.Tokenizer.encode_batch_binaries(tokenizer, inputs)
+      input_ids = batch.ids |> Nx.from_binary(:u32) |> Nx.reshape(batch.shape)
+
+  All encodings must have the same length, so the tokenizer is
+  expected to have padding configured (see `set_padding/2`), or a pad
+  transformation should be given.
+
+  ## Options
+
+  Accepts the same options as `encode/3`, plus:
+
+    * `:types` - a keyword list with the integer type of each field,
+      for example `[ids: {:s, 64}, attention_mask: {:u, 8}]`. See
+      `t:Tokenizers.Encoding.binary_type/0` for the supported types.
+      Fields default to `{:u, 32}`. Returns an error if any value
+      doesn't fit in the requested type
+
+    * `:endianness` - the byte order of all fields, either `:native`,
+      `:little` or `:big`. Defaults to `:native`
+
+  """
+  @doc type: :inference
+  @spec encode_batch_binaries(t(), [encode_input()], keyword()) ::
+          {:ok, batch_binaries()} | {:error, term()}
+  def encode_batch_binaries(tokenizer, input, opts \\ []) do
+    {binary_opts, opts} = Keyword.split(opts, [:types, :endianness])
+    Tokenizers.Native.tokenizer_encode_batch_binaries(tokenizer, input, opts, binary_opts)
+  end
+
+  @doc """
+  Encodes a long document into overlapping windows of at most
+  `max_length` tokens.
+
+  Unlike truncation (see `set_truncation/2`), this doesn't depend on
+  the tokenizer settings and keeps the whole document. Consecutive
+  windows share `:stride` tokens, and special tokens are added to each
+  window by the post-processor, counting towards `max_length`. The
+  tokenizer's truncation and padding are not applied.
+
+  Returns `{index, encoding}` pairs, one per window in order. Encoding
+  offsets refer to the whole document.
+
+  ## Options
+
+    * `:stride` - the number of tokens shared by consecutive windows.
+      Must be less than the number of tokens per window, excluding
+      special tokens. Defaults to `0`
+
+    * `:add_special_tokens` - whether to add special tokens to each
+      window. Defaults to `true`
+
+    * `:offset_type` - the unit of the encoding offsets, either `:bytes`
+      or `:chars`. Defaults to `:bytes`
+
+  ## Examples
+
+      iex> {:ok, tokenizer} = Tokenizers.Tokenizer.from_file("test/fixtures/bert-base-cased.json")
+      iex> text = "This is a test of windows"
+      iex> {:ok, windows} = Tokenizers.Tokenizer.encode_windows(tokenizer, text, 5, stride: 1)
+      iex> for {index, encoding} <- windows, do: {index, Tokenizers.Encoding.get_tokens(encoding)}
+      [
+        {0, ["[CLS]", "This", "is", "a", "[SEP]"]},
+        {1, ["[CLS]", "a", "test", "of", "[SEP]"]},
+        {2, ["[CLS]", "of", "windows", "[SEP]"]}
+      ]
+
+  """
+  @doc type: :inference
+  @spec encode_windows(t(), String.t(), pos_integer(), keyword()) ::
+          {:ok, [{non_neg_integer(), Encoding.t()}]} | {:error, term()}
+  defdelegate encode_windows(tokenizer, input, max_length, opts \\ []),
+    to: Tokenizers.Native,
+    as: :tokenizer_encode_windows
+
+  @doc """
+  Batched version of `encode_windows/4`.
+
+  Returns the windows of each document.
+  """
+  @doc type: :inference
+  @spec encode_windows_batch(t(), [String.t()], pos_integer(), keyword()) ::
+          {:ok, [[{non_neg_integer(), Encoding.t()}]]} | {:error, term()}
+  defdelegate encode_windows_batch(tokenizer, inputs, max_length, opts \\ []),
+    to: Tokenizers.Native,
+    as: :tokenizer_encode_windows_batch
+
+  @doc """
+  Counts the tokens `input` is encoded into.
+
+  This is faster than `encode/3` followed by `Tokenizers.Encoding.get_length/1`,
+  since no encoding is built. As with `encode/3`, the count is capped
+  by the tokenizer's truncation `:max_length`, while padding is not
+  counted.
+
+  ## Options
+
+    * `:add_special_tokens` - whether to count the special tokens added
+      by the post-processor. Defaults to `true`
+
+    * `:truncate` - whether to cap the count by the tokenizer's
+      truncation. Set it to `false` to count the full length of the
+      input. Defaults to `true`
+
+  ## Examples
+
+      iex> {:ok, tokenizer} = Tokenizers.Tokenizer.from_file("test/fixtures/bert-base-cased.json")
+      iex> Tokenizers.Tokenizer.count_tokens(tokenizer, "This is a test")
+      {:ok, 6}
+      iex> text = "This is a test"
+      iex> Tokenizers.Tokenizer.count_tokens(tokenizer, text, add_special_tokens: false)
+      {:ok, 4}
+
+  """
+  @doc type: :inference
+  @spec count_tokens(t(), String.t(), keyword()) :: {:ok, non_neg_integer()} | {:error, term()}
+  defdelegate count_tokens(tokenizer, input, opts \\ []),
+    to: Tokenizers.Native,
+    as: :tokenizer_count_tokens
+
+  @doc """
+  Batched version of `count_tokens/3`.
+  """
+  @doc type: :inference
+  @spec count_tokens_batch(t(), [String.t()], keyword()) ::
+          {:ok, [non_neg_integer()]} | {:error, term()}
+  defdelegate count_tokens_batch(tokenizer, inputs, opts \\ []),
+    to: Tokenizers.Native,
+    as: :tokenizer_count_tokens_batch
+
+  @typedoc """
+  A chat message, such as `%{"role" => "user", "content" => "Hello!"}`.
+
+  Keys may also be atoms. Besides `"role"` and `"content"`, messages
+  may hold any other fields used by the chat template.
+  """
+  @type chat_message :: %{optional(String.t() | atom()) => term()}
+
+  @doc """
+  Formats a conversation into a prompt using the tokenizer's chat
+  template.
+
+  Chat templates are Jinja templates shipped by instruction-tuned models
+  in `tokenizer_config.json`, which is loaded with the `:tokenizer_config`
+  option of `from_file/2`. The template receives the `messages`, the
+  `add_generation_prompt` flag and the special tokens by role, such as
+  `bos_token` and `eos_token`.
+
+  Templates are rendered natively, supporting the subset of Jinja used
+  by the templates published on Hugging Face Hub.
+
+  ## Options
+
+    * `:add_generation_prompt` - whether to end the prompt with the
+      tokens starting an assistant message. Defaults to `false`
+
+    * `:chat_template` - a Jinja template to use instead of the one
+      loaded with the tokenizer
+
+  ## Examples
+
+      iex> {:ok, tokenizer} = Tokenizers.Tokenizer.from_file("test/fixtures/bert-base-cased.json")
+      iex> template = "{% for m in messages %}<|{{ m.role }}|>{{ m.content }}\\n{% endfor %}"
+      iex> messages = [%{role: "user", content: "Hi!"}]
+      iex> Tokenizers.Tokenizer.apply_chat_template(tokenizer, messages, chat_template: template)
+      {:ok, "<|user|>Hi!\\n"}
+
+  """
+  @doc type: :inference
+  @spec apply_chat_template(t(), [chat_message()], keyword()) ::
+          {:ok, String.t()} | {:error, term()}
+  def apply_chat_template(tokenizer, messages, opts \\ []) do
+    opts = Keyword.validate!(opts, [:chat_template, add_generation_prompt: false])
+    opts = Enum.reject(opts, fn {_key, value} -> is_nil(value) end)
+    Tokenizers.Native.tokenizer_apply_chat_template(tokenizer, messages, opts)
+  end
+
+  @doc """
+  Formats a conversation with `apply_chat_template/3` and encodes the
+  resulting prompt.
+
+  Chat templates already include the special tokens, so they are not
+  added again unless `:add_special_tokens` is set to `true`.
+
+  ## Options
+
+  Accepts the options of `apply_chat_template/3` and `encode/3`.
+  """
+  @doc type: :inference
+  @spec encode_chat(t(), [chat_message()], keyword()) ::
+          {:ok, Encoding.t()} | {:error, term()}
+  def encode_chat(tokenizer, messages, opts \\ []) do
+    {template_opts, opts} = Keyword.split(opts, [:chat_template, :add_generation_prompt])
+
+    with {:ok, prompt} <- apply_chat_template(tokenizer, messages, template_opts) do
+      encode(tokenizer, prompt, Keyword.put_new(opts, :add_special_tokens, false))
+    end
+  end
+
   @doc """
   Decodes the given list of ids back to a string.
 
@@ -497,6 +952,41 @@ defmodule Tokenizers.Tokenizer do
     to: Tokenizers.Native,
     as: :tokenizer_decode_batch
 
+  @doc """
+  Decodes the given list of ids, returning the text along with the span
+  each id contributed to it.
+
+  Spans are `{start, end}` pairs, one per id, following the decoder,
+  so joined subwords, stripped spaces and merged bytes are accounted
+  for. Ids that add no text, such as skipped special tokens, get an
+  empty span. When a character is split across several byte tokens,
+  it is attributed to the token completing it.
+
+  ## Options
+
+    * `:skip_special_tokens` - whether to exclude special tokens from
+      the decoded string. Defaults to `true`
+
+    * `:offset_type` - the unit of the spans, either `:bytes` or `:chars`.
+      Byte spans can be used directly with `binary_part/3`. Defaults to
+      `:bytes`
+
+  ## Examples
+
+      iex> {:ok, tokenizer} = Tokenizers.Tokenizer.from_file("test/fixtures/bert-base-cased.json")
+      iex> {:ok, encoding} = Tokenizers.Tokenizer.encode(tokenizer, "Hello world!")
+      iex> ids = Tokenizers.Encoding.get_ids(encoding)
+      iex> Tokenizers.Tokenizer.decode_with_spans(tokenizer, ids)
+      {:ok, {"Hello world!", [{0, 0}, {0, 5}, {5, 11}, {11, 12}, {12, 12}]}}
+
+  """
+  @doc type: :inference
+  @spec decode_with_spans(t(), [non_neg_integer()], keyword()) ::
+          {:ok, {String.t(), [{non_neg_integer(), non_neg_integer()}]}} | {:error, term()}
+  defdelegate decode_with_spans(tokenizer, ids, opts \\ []),
+    to: Tokenizers.Native,
+    as: :tokenizer_decode_with_spans
+
   @doc """
   Convert a given id to its token.
   """
@@ -523,12 +1013,101 @@ defmodule Tokenizers.Tokenizer do
     * `:trainer` - the trainer to use. Defaults to the default trainer
       corresponding to `tokenizers`'s model
 
+    * `:progress` - a pid to receive training progress messages. See
+      "Progress messages" below. Defaults to `nil`
+
+  ## Progress messages
+
+  When `:progress` is given, the process receives messages of the form
+  `{:training_progress, event}`, where `event` is a map with the
+  following keys:
+
+    * `:phase` - one of `:pre_processing`, `:training` or `:done`.
+      `:pre_processing` is sent periodically while the input is being
+      read, and once more when it has been fully consumed. `:training`
+      is sent once, when the model starts training
+
+    * `:sequences` - the number of sequences (lines) read so far
+
+    * `:words` - the number of words produced by pre-tokenization so far
+
+  The `:done` event also has the following keys:
+
+    * `:vocab_size` - the vocabulary size of the trained model
+
+    * `:merges` - the number of merges learned by a BPE model. For a
+      WordPiece model, which is trained as a BPE model before dropping
+      its merges, the number of tokens added by those merges. Not set
+      for other models
+
+  Note that no progress is reported while the model itself trains, such
+  as the BPE merges, since the underlying trainers don't expose their
+  training loop. The `:training` and `:done` events mark its start and
+  end, so the merges and vocabulary size are only known once it is done.
+
   """
   @doc type: :training
   @spec train_from_files(t(), [String.t()], keyword()) :: {:ok, t()} | {:error, term()}
   def train_from_files(tokenizer, paths, opts \\ []) do
-    opts = Keyword.validate!(opts, trainer: nil)
-    Tokenizers.Native.tokenizer_train_from_files(tokenizer, paths, opts[:trainer])
+    opts = Keyword.validate!(opts, trainer: nil, progress: nil)
+
+    Tokenizers.Native.tokenizer_train_from_files(
+      tokenizer,
+      paths,
+      opts[:trainer],
+      opts[:progress]
+    )
+  end
+
+  @doc """
+  Train the tokenizer on the given sequences.
+
+  Accepts any enumerable of strings, such as a list or a stream, so
+  the training data doesn't have to be written to files first. The
+  enumerable is fed to the trainer in chunks while it is being read,
+  so a lazy stream is never loaded in memory at once.
+
+  ## Options
+
+    * `:trainer` - the trainer to use. Defaults to the default trainer
+      corresponding to `tokenizers`'s model
+
+    * `:progress` - a pid to receive training progress messages. See
+      `train_from_files/3` for the message format. Defaults to `nil`
+
+    * `:chunk_size` - the number of sequences sent to the trainer at
+      once. Defaults to `1000`
+
+  """
+  @doc type: :training
+  @spec train_from_iterator(t(), Enumerable.t(), keyword()) :: {:ok, t()} | {:error, term()}
+  def train_from_iterator(tokenizer, sequences, opts \\ []) do
+    opts = Keyword.validate!(opts, trainer: nil, progress: nil, chunk_size: 1000)
+
+    training =
+      Tokenizers.Native.tokenizer_train_from_iterator_start(
+        tokenizer,
+        opts[:trainer],
+        opts[:progress]
+      )
+
+    try do
+      sequences
+      |> Stream.chunk_every(opts[:chunk_size])
+      |> Enum.reduce_while(:ok, fn chunk, :ok ->
+        case Tokenizers.Native.tokenizer_train_from_iterator_feed(training, chunk) do
+          :ok -> {:cont, :ok}
+          # Training stopped reading input, its result tells why
+          :closed -> {:halt, :ok}
+        end
+      end)
+    catch
+      kind, reason ->
+        Tokenizers.Native.tokenizer_train_from_iterator_cancel(training)
+        :erlang.raise(kind, reason, __STACKTRACE__)
+    end
+
+    Tokenizers.Native.tokenizer_train_from_iterator(training)
   end
 end
 
@@ -0,0 +1,171 @@
+defmodule Tokenizers.TrainingJob do
+  @moduledoc """
+  Training running in the background.
+
+  Training a tokenizer on a large corpus can take minutes. Instead of
+  blocking the caller with `Tokenizers.Tokenizer.train_from_files/3`,
+  you can start a job with `start_from_files/3` or `start_from_iterator/3`,
+  which trains on a separate OS thread and returns immediately.
+
+  The job can then be polled with `status/1`, awaited with `await/2`
+  or cancelled with `cancel/1`.
+
+  When the job finishes, the process that started it receives a
+  `{:training_job_done, job, status}` message, where `status` is the
+  same as returned by `status/1`. As it waits for that message, only
+  the process that started the job can call `await/2`, while any
+  process can use `status/1` and `cancel/1`.
+
+  Jobs started with `start_from_iterator/3` are cancelled when the
+  process that started them exits before all sequences were consumed.
+  """
+
+  defstruct [:resource]
+
+  alias Tokenizers.Tokenizer
+
+  @type t() :: %__MODULE__{resource: reference()}
+
+  @chunk_size 1000
+
+  @typedoc """
+  The status of a training job.
+  """
+  @type status() :: :running | {:ok, Tokenizer.t()} | {:error, term()}
+
+  @doc """
+  Starts training `tokenizer` on the given files in the background.
+
+  ## Options
+
+    * `:trainer` - the trainer to use. Defaults to the default trainer
+      corresponding to `tokenizers`'s model
+
+    * `:progress` - a pid to receive training progress messages. See
+      `Tokenizers.Tokenizer.train_from_files/3` for the message format.
+      Defaults to `nil`
+
+  """
+  @spec start_from_files(Tokenizer.t(), [String.t()], keyword()) :: t()
+  def start_from_files(tokenizer, paths, opts \\ []) do
+    opts = Keyword.validate!(opts, trainer: nil, progress: nil)
+
+    Tokenizers.Native.training_job_start_from_files(
+      tokenizer,
+      paths,
+      opts[:trainer],
+      opts[:progress]
+    )
+  end
+
+  @doc """
+  Starts training `tokenizer` on the given sequences in the background.
+
+  The enumerable is consumed by a separate process in chunks of
+  `:chunk_size` sequences, which are handed over to the training thread
+  as it reads them, so only a few chunks are held in memory at a time
+  and the caller is not blocked. If the enumerable raises, or the caller
+  exits before it is fully consumed, enumeration stops and the job is
+  cancelled.
+
+  ## Options
+
+  Accepts the same options as `start_from_files/3`, plus:
+
+    * `:chunk_size` - the number of sequences sent to the trainer at
+      once. Defaults to `#{@chunk_size}`
+
+  """
+  @spec start_from_iterator(Tokenizer.t(), Enumerable.t(), keyword()) :: t()
+  def start_from_iterator(tokenizer, sequences, opts \\ []) do
+    opts = Keyword.validate!(opts, trainer: nil, progress: nil, chunk_size: @chunk_size)
+
+    job =
+      Tokenizers.Native.training_job_start_from_iterator(
+        tokenizer,
+        opts[:trainer],
+        opts[:progress]
+      )
+
+    owner = self()
+    {:ok, _pid} = Task.start(fn -> watch_feed(job, owner, sequences, opts[:chunk_size]) end)
+    job
+  end
+
+  # Feeds the job from a separate process while monitoring the owner,
+  # so the input stops being consumed and the job is cancelled when the
+  # owner exits before all sequences were sent.
+  defp watch_feed(job, owner, sequences, chunk_size) do
+    owner_ref = Process.monitor(owner)
+    {feeder, feeder_ref} = spawn_monitor(fn -> feed(job, sequences, chunk_size) end)
+
+    receive do
+      {:DOWN, ^owner_ref, :process, _, _} ->
+        Process.exit(feeder, :kill)
+        cancel(job)
+
+      {:DOWN, ^feeder_ref, :process, _, _} ->
+        Process.demonitor(owner_ref, [:flush])
+        :ok
+    end
+  end
+
+  # Sends the sequences to the job in chunks and closes its input. If
+  # the enumerable raises, the job is cancelled and the error reraised.
+  defp feed(job, sequences, chunk_size) do
+    sequences
+    |> Stream.chunk_every(chunk_size)
+    |> Enum.reduce_while(:ok, fn chunk, :ok ->
+      case Tokenizers.Native.training_job_feed(job, chunk) do
+        :ok -> {:cont, :ok}
+        # The job stopped reading input, its status tells why
+        :closed -> {:halt, :ok}
+      end
+    end)
+  catch
+    kind, reason ->
+      cancel(job)
+      :erlang.raise(kind, reason, __STACKTRACE__)
+  after
+    Tokenizers.Native.training_job_close_input(job)
+  end
+
+  @doc """
+  Returns the current status of `job`.
+
+  Returns `:running` while training, `{:ok, tokenizer}` once it is
+  done, `{:error, :cancelled}` if the job was cancelled and
+  `{:error, reason}` if training failed.
+  """
+  @spec status(t()) :: status()
+  defdelegate status(job), to: Tokenizers.Native, as: :training_job_status
+
+  @doc """
+  Waits for `job` to finish and returns its final status.
+
+  This must be called from the process that started the job, as it
+  waits for the completion message sent to that process. Calling it
+  from any other process waits until `timeout`, so other processes
+  should poll `status/1` instead. Returns `{:error, :timeout}` if the
+  job doesn't finish within `timeout` milliseconds.
+  """
+  @spec await(t(), timeout()) :: {:ok, Tokenizer.t()} | {:error, term()}
+  def await(%__MODULE__{} = job, timeout \\ :infinity) do
+    receive do
+      {:training_job_done, ^job, status} -> status
+    after
+      timeout -> {:error, :timeout}
+    end
+  end
+
+  @doc """
+  Requests `job` to be cancelled.
+
+  Cancellation is observed while the input is being read and right
+  before the model starts training. Once the model training itself has
+  started, it runs to completion and its result is discarded. Either
+  way, the job finishes with `{:error, :cancelled}`.
+  """
+  @spec cancel(t()) :: :ok
+  defdelegate cancel(job), to: Tokenizers.Native, as: :training_job_cancel
+end
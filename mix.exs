@@ -47,7 +47,8 @@ defmodule Tokenizers.MixProject do
           Tokenizers.Tokenizer,
           Tokenizers.Encoding,
           Tokenizers.Encoding.Transformation,
-          Tokenizers.Decoder
+          Tokenizers.Decoder,
+          Tokenizers.DecodeStream
         ],
         Pipeline: [
           Tokenizers.Normalizer,
@@ -61,9 +62,11 @@ defmodule Tokenizers.MixProject do
           Tokenizers.Model.WordLevel,
           Tokenizers.Model.WordPiece,
           Tokenizers.Trainer,
+          Tokenizers.TrainingJob,
           Tokenizers.AddedToken
         ],
         Other: [
+          Tokenizers.Error,
           Tokenizers.HTTPClient
         ]
       ],
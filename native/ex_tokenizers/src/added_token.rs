@@ -1,4 +1,5 @@
-use crate::{new_info, util::Info};
+use crate::util::{from_json, to_json, Info, JsonOption};
+use crate::{new_info, ExTokenizersError};
 use rustler::{NifTaggedEnum, NifUntaggedEnum};
 use serde::{Deserialize, Serialize};
 use tokenizers::AddedToken;
@@ -144,3 +145,20 @@ fn added_token_new(token: String, options: Vec<AddedTokenOption>) -> ExTokenizer
 
     ExTokenizersAddedToken::new(token)
 }
+
+///////////////////////////////////////////////////////////////////////////////
+/// Serialization
+///////////////////////////////////////////////////////////////////////////////
+
+#[rustler::nif]
+pub fn added_token_to_json(
+    added_token: ExTokenizersAddedToken,
+    options: Vec<JsonOption>,
+) -> Result<String, ExTokenizersError> {
+    to_json(&added_token, options)
+}
+
+#[rustler::nif]
+pub fn added_token_from_json(json: &str) -> Result<ExTokenizersAddedToken, ExTokenizersError> {
+    from_json(json)
+}
@@ -0,0 +1,2168 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+use rustler::types::map::MapIterator;
+use rustler::types::tuple::get_tuple;
+use rustler::{NifResult, NifTaggedEnum, Term, TermType};
+
+use crate::error::ExTokenizersError;
+use crate::tokenizer::ExTokenizersTokenizer;
+
+// Chat templates are Jinja templates rendered by transformers in a
+// sandboxed environment. This module implements the subset of Jinja used
+// by the templates published on the Hub: output, `if`, `for`, `set` and
+// `macro` tags, the usual operators, filters and tests, Python string and
+// dict methods, and the `raise_exception` and `namespace` functions.
+
+type Result<T> = std::result::Result<T, ExTokenizersError>;
+
+fn error(message: impl Into<String>) -> ExTokenizersError {
+    ExTokenizersError::InvalidTemplate(message.into())
+}
+
+// Templates come from the Hub, so parsing and rendering them must not
+// exhaust the stack or the memory, whatever they contain
+const MAX_DEPTH: usize = 500;
+const MAX_LENGTH: usize = 1 << 28;
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+fn too_deep() -> ExTokenizersError {
+    error(format!(
+        "template nested too deeply, the sandbox allows at most {MAX_DEPTH} levels"
+    ))
+}
+
+fn too_long() -> ExTokenizersError {
+    error(format!(
+        "string too long, the sandbox allows at most {MAX_LENGTH} bytes"
+    ))
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Values
+// /////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    Undefined,
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<Value>),
+    Map(IndexMap<String, Value>),
+    Namespace(Rc<RefCell<IndexMap<String, Value>>>),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Undefined => "undefined",
+            Value::None => "none",
+            Value::Bool(_) => "boolean",
+            Value::Int(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Str(_) => "string",
+            Value::List(_) => "list",
+            Value::Map(_) => "mapping",
+            Value::Namespace(_) => "namespace",
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Undefined | Value::None => false,
+            Value::Bool(value) => *value,
+            Value::Int(value) => *value != 0,
+            Value::Float(value) => *value != 0.0,
+            Value::Str(value) => !value.is_empty(),
+            Value::List(value) => !value.is_empty(),
+            Value::Map(value) => !value.is_empty(),
+            Value::Namespace(_) => true,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(value) => Some(*value as f64),
+            Value::Float(value) => Some(*value),
+            Value::Bool(value) => Some(f64::from(u8::from(*value))),
+            _ => None,
+        }
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Int(value) => Some(*value),
+            Value::Bool(value) => Some(i64::from(*value)),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Result<&str> {
+        match self {
+            Value::Str(value) => Ok(value),
+            other => Err(error(format!(
+                "expected a string, got {}",
+                other.type_name()
+            ))),
+        }
+    }
+
+    /// Iterates the value the way a Jinja `for` loop does.
+    fn iterate(&self) -> Result<Vec<Value>> {
+        match self {
+            Value::Undefined => Ok(vec![]),
+            Value::List(items) => Ok(items.clone()),
+            Value::Map(map) => Ok(map.keys().cloned().map(Value::Str).collect()),
+            Value::Str(value) => Ok(value.chars().map(|c| Value::Str(c.to_string())).collect()),
+            other => Err(error(format!("{} is not iterable", other.type_name()))),
+        }
+    }
+
+    fn length(&self) -> Result<usize> {
+        match self {
+            Value::Str(value) => Ok(value.chars().count()),
+            Value::List(items) => Ok(items.len()),
+            Value::Map(map) => Ok(map.len()),
+            Value::Undefined => Ok(0),
+            other => Err(error(format!("{} has no length", other.type_name()))),
+        }
+    }
+
+    /// Returns how deeply lists, dicts and namespaces nest in the value.
+    fn depth(&self) -> usize {
+        let depth = |values: &mut dyn Iterator<Item = &Value>| {
+            1 + values.map(Value::depth).max().unwrap_or(0)
+        };
+        match self {
+            Value::List(items) => depth(&mut items.iter()),
+            Value::Map(map) => depth(&mut map.values()),
+            Value::Namespace(namespace) => depth(&mut namespace.borrow().values()),
+            _ => 0,
+        }
+    }
+
+    /// Fails if the value nests deeper than the sandbox allows, which keeps
+    /// formatting and dropping values from recursing without bound.
+    fn checked(self) -> Result<Value> {
+        if self.depth() > MAX_DEPTH {
+            return Err(error(format!(
+                "value nested too deeply, the sandbox allows at most {MAX_DEPTH} levels"
+            )));
+        }
+        Ok(self)
+    }
+
+    fn has_namespace(&self) -> bool {
+        match self {
+            Value::List(items) => items.iter().any(Value::has_namespace),
+            Value::Map(map) => map.values().any(Value::has_namespace),
+            Value::Namespace(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Formats the value as Jinja prints it, which follows Python's `str`.
+    fn render(&self) -> String {
+        match self {
+            Value::Undefined => String::new(),
+            Value::Str(value) => value.clone(),
+            other => other.repr(),
+        }
+    }
+
+    /// Formats the value as Python's `repr`.
+    fn repr(&self) -> String {
+        match self {
+            Value::Undefined => String::new(),
+            Value::None => "None".to_string(),
+            Value::Bool(true) => "True".to_string(),
+            Value::Bool(false) => "False".to_string(),
+            Value::Int(value) => value.to_string(),
+            Value::Float(value) if value.is_finite() && value.fract() == 0.0 => {
+                format!("{value:.1}")
+            }
+            Value::Float(value) => value.to_string(),
+            Value::Str(value) => format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'")),
+            Value::List(items) => {
+                let items = items.iter().map(Value::repr).collect::<Vec<_>>();
+                format!("[{}]", items.join(", "))
+            }
+            Value::Map(map) => {
+                let entries = map
+                    .iter()
+                    .map(|(key, value)| format!("'{}': {}", key, value.repr()))
+                    .collect::<Vec<_>>();
+                format!("{{{}}}", entries.join(", "))
+            }
+            Value::Namespace(_) => "<Namespace>".to_string(),
+        }
+    }
+
+    /// Serializes the value as Python's `json.dumps` does.
+    fn to_json(&self, indent: Option<usize>, depth: usize) -> String {
+        let (separator, padding, closing) = match indent {
+            Some(indent) => (
+                ",".to_string() + "\n" + &" ".repeat(indent * (depth + 1)),
+                "\n".to_string() + &" ".repeat(indent * (depth + 1)),
+                "\n".to_string() + &" ".repeat(indent * depth),
+            ),
+            None => (", ".to_string(), String::new(), String::new()),
+        };
+        match self {
+            Value::Undefined | Value::None => "null".to_string(),
+            Value::Bool(value) => value.to_string(),
+            Value::Int(_) | Value::Float(_) => self.repr(),
+            Value::Str(value) => serde_json::to_string(value).unwrap_or_default(),
+            Value::List(items) if items.is_empty() => "[]".to_string(),
+            Value::List(items) => {
+                let items = items
+                    .iter()
+                    .map(|item| item.to_json(indent, depth + 1))
+                    .collect::<Vec<_>>();
+                format!("[{padding}{}{closing}]", items.join(&separator))
+            }
+            Value::Map(map) if map.is_empty() => "{}".to_string(),
+            Value::Map(map) => {
+                let entries = map
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = serde_json::to_string(key).unwrap_or_default();
+                        format!("{key}: {}", value.to_json(indent, depth + 1))
+                    })
+                    .collect::<Vec<_>>();
+                format!("{{{padding}{}{closing}}}", entries.join(&separator))
+            }
+            Value::Namespace(namespace) => {
+                Value::Map(namespace.borrow().clone()).to_json(indent, depth)
+            }
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Undefined, Value::Undefined) | (Value::None, Value::None) => true,
+            (Value::Str(left), Value::Str(right)) => left == right,
+            (Value::List(left), Value::List(right)) => left == right,
+            (Value::Map(left), Value::Map(right)) => left == right,
+            (Value::Namespace(left), Value::Namespace(right)) => Rc::ptr_eq(left, right),
+            (left, right) => match (left.as_f64(), right.as_f64()) {
+                (Some(left), Some(right)) => left == right,
+                _ => false,
+            },
+        }
+    }
+}
+
+impl<'a> rustler::Decoder<'a> for Value {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        match term.get_type() {
+            TermType::Binary => Ok(Value::Str(term.decode()?)),
+            TermType::Integer => Ok(Value::Int(term.decode()?)),
+            TermType::Float => Ok(Value::Float(term.decode()?)),
+            TermType::Atom => match term.atom_to_string()?.as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "nil" => Ok(Value::None),
+                atom => Ok(Value::Str(atom.to_string())),
+            },
+            TermType::List => Ok(Value::List(term.decode()?)),
+            TermType::Tuple => Ok(Value::List(
+                get_tuple(term)?
+                    .into_iter()
+                    .map(Value::decode)
+                    .collect::<NifResult<_>>()?,
+            )),
+            TermType::Map => {
+                let mut map = IndexMap::new();
+                for (key, value) in MapIterator::new(term).ok_or(rustler::Error::BadArg)? {
+                    let key = match Value::decode(key)? {
+                        Value::Str(key) => key,
+                        _ => return Err(rustler::Error::BadArg),
+                    };
+                    map.insert(key, Value::decode(value)?);
+                }
+                Ok(Value::Map(map))
+            }
+            _ => Err(rustler::Error::BadArg),
+        }
+    }
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Lexer
+// /////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Name(String),
+    Str(String),
+    Int(i64),
+    Float(f64),
+    Op(&'static str),
+}
+
+const OPERATORS: [&str; 25] = [
+    "**", "//", "==", "!=", "<=", ">=", "(", ")", "[", "]", "{", "}", ".", ",", ":", "|", "+", "-",
+    "*", "/", "%", "~", "<", ">", "=",
+];
+
+enum Segment {
+    Text(String),
+    Output(Vec<Token>),
+    Statement(Vec<Token>),
+}
+
+/// Splits the source into text and tags, applying whitespace control.
+/// As in transformers, blocks are rendered with `trim_blocks` and
+/// `lstrip_blocks` enabled.
+fn scan(source: &str) -> Result<Vec<Segment>> {
+    let mut segments = vec![];
+    let mut position = 0;
+    let mut strip_next = false;
+    let mut trim_newline = false;
+
+    loop {
+        let next = source[position..]
+            .match_indices('{')
+            .map(|(index, _)| position + index)
+            .find(|index| matches!(source.as_bytes().get(index + 1), Some(b'{' | b'%' | b'#')));
+        let end = next.unwrap_or(source.len());
+
+        let mut text = &source[position..end];
+        let mut at_line_start = position == 0 || source[..position].ends_with('\n');
+        if trim_newline {
+            if let Some(rest) = text
+                .strip_prefix("\r\n")
+                .or_else(|| text.strip_prefix('\n'))
+            {
+                text = rest;
+                at_line_start = true;
+            }
+        }
+        if strip_next {
+            text = text.trim_start();
+        }
+
+        let Some(start) = next else {
+            if !text.is_empty() {
+                segments.push(Segment::Text(text.to_string()));
+            }
+            return Ok(segments);
+        };
+
+        let kind = source.as_bytes()[start + 1];
+        let mut body = start + 2;
+        match source.as_bytes().get(body) {
+            Some(b'-') => {
+                text = text.trim_end();
+                body += 1;
+            }
+            Some(b'+') if kind != b'{' => body += 1,
+            _ if kind != b'{' => {
+                let line_start = text.rfind('\n').map_or(0, |index| index + 1);
+                let indent = &text[line_start..];
+                if (line_start > 0 || at_line_start)
+                    && indent.chars().all(|c| c == ' ' || c == '\t')
+                {
+                    text = &text[..line_start];
+                }
+            }
+            _ => {}
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text.to_string()));
+        }
+
+        let (close, stripped) = if kind == b'#' {
+            let close = source[body..]
+                .find("#}")
+                .map(|index| body + index)
+                .ok_or_else(|| error("unclosed comment"))?;
+            (close + 2, source[..close].ends_with('-'))
+        } else {
+            let closing = if kind == b'{' { "}}" } else { "%}" };
+            let (tokens, close, stripped) = tokenize(source, body, closing)?;
+            segments.push(if kind == b'{' {
+                Segment::Output(tokens)
+            } else {
+                Segment::Statement(tokens)
+            });
+            (close, stripped)
+        };
+
+        position = close;
+        strip_next = stripped;
+        trim_newline = kind != b'{' && !stripped;
+    }
+}
+
+/// Tokenizes a tag body starting at `position`, up to the `closing`
+/// delimiter. Returns the tokens, the position after the delimiter and
+/// whether the delimiter strips the following whitespace.
+fn tokenize(source: &str, mut position: usize, closing: &str) -> Result<(Vec<Token>, usize, bool)> {
+    let mut tokens = vec![];
+    let mut depth = 0;
+
+    loop {
+        let rest = source[position..].trim_start();
+        position = source.len() - rest.len();
+
+        if rest.is_empty() {
+            return Err(error(format!("missing {closing:?}")));
+        }
+        if depth == 0 {
+            if rest.starts_with(closing) {
+                return Ok((tokens, position + closing.len(), false));
+            }
+            if rest.starts_with('-') && rest[1..].starts_with(closing) {
+                return Ok((tokens, position + 1 + closing.len(), true));
+            }
+        }
+
+        let first = rest.chars().next().unwrap_or_default();
+        if first.is_ascii_alphabetic() || first == '_' {
+            let length = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..length].to_string()));
+            position += length;
+        } else if first.is_ascii_digit() {
+            let mut length = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let fraction = rest[length..]
+                .strip_prefix('.')
+                .filter(|fraction| fraction.starts_with(|c: char| c.is_ascii_digit()));
+            if let Some(fraction) = fraction {
+                length += 1 + fraction
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(fraction.len());
+                let value = rest[..length]
+                    .parse()
+                    .map_err(|_| error(format!("invalid number {}", &rest[..length])))?;
+                tokens.push(Token::Float(value));
+            } else {
+                let value = rest[..length]
+                    .parse()
+                    .map_err(|_| error(format!("invalid number {}", &rest[..length])))?;
+                tokens.push(Token::Int(value));
+            }
+            position += length;
+        } else if first == '\'' || first == '"' {
+            let (value, length) = read_string(rest)?;
+            tokens.push(Token::Str(value));
+            position += length;
+        } else {
+            let operator = OPERATORS
+                .iter()
+                .find(|operator| rest.starts_with(*operator))
+                .ok_or_else(|| error(format!("unexpected character {first:?}")))?;
+            match *operator {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" | "}" => depth -= 1,
+                _ => {}
+            }
+            tokens.push(Token::Op(operator));
+            position += operator.len();
+        }
+    }
+}
+
+/// Reads a quoted string literal, returning its value and length.
+fn read_string(source: &str) -> Result<(String, usize)> {
+    let mut chars = source.char_indices();
+    let (_, quote) = chars.next().unwrap_or_default();
+    let mut value = String::new();
+    while let Some((index, char)) = chars.next() {
+        match char {
+            '\\' => match chars.next() {
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, 'r')) => value.push('\r'),
+                Some((_, escaped @ ('\\' | '\'' | '"'))) => value.push(escaped),
+                Some((_, other)) => {
+                    value.push('\\');
+                    value.push(other);
+                }
+                None => break,
+            },
+            char if char == quote => return Ok((value, index + 1)),
+            char => value.push(char),
+        }
+    }
+    Err(error("unterminated string"))
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Parser
+// /////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+enum Expr {
+    Literal(Value),
+    List(Vec<Expr>),
+    Dict(Vec<(Expr, Expr)>),
+    Name(String),
+    Attr(Box<Expr>, String),
+    Item(Box<Expr>, Box<Expr>),
+    Slice(Box<Expr>, [Option<Box<Expr>>; 3]),
+    Call(Box<Expr>, Args),
+    Filter(Box<Expr>, String, Args),
+    Test(Box<Expr>, String, Args, bool),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Condition(Box<Expr>, Box<Expr>, Option<Box<Expr>>),
+}
+
+#[derive(Debug, Default)]
+struct Args {
+    positional: Vec<Expr>,
+    keyword: Vec<(String, Expr)>,
+}
+
+#[derive(Debug)]
+enum Target {
+    Name(String),
+    Attr(String, String),
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Output(Expr),
+    If(Vec<(Expr, Vec<Node>)>, Vec<Node>),
+    For {
+        targets: Vec<String>,
+        iterable: Expr,
+        condition: Option<Expr>,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Set(Target, Expr),
+    SetBlock(String, Vec<Node>),
+    Macro(Rc<Macro>),
+}
+
+#[derive(Debug)]
+struct Macro {
+    name: String,
+    params: Vec<(String, Option<Expr>)>,
+    body: Vec<Node>,
+}
+
+struct ExprParser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl ExprParser {
+    fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    /// Enters one more level of the expression tree. Errors abandon the
+    /// parser, so only successful parses restore the depth.
+    fn deeper(&mut self) -> Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(too_deep());
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek_op(&self, op: &str) -> bool {
+        matches!(self.peek(), Some(Token::Op(other)) if *other == op)
+    }
+
+    fn peek_name(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(Token::Name(other)) if other == name)
+    }
+
+    fn skip_op(&mut self, op: &str) -> bool {
+        let found = self.peek_op(op);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn skip_name(&mut self, name: &str) -> bool {
+        let found = self.peek_name(name);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect_op(&mut self, op: &str) -> Result<()> {
+        if self.skip_op(op) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("{op:?}")))
+        }
+    }
+
+    fn expect_name(&mut self) -> Result<String> {
+        match self.next() {
+            Some(Token::Name(name)) => Ok(name),
+            _ => {
+                self.position -= 1;
+                Err(self.unexpected("a name"))
+            }
+        }
+    }
+
+    fn expect_end(&self) -> Result<()> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.unexpected("the end of the tag")),
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> ExTokenizersError {
+        match self.peek() {
+            Some(token) => error(format!("expected {expected}, got {token:?}")),
+            None => error(format!("expected {expected}, got the end of the tag")),
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr> {
+        self.deeper()?;
+        let mut expr = self.parse_or()?;
+        if self.skip_name("if") {
+            let condition = self.parse_or()?;
+            let otherwise = if self.skip_name("else") {
+                Some(Box::new(self.parse_expr()?))
+            } else {
+                None
+            };
+            expr = Expr::Condition(Box::new(expr), Box::new(condition), otherwise);
+        }
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    // Chains of binary operators, postfixes and filters nest the expression
+    // once per operator, so each one counts as a level
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let depth = self.depth;
+        let mut expr = self.parse_and()?;
+        while self.skip_name("or") {
+            self.deeper()?;
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let depth = self.depth;
+        let mut expr = self.parse_not()?;
+        while self.skip_name("and") {
+            self.deeper()?;
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr> {
+        if self.skip_name("not") {
+            self.deeper()?;
+            let expr = Expr::Not(Box::new(self.parse_not()?));
+            self.depth -= 1;
+            return Ok(expr);
+        }
+        self.parse_compare()
+    }
+
+    fn parse_compare(&mut self) -> Result<Expr> {
+        let depth = self.depth;
+        let mut expr = self.parse_math1()?;
+        loop {
+            let operator = match self.peek() {
+                Some(Token::Op(op @ ("==" | "!=" | "<" | ">" | "<=" | ">="))) => *op,
+                Some(Token::Name(name)) if name == "in" => "in",
+                Some(Token::Name(name))
+                    if name == "not"
+                        && matches!(self.tokens.get(self.position + 1), Some(Token::Name(name)) if name == "in") =>
+                {
+                    self.position += 1;
+                    "not in"
+                }
+                _ => break,
+            };
+            self.position += 1;
+            self.deeper()?;
+            expr = Expr::Binary(operator, Box::new(expr), Box::new(self.parse_math1()?));
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn parse_math1(&mut self) -> Result<Expr> {
+        let depth = self.depth;
+        let mut expr = self.parse_concat()?;
+        while let Some(Token::Op(op @ ("+" | "-"))) = self.peek() {
+            let operator = *op;
+            self.position += 1;
+            self.deeper()?;
+            expr = Expr::Binary(operator, Box::new(expr), Box::new(self.parse_concat()?));
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn parse_concat(&mut self) -> Result<Expr> {
+        let depth = self.depth;
+        let mut expr = self.parse_math2()?;
+        while self.skip_op("~") {
+            self.deeper()?;
+            expr = Expr::Binary("~", Box::new(expr), Box::new(self.parse_math2()?));
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn parse_math2(&mut self) -> Result<Expr> {
+        let depth = self.depth;
+        let mut expr = self.parse_pow()?;
+        while let Some(Token::Op(op @ ("*" | "/" | "//" | "%"))) = self.peek() {
+            let operator = *op;
+            self.position += 1;
+            self.deeper()?;
+            expr = Expr::Binary(operator, Box::new(expr), Box::new(self.parse_pow()?));
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn parse_pow(&mut self) -> Result<Expr> {
+        let depth = self.depth;
+        let mut expr = self.parse_unary()?;
+        while self.skip_op("**") {
+            self.deeper()?;
+            expr = Expr::Binary("**", Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.skip_op("-") {
+            self.deeper()?;
+            let expr = Expr::Neg(Box::new(self.parse_unary()?));
+            self.depth -= 1;
+            return Ok(expr);
+        }
+        let expr = self.parse_primary()?;
+        let expr = self.parse_postfix(expr)?;
+        self.parse_filters(expr)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Name(name)) => Ok(match name.as_str() {
+                "true" | "True" => Expr::Literal(Value::Bool(true)),
+                "false" | "False" => Expr::Literal(Value::Bool(false)),
+                "none" | "None" => Expr::Literal(Value::None),
+                _ => Expr::Name(name),
+            }),
+            Some(Token::Str(mut value)) => {
+                // Adjacent string literals are concatenated
+                while let Some(Token::Str(next)) = self.peek() {
+                    value.push_str(next);
+                    self.position += 1;
+                }
+                Ok(Expr::Literal(Value::Str(value)))
+            }
+            Some(Token::Int(value)) => Ok(Expr::Literal(Value::Int(value))),
+            Some(Token::Float(value)) => Ok(Expr::Literal(Value::Float(value))),
+            Some(Token::Op("(")) => {
+                let expr = self.parse_expr()?;
+                if self.peek_op(",") {
+                    let mut items = vec![expr];
+                    while self.skip_op(",") && !self.peek_op(")") {
+                        items.push(self.parse_expr()?);
+                    }
+                    self.expect_op(")")?;
+                    return Ok(Expr::List(items));
+                }
+                self.expect_op(")")?;
+                Ok(expr)
+            }
+            Some(Token::Op("[")) => {
+                let mut items = vec![];
+                while !self.skip_op("]") {
+                    items.push(self.parse_expr()?);
+                    if !self.skip_op(",") {
+                        self.expect_op("]")?;
+                        break;
+                    }
+                }
+                Ok(Expr::List(items))
+            }
+            Some(Token::Op("{")) => {
+                let mut entries = vec![];
+                while !self.skip_op("}") {
+                    let key = self.parse_expr()?;
+                    self.expect_op(":")?;
+                    entries.push((key, self.parse_expr()?));
+                    if !self.skip_op(",") {
+                        self.expect_op("}")?;
+                        break;
+                    }
+                }
+                Ok(Expr::Dict(entries))
+            }
+            _ => {
+                self.position -= 1;
+                Err(self.unexpected("an expression"))
+            }
+        }
+    }
+
+    fn parse_postfix(&mut self, mut expr: Expr) -> Result<Expr> {
+        let depth = self.depth;
+        loop {
+            if self.skip_op(".") {
+                self.deeper()?;
+                expr = match self.next() {
+                    Some(Token::Name(name)) => Expr::Attr(Box::new(expr), name),
+                    Some(Token::Int(index)) => {
+                        Expr::Item(Box::new(expr), Box::new(Expr::Literal(Value::Int(index))))
+                    }
+                    _ => {
+                        self.position -= 1;
+                        return Err(self.unexpected("an attribute name"));
+                    }
+                };
+            } else if self.skip_op("[") {
+                self.deeper()?;
+                expr = self.parse_subscript(expr)?;
+            } else if self.peek_op("(") {
+                self.deeper()?;
+                expr = Expr::Call(Box::new(expr), self.parse_args()?);
+            } else {
+                self.depth = depth;
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_subscript(&mut self, expr: Expr) -> Result<Expr> {
+        let mut parts: [Option<Box<Expr>>; 3] = [None, None, None];
+        let mut index = 0;
+        loop {
+            if self.peek_op(":") {
+                index += 1;
+                if index > 2 {
+                    return Err(self.unexpected("\"]\""));
+                }
+                self.position += 1;
+            } else if self.skip_op("]") {
+                break;
+            } else {
+                parts[index] = Some(Box::new(self.parse_expr()?));
+            }
+        }
+        if index == 0 {
+            let key = parts[0].take().ok_or_else(|| error("empty subscript"))?;
+            return Ok(Expr::Item(Box::new(expr), key));
+        }
+        Ok(Expr::Slice(Box::new(expr), parts))
+    }
+
+    fn parse_args(&mut self) -> Result<Args> {
+        let mut args = Args::default();
+        self.expect_op("(")?;
+        while !self.skip_op(")") {
+            match (self.peek(), self.tokens.get(self.position + 1)) {
+                (Some(Token::Name(name)), Some(Token::Op("="))) => {
+                    let name = name.clone();
+                    self.position += 2;
+                    args.keyword.push((name, self.parse_expr()?));
+                }
+                _ => args.positional.push(self.parse_expr()?),
+            }
+            if !self.skip_op(",") {
+                self.expect_op(")")?;
+                break;
+            }
+        }
+        Ok(args)
+    }
+
+    fn parse_filters(&mut self, mut expr: Expr) -> Result<Expr> {
+        let depth = self.depth;
+        loop {
+            if self.skip_op("|") {
+                self.deeper()?;
+                let name = self.expect_name()?;
+                let args = if self.peek_op("(") {
+                    self.parse_args()?
+                } else {
+                    Args::default()
+                };
+                expr = Expr::Filter(Box::new(expr), name, args);
+            } else if self.skip_name("is") {
+                self.deeper()?;
+                let negated = self.skip_name("not");
+                let name = match self.next() {
+                    Some(Token::Name(name)) => name,
+                    Some(Token::Op("==")) => "eq".to_string(),
+                    _ => {
+                        self.position -= 1;
+                        return Err(self.unexpected("a test name"));
+                    }
+                };
+                let args = if self.peek_op("(") {
+                    self.parse_args()?
+                } else if matches!(
+                    self.peek(),
+                    Some(Token::Str(_) | Token::Int(_) | Token::Float(_))
+                ) {
+                    Args {
+                        positional: vec![self.parse_primary()?],
+                        keyword: vec![],
+                    }
+                } else {
+                    Args::default()
+                };
+                expr = Expr::Test(Box::new(expr), name, args, negated);
+            } else {
+                self.depth = depth;
+                return Ok(expr);
+            }
+        }
+    }
+}
+
+/// A closing tag, with the parser for the rest of its tokens.
+type EndTag = (String, ExprParser);
+
+struct TemplateParser {
+    segments: std::vec::IntoIter<Segment>,
+    depth: usize,
+}
+
+impl TemplateParser {
+    /// Parses nodes until one of the `ends` tags, which is returned along
+    /// with the rest of its tokens.
+    fn parse_nodes(&mut self, ends: &[&str]) -> Result<(Vec<Node>, Option<EndTag>)> {
+        let mut nodes = vec![];
+        while let Some(segment) = self.segments.next() {
+            match segment {
+                Segment::Text(text) => nodes.push(Node::Text(text)),
+                Segment::Output(tokens) => {
+                    let mut parser = ExprParser::new(tokens);
+                    nodes.push(Node::Output(parser.parse_expr()?));
+                    parser.expect_end()?;
+                }
+                Segment::Statement(tokens) => {
+                    let mut parser = ExprParser::new(tokens);
+                    let tag = parser.expect_name()?;
+                    if ends.contains(&tag.as_str()) {
+                        return Ok((nodes, Some((tag, parser))));
+                    }
+                    self.depth += 1;
+                    if self.depth > MAX_DEPTH {
+                        return Err(too_deep());
+                    }
+                    if let Some(node) = self.parse_statement(&tag, parser)? {
+                        nodes.push(node);
+                    }
+                    self.depth -= 1;
+                }
+            }
+        }
+        match ends.first() {
+            Some(end) => Err(error(format!("missing {{% {end} %}}"))),
+            None => Ok((nodes, None)),
+        }
+    }
+
+    fn parse_statement(&mut self, tag: &str, mut parser: ExprParser) -> Result<Option<Node>> {
+        match tag {
+            "if" => {
+                let mut branches = vec![];
+                let mut condition = parser.parse_expr()?;
+                parser.expect_end()?;
+                loop {
+                    let (body, end) = self.parse_nodes(&["endif", "elif", "else"])?;
+                    branches.push((condition, body));
+                    match end {
+                        Some((tag, mut parser)) if tag == "elif" => {
+                            condition = parser.parse_expr()?;
+                            parser.expect_end()?;
+                        }
+                        Some((tag, parser)) if tag == "else" => {
+                            parser.expect_end()?;
+                            let (otherwise, _) = self.parse_nodes(&["endif"])?;
+                            return Ok(Some(Node::If(branches, otherwise)));
+                        }
+                        _ => return Ok(Some(Node::If(branches, vec![]))),
+                    }
+                }
+            }
+            "for" => {
+                let mut targets = vec![parser.expect_name()?];
+                while parser.skip_op(",") {
+                    targets.push(parser.expect_name()?);
+                }
+                if !parser.skip_name("in") {
+                    return Err(parser.unexpected("\"in\""));
+                }
+                let iterable = parser.parse_or()?;
+                let condition = if parser.skip_name("if") {
+                    Some(parser.parse_expr()?)
+                } else {
+                    None
+                };
+                parser.expect_end()?;
+                let (body, end) = self.parse_nodes(&["endfor", "else"])?;
+                let otherwise = match end {
+                    Some((end, _)) if end == "else" => self.parse_nodes(&["endfor"])?.0,
+                    _ => vec![],
+                };
+                Ok(Some(Node::For {
+                    targets,
+                    iterable,
+                    condition,
+                    body,
+                    otherwise,
+                }))
+            }
+            "set" => {
+                let name = parser.expect_name()?;
+                let target = if parser.skip_op(".") {
+                    Target::Attr(name, parser.expect_name()?)
+                } else {
+                    Target::Name(name)
+                };
+                if parser.skip_op("=") {
+                    let value = parser.parse_expr()?;
+                    parser.expect_end()?;
+                    return Ok(Some(Node::Set(target, value)));
+                }
+                parser.expect_end()?;
+                match target {
+                    Target::Name(name) => {
+                        let (body, _) = self.parse_nodes(&["endset"])?;
+                        Ok(Some(Node::SetBlock(name, body)))
+                    }
+                    Target::Attr(..) => Err(parser.unexpected("\"=\"")),
+                }
+            }
+            "macro" => {
+                let name = parser.expect_name()?;
+                let mut params = vec![];
+                parser.expect_op("(")?;
+                while !parser.skip_op(")") {
+                    let param = parser.expect_name()?;
+                    let default = if parser.skip_op("=") {
+                        Some(parser.parse_expr()?)
+                    } else {
+                        None
+                    };
+                    params.push((param, default));
+                    if !parser.skip_op(",") {
+                        parser.expect_op(")")?;
+                        break;
+                    }
+                }
+                parser.expect_end()?;
+                let (body, _) = self.parse_nodes(&["endmacro"])?;
+                Ok(Some(Node::Macro(Rc::new(Macro { name, params, body }))))
+            }
+            // Marks assistant messages for transformers' masks, it has no
+            // effect on the output
+            "generation" | "endgeneration" => Ok(None),
+            other => Err(error(format!("unsupported tag {other:?}"))),
+        }
+    }
+}
+
+fn parse(source: &str) -> Result<Vec<Node>> {
+    // Jinja drops a single trailing newline unless `keep_trailing_newline`
+    // is set, which transformers doesn't do
+    let source = ["\r\n", "\n", "\r"]
+        .iter()
+        .find_map(|newline| source.strip_suffix(newline))
+        .unwrap_or(source);
+    let mut parser = TemplateParser {
+        segments: scan(source)?.into_iter(),
+        depth: 0,
+    };
+    Ok(parser.parse_nodes(&[])?.0)
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Rendering
+// /////////////////////////////////////////////////////////////////////////////
+
+struct Renderer {
+    scopes: Vec<HashMap<String, Value>>,
+    macros: HashMap<String, Rc<Macro>>,
+    depth: usize,
+}
+
+impl Renderer {
+    /// Enters one more level of tags, expressions or macro calls. Errors
+    /// abort the rendering, so only successful calls restore the depth.
+    fn deeper(&mut self) -> Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(too_deep());
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Value {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .cloned()
+            .unwrap_or(Value::Undefined)
+    }
+
+    fn assign(&mut self, name: String, value: Value) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name, value);
+        }
+    }
+
+    fn render(&mut self, nodes: &[Node], output: &mut String) -> Result<()> {
+        self.deeper()?;
+        for node in nodes {
+            match node {
+                Node::Text(text) => push_checked(output, text)?,
+                Node::Output(expr) => push_checked(output, &self.eval(expr)?.render())?,
+                Node::If(branches, otherwise) => {
+                    let mut body = otherwise;
+                    for (condition, branch) in branches {
+                        if self.eval(condition)?.is_truthy() {
+                            body = branch;
+                            break;
+                        }
+                    }
+                    self.render(body, output)?;
+                }
+                Node::For {
+                    targets,
+                    iterable,
+                    condition,
+                    body,
+                    otherwise,
+                } => self.render_for(
+                    targets,
+                    iterable,
+                    condition.as_ref(),
+                    body,
+                    otherwise,
+                    output,
+                )?,
+                Node::Set(Target::Name(name), expr) => {
+                    let value = self.eval(expr)?;
+                    self.assign(name.clone(), value);
+                }
+                Node::Set(Target::Attr(name, attr), expr) => {
+                    let value = self.eval(expr)?;
+                    if value.has_namespace() {
+                        return Err(error("namespaces cannot hold other namespaces"));
+                    }
+                    match self.lookup(name) {
+                        Value::Namespace(namespace) => {
+                            namespace.borrow_mut().insert(attr.clone(), value);
+                        }
+                        _ => return Err(error(format!("{name} is not a namespace"))),
+                    }
+                }
+                Node::SetBlock(name, body) => {
+                    let mut value = String::new();
+                    self.render(body, &mut value)?;
+                    self.assign(name.clone(), Value::Str(value));
+                }
+                Node::Macro(definition) => {
+                    self.macros
+                        .insert(definition.name.clone(), definition.clone());
+                }
+            }
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+
+    fn render_for(
+        &mut self,
+        targets: &[String],
+        iterable: &Expr,
+        condition: Option<&Expr>,
+        body: &[Node],
+        otherwise: &[Node],
+        output: &mut String,
+    ) -> Result<()> {
+        let mut items = vec![];
+        for item in self.eval(iterable)?.iterate()? {
+            if let Some(condition) = condition {
+                self.scopes.push(self.bind(targets, &item)?);
+                let keep = self.eval(condition);
+                self.scopes.pop();
+                if !keep?.is_truthy() {
+                    continue;
+                }
+            }
+            items.push(item);
+        }
+
+        if items.is_empty() {
+            return self.render(otherwise, output);
+        }
+
+        let length = items.len();
+        for (index, item) in items.iter().enumerate() {
+            let mut scope = self.bind(targets, item)?;
+            let mut state = IndexMap::new();
+            state.insert("index".to_string(), Value::Int(index as i64 + 1));
+            state.insert("index0".to_string(), Value::Int(index as i64));
+            state.insert("revindex".to_string(), Value::Int((length - index) as i64));
+            state.insert(
+                "revindex0".to_string(),
+                Value::Int((length - index - 1) as i64),
+            );
+            state.insert("first".to_string(), Value::Bool(index == 0));
+            state.insert("last".to_string(), Value::Bool(index == length - 1));
+            state.insert("length".to_string(), Value::Int(length as i64));
+            if index > 0 {
+                state.insert("previtem".to_string(), items[index - 1].clone());
+            }
+            if index + 1 < length {
+                state.insert("nextitem".to_string(), items[index + 1].clone());
+            }
+            scope.insert("loop".to_string(), Value::Map(state));
+
+            self.scopes.push(scope);
+            let result = self.render(body, output);
+            self.scopes.pop();
+            result?;
+        }
+        Ok(())
+    }
+
+    fn bind(&self, targets: &[String], item: &Value) -> Result<HashMap<String, Value>> {
+        let mut scope = HashMap::new();
+        match (targets, item) {
+            ([target], _) => {
+                scope.insert(target.clone(), item.clone());
+            }
+            (targets, Value::List(values)) if targets.len() == values.len() => {
+                scope.extend(targets.iter().cloned().zip(values.iter().cloned()));
+            }
+            _ => return Err(error(format!("cannot unpack {}", item.repr()))),
+        }
+        Ok(scope)
+    }
+
+    fn call_macro(
+        &mut self,
+        definition: &Macro,
+        args: Vec<Value>,
+        mut keyword: IndexMap<String, Value>,
+    ) -> Result<Value> {
+        if args.len() > definition.params.len() {
+            return Err(error(format!(
+                "macro {:?} takes at most {} arguments",
+                definition.name,
+                definition.params.len()
+            )));
+        }
+        self.deeper()?;
+        // Macros only see the variables set at the top level of the template
+        let outer = self.scopes.split_off(1);
+        self.scopes.push(HashMap::new());
+        let mut output = String::new();
+        let result = self
+            .bind_params(definition, args, &mut keyword)
+            .and_then(|_| match keyword.keys().next() {
+                Some(name) => Err(error(format!(
+                    "macro {:?} takes no argument {name:?}",
+                    definition.name
+                ))),
+                None => self.render(&definition.body, &mut output),
+            });
+        self.scopes.truncate(1);
+        self.scopes.extend(outer);
+        result?;
+        self.depth -= 1;
+        Ok(Value::Str(output))
+    }
+
+    fn bind_params(
+        &mut self,
+        definition: &Macro,
+        args: Vec<Value>,
+        keyword: &mut IndexMap<String, Value>,
+    ) -> Result<()> {
+        let mut args = args.into_iter();
+        for (name, default) in &definition.params {
+            let value = match (args.next(), keyword.shift_remove(name), default) {
+                (Some(value), _, _) | (None, Some(value), _) => value,
+                (None, None, Some(default)) => self.eval(default)?,
+                (None, None, None) => Value::Undefined,
+            };
+            self.assign(name.clone(), value);
+        }
+        Ok(())
+    }
+
+    fn eval_args(&mut self, args: &Args) -> Result<(Vec<Value>, IndexMap<String, Value>)> {
+        let positional = args
+            .positional
+            .iter()
+            .map(|expr| self.eval(expr))
+            .collect::<Result<Vec<_>>>()?;
+        let keyword = args
+            .keyword
+            .iter()
+            .map(|(name, expr)| Ok((name.clone(), self.eval(expr)?)))
+            .collect::<Result<IndexMap<_, _>>>()?;
+        Ok((positional, keyword))
+    }
+
+    fn eval(&mut self, expr: &Expr) -> Result<Value> {
+        self.deeper()?;
+        let value = self.eval_expr(expr)?;
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    fn eval_expr(&mut self, expr: &Expr) -> Result<Value> {
+        match expr {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::List(items) => Value::List(
+                items
+                    .iter()
+                    .map(|item| self.eval(item))
+                    .collect::<Result<_>>()?,
+            )
+            .checked(),
+            Expr::Dict(entries) => {
+                let mut map = IndexMap::new();
+                for (key, value) in entries {
+                    let key = self.eval(key)?.render();
+                    map.insert(key, self.eval(value)?);
+                }
+                Value::Map(map).checked()
+            }
+            Expr::Name(name) => Ok(self.lookup(name)),
+            Expr::Attr(value, name) => Ok(get_attr(&self.eval(value)?, name)),
+            Expr::Item(value, key) => {
+                let value = self.eval(value)?;
+                let key = self.eval(key)?;
+                Ok(get_item(&value, &key))
+            }
+            Expr::Slice(value, parts) => {
+                let value = self.eval(value)?;
+                let mut bounds = [None, None, None];
+                for (bound, part) in bounds.iter_mut().zip(parts) {
+                    if let Some(part) = part {
+                        *bound = self.eval(part)?.as_i64();
+                    }
+                }
+                slice(&value, bounds)
+            }
+            Expr::Call(callee, args) => {
+                let (positional, keyword) = self.eval_args(args)?;
+                match callee.as_ref() {
+                    Expr::Attr(value, method) => {
+                        let value = self.eval(value)?;
+                        call_method(&value, method, positional, keyword)
+                    }
+                    Expr::Name(name) => match self.macros.get(name).cloned() {
+                        Some(definition) => self.call_macro(&definition, positional, keyword),
+                        None => call_function(name, positional, keyword),
+                    },
+                    _ => Err(error("expression is not callable")),
+                }
+            }
+            Expr::Filter(value, name, args) => {
+                let value = self.eval(value)?;
+                let (positional, keyword) = self.eval_args(args)?;
+                apply_filter(value, name, positional, keyword)
+            }
+            Expr::Test(value, name, args, negated) => {
+                let value = self.eval(value)?;
+                let (positional, _) = self.eval_args(args)?;
+                Ok(Value::Bool(
+                    apply_test(&value, name, &positional)? != *negated,
+                ))
+            }
+            Expr::Not(value) => Ok(Value::Bool(!self.eval(value)?.is_truthy())),
+            Expr::Neg(value) => match self.eval(value)? {
+                Value::Int(value) => value.checked_neg().map(Value::Int).ok_or_else(overflow),
+                Value::Float(value) => Ok(Value::Float(-value)),
+                other => Err(error(format!("cannot negate {}", other.type_name()))),
+            },
+            Expr::And(left, right) => {
+                let left = self.eval(left)?;
+                if left.is_truthy() {
+                    self.eval(right)
+                } else {
+                    Ok(left)
+                }
+            }
+            Expr::Or(left, right) => {
+                let left = self.eval(left)?;
+                if left.is_truthy() {
+                    Ok(left)
+                } else {
+                    self.eval(right)
+                }
+            }
+            Expr::Binary(operator, left, right) => {
+                let left = self.eval(left)?;
+                let right = self.eval(right)?;
+                binary(operator, left, right)
+            }
+            Expr::Condition(value, condition, otherwise) => {
+                if self.eval(condition)?.is_truthy() {
+                    self.eval(value)
+                } else {
+                    match otherwise {
+                        Some(otherwise) => self.eval(otherwise),
+                        None => Ok(Value::Undefined),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn get_attr(value: &Value, name: &str) -> Value {
+    match value {
+        Value::Map(map) => map.get(name).cloned().unwrap_or(Value::Undefined),
+        Value::Namespace(namespace) => namespace
+            .borrow()
+            .get(name)
+            .cloned()
+            .unwrap_or(Value::Undefined),
+        _ => Value::Undefined,
+    }
+}
+
+fn get_item(value: &Value, key: &Value) -> Value {
+    match (value, key) {
+        (Value::Map(_) | Value::Namespace(_), Value::Str(key)) => get_attr(value, key),
+        (Value::List(items), Value::Int(index)) => python_index(items.len(), *index)
+            .map(|index| items[index].clone())
+            .unwrap_or(Value::Undefined),
+        (Value::Str(string), Value::Int(index)) => {
+            let chars = string.chars().collect::<Vec<_>>();
+            python_index(chars.len(), *index)
+                .map(|index| Value::Str(chars[index].to_string()))
+                .unwrap_or(Value::Undefined)
+        }
+        _ => Value::Undefined,
+    }
+}
+
+/// Looks up a dotted path such as `"function.name"`, as the `attribute`
+/// arguments of filters do.
+fn get_path(value: &Value, path: &Value) -> Value {
+    match path {
+        Value::Str(path) => {
+            path.split('.')
+                .fold(value.clone(), |value, part| match part.parse::<i64>() {
+                    Ok(index) => get_item(&value, &Value::Int(index)),
+                    Err(_) => get_item(&value, &Value::Str(part.to_string())),
+                })
+        }
+        key => get_item(value, key),
+    }
+}
+
+fn python_index(length: usize, index: i64) -> Option<usize> {
+    let index = if index < 0 {
+        length as i64 + index
+    } else {
+        index
+    };
+    (0..length as i64)
+        .contains(&index)
+        .then_some(index as usize)
+}
+
+/// Returns the indices selected by a Python slice.
+fn slice_indices(length: usize, [start, stop, step]: [Option<i64>; 3]) -> Result<Vec<usize>> {
+    let length = length as i64;
+    let step = step.unwrap_or(1);
+    if step == 0 {
+        return Err(error("slice step cannot be zero"));
+    }
+    let clamp = |bound: i64, low: i64, high: i64| {
+        let bound = if bound < 0 { bound + length } else { bound };
+        bound.clamp(low, high)
+    };
+    let mut indices = vec![];
+    if step > 0 {
+        let start = start.map_or(0, |start| clamp(start, 0, length));
+        let stop = stop.map_or(length, |stop| clamp(stop, 0, length));
+        let mut index = start;
+        while index < stop {
+            indices.push(index as usize);
+            index += step;
+        }
+    } else {
+        let start = start.map_or(length - 1, |start| clamp(start, -1, length - 1));
+        let stop = stop.map_or(-1, |stop| clamp(stop, -1, length - 1));
+        let mut index = start;
+        while index > stop {
+            indices.push(index as usize);
+            index += step;
+        }
+    }
+    Ok(indices)
+}
+
+fn slice(value: &Value, bounds: [Option<i64>; 3]) -> Result<Value> {
+    match value {
+        Value::List(items) => Ok(Value::List(
+            slice_indices(items.len(), bounds)?
+                .into_iter()
+                .map(|index| items[index].clone())
+                .collect(),
+        )),
+        Value::Str(string) => {
+            let chars = string.chars().collect::<Vec<_>>();
+            Ok(Value::Str(
+                slice_indices(chars.len(), bounds)?
+                    .into_iter()
+                    .map(|index| chars[index])
+                    .collect(),
+            ))
+        }
+        other => Err(error(format!("cannot slice {}", other.type_name()))),
+    }
+}
+
+fn binary(operator: &str, left: Value, right: Value) -> Result<Value> {
+    let invalid = |left: &Value, right: &Value| {
+        error(format!(
+            "unsupported operand types for {operator}: {} and {}",
+            left.type_name(),
+            right.type_name()
+        ))
+    };
+    match operator {
+        "==" => Ok(Value::Bool(left == right)),
+        "!=" => Ok(Value::Bool(left != right)),
+        "<" | ">" | "<=" | ">=" => {
+            let ordering = match (&left, &right) {
+                (Value::Str(left), Value::Str(right)) => left.partial_cmp(right),
+                _ => match (left.as_f64(), right.as_f64()) {
+                    (Some(left), Some(right)) => left.partial_cmp(&right),
+                    _ => None,
+                },
+            }
+            .ok_or_else(|| invalid(&left, &right))?;
+            Ok(Value::Bool(match operator {
+                "<" => ordering.is_lt(),
+                ">" => ordering.is_gt(),
+                "<=" => ordering.is_le(),
+                _ => ordering.is_ge(),
+            }))
+        }
+        "in" | "not in" => {
+            let found = match &right {
+                Value::Str(string) => string.contains(left.as_str()?),
+                Value::List(items) => items.contains(&left),
+                Value::Map(map) => matches!(&left, Value::Str(key) if map.contains_key(key)),
+                Value::Undefined => false,
+                _ => return Err(invalid(&left, &right)),
+            };
+            Ok(Value::Bool(found == (operator == "in")))
+        }
+        "~" => concat(left.render(), &right.render()),
+        "+" => match (left, right) {
+            (Value::Str(left), Value::Str(right)) => concat(left, &right),
+            (Value::List(left), Value::List(right)) => Ok(Value::List([left, right].concat())),
+            (left, right) => {
+                arithmetic(operator, &left, &right).ok_or_else(|| invalid(&left, &right))?
+            }
+        },
+        "*" => match (&left, &right) {
+            (Value::Str(string), Value::Int(count)) | (Value::Int(count), Value::Str(string)) => {
+                let count = (*count).max(0) as usize;
+                if string.len().saturating_mul(count) > MAX_LENGTH {
+                    return Err(too_long());
+                }
+                Ok(Value::Str(string.repeat(count)))
+            }
+            _ => arithmetic(operator, &left, &right).ok_or_else(|| invalid(&left, &right))?,
+        },
+        _ => arithmetic(operator, &left, &right).ok_or_else(|| invalid(&left, &right))?,
+    }
+}
+
+fn overflow() -> ExTokenizersError {
+    error("integer overflow")
+}
+
+/// Returns `None` for unsupported operands, and an error for operations
+/// that overflow 64-bit integers.
+fn arithmetic(operator: &str, left: &Value, right: &Value) -> Option<Result<Value>> {
+    if let (Some(left), Some(right)) = (left.as_i64(), right.as_i64()) {
+        let result = match operator {
+            "+" => left.checked_add(right),
+            "-" => left.checked_sub(right),
+            "*" => left.checked_mul(right),
+            "/" if right != 0 => return Some(Ok(Value::Float(left as f64 / right as f64))),
+            "//" if right != 0 => left.checked_div_euclid(right),
+            "%" if right != 0 => left.checked_rem_euclid(right),
+            "**" if right >= 0 => u32::try_from(right)
+                .ok()
+                .and_then(|right| left.checked_pow(right)),
+            "**" => return Some(Ok(Value::Float((left as f64).powf(right as f64)))),
+            _ => return None,
+        };
+        return Some(result.map(Value::Int).ok_or_else(overflow));
+    }
+    let (left, right) = (left.as_f64()?, right.as_f64()?);
+    let result = match operator {
+        "+" => left + right,
+        "-" => left - right,
+        "*" => left * right,
+        "/" if right != 0.0 => left / right,
+        "//" if right != 0.0 => (left / right).floor(),
+        "%" if right != 0.0 => left.rem_euclid(right),
+        "**" => left.powf(right),
+        _ => return None,
+    };
+    Some(Ok(Value::Float(result)))
+}
+
+fn concat(left: String, right: &str) -> Result<Value> {
+    if left.len() + right.len() > MAX_LENGTH {
+        return Err(too_long());
+    }
+    Ok(Value::Str(left + right))
+}
+
+fn push_checked(output: &mut String, text: &str) -> Result<()> {
+    if output.len() + text.len() > MAX_LENGTH {
+        return Err(too_long());
+    }
+    output.push_str(text);
+    Ok(())
+}
+
+fn argument(
+    args: &[Value],
+    keyword: &IndexMap<String, Value>,
+    index: usize,
+    name: &str,
+) -> Option<Value> {
+    args.get(index).or_else(|| keyword.get(name)).cloned()
+}
+
+const MAX_RANGE: i128 = 100_000;
+
+fn call_function(name: &str, args: Vec<Value>, keyword: IndexMap<String, Value>) -> Result<Value> {
+    match name {
+        "raise_exception" => {
+            let message = args.first().map(Value::render).unwrap_or_default();
+            Err(error(message))
+        }
+        "namespace" => {
+            let mut values = match args.into_iter().next() {
+                Some(Value::Map(map)) => map,
+                _ => IndexMap::new(),
+            };
+            values.extend(keyword);
+            if values.values().any(Value::has_namespace) {
+                return Err(error("namespaces cannot hold other namespaces"));
+            }
+            Value::Namespace(Rc::new(RefCell::new(values))).checked()
+        }
+        "dict" => Value::Map(keyword).checked(),
+        "range" => {
+            let bounds = args
+                .iter()
+                .map(|arg| arg.as_i64().ok_or_else(|| error("range expects integers")))
+                .collect::<Result<Vec<_>>>()?;
+            let (start, stop, step) = match bounds[..] {
+                [stop] => (0, stop, 1),
+                [start, stop] => (start, stop, 1),
+                [start, stop, step] => (start, stop, step),
+                _ => return Err(error("range expects 1 to 3 arguments")),
+            };
+            if step == 0 {
+                return Err(error("range step cannot be zero"));
+            }
+            // The length as Python computes it, rounding towards the stop
+            let (start, stop, step) = (i128::from(start), i128::from(stop), i128::from(step));
+            let length = if step > 0 {
+                (stop - start + step - 1) / step
+            } else {
+                (start - stop - step - 1) / -step
+            }
+            .max(0);
+            // Jinja's sandbox refuses ranges longer than this
+            if length > MAX_RANGE {
+                return Err(error(format!(
+                    "range too big, the sandbox allows at most {MAX_RANGE} items"
+                )));
+            }
+            Ok(Value::List(
+                (0..length)
+                    .map(|index| Value::Int((start + index * step) as i64))
+                    .collect(),
+            ))
+        }
+        other => Err(error(format!("unknown function {other:?}"))),
+    }
+}
+
+fn strip_chars<'a>(string: &'a str, chars: Option<&Value>, left: bool, right: bool) -> &'a str {
+    let matches = |c: char| match chars {
+        Some(Value::Str(chars)) => chars.contains(c),
+        _ => c.is_whitespace(),
+    };
+    let mut string = string;
+    if left {
+        string = string.trim_start_matches(matches);
+    }
+    if right {
+        string = string.trim_end_matches(matches);
+    }
+    string
+}
+
+fn capitalize(string: &str) -> String {
+    let mut chars = string.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+fn title(string: &str) -> String {
+    let mut result = String::with_capacity(string.len());
+    let mut previous_cased = false;
+    for char in string.chars() {
+        if previous_cased {
+            result.extend(char.to_lowercase());
+        } else {
+            result.extend(char.to_uppercase());
+        }
+        previous_cased = char.is_alphanumeric();
+    }
+    result
+}
+
+/// Splits lines at the boundaries of Python's `str.splitlines`.
+fn split_lines(string: &str) -> Vec<&str> {
+    let mut lines = vec![];
+    let mut start = 0;
+    let mut chars = string.char_indices().peekable();
+    while let Some((index, char)) = chars.next() {
+        if matches!(
+            char,
+            '\n' | '\r' | '\x0b' | '\x0c' | '\x1c'..='\x1e' | '\u{85}' | '\u{2028}' | '\u{2029}'
+        ) {
+            lines.push(&string[start..index]);
+            start = index + char.len_utf8();
+            if char == '\r' && chars.next_if(|(_, next)| *next == '\n').is_some() {
+                start += 1;
+            }
+        }
+    }
+    if start < string.len() {
+        lines.push(&string[start..]);
+    }
+    lines
+}
+
+/// Indents all lines but the first, as Jinja's `indent` filter does.
+fn indent(string: &str, indentation: &str, first: bool, blank: bool) -> String {
+    let string = format!("{string}\n");
+    let mut lines = split_lines(&string).into_iter();
+    let mut result = lines.next().unwrap_or_default().to_string();
+    for line in lines {
+        result.push('\n');
+        if blank || !line.is_empty() {
+            result.push_str(indentation);
+        }
+        result.push_str(line);
+    }
+    if first {
+        result.insert_str(0, indentation);
+    }
+    result
+}
+
+/// Keeps the items passing a test, or the truthy ones without a test,
+/// as the `select` family of filters does.
+fn select(value: &Value, args: &[Value], by_attribute: bool, keep: bool) -> Result<Value> {
+    let (attribute, args) = match (by_attribute, args.split_first()) {
+        (true, Some((attribute, args))) => (Some(attribute), args),
+        (true, None) => return Err(error("expected an attribute")),
+        (false, _) => (None, args),
+    };
+    let mut items = vec![];
+    for item in value.iterate()? {
+        let subject = match attribute {
+            Some(attribute) => get_path(&item, attribute),
+            None => item.clone(),
+        };
+        let passed = match args.split_first() {
+            Some((test, args)) => apply_test(&subject, test.as_str()?, args)?,
+            None => subject.is_truthy(),
+        };
+        if passed == keep {
+            items.push(item);
+        }
+    }
+    Ok(Value::List(items))
+}
+
+fn call_method(
+    value: &Value,
+    method: &str,
+    args: Vec<Value>,
+    keyword: IndexMap<String, Value>,
+) -> Result<Value> {
+    match (value, method) {
+        (Value::Str(string), "strip" | "lstrip" | "rstrip") => {
+            let (left, right) = (method != "rstrip", method != "lstrip");
+            Ok(Value::Str(
+                strip_chars(string, args.first(), left, right).to_string(),
+            ))
+        }
+        (Value::Str(string), "upper") => Ok(Value::Str(string.to_uppercase())),
+        (Value::Str(string), "lower") => Ok(Value::Str(string.to_lowercase())),
+        (Value::Str(string), "capitalize") => Ok(Value::Str(capitalize(string))),
+        (Value::Str(string), "title") => Ok(Value::Str(title(string))),
+        (Value::Str(string), "startswith" | "endswith") => {
+            let affixes = match args.first() {
+                Some(Value::List(affixes)) => affixes.clone(),
+                Some(affix) => vec![affix.clone()],
+                None => return Err(error(format!("{method} expects an argument"))),
+            };
+            let mut found = false;
+            for affix in &affixes {
+                let affix = affix.as_str()?;
+                found |= if method == "startswith" {
+                    string.starts_with(affix)
+                } else {
+                    string.ends_with(affix)
+                };
+            }
+            Ok(Value::Bool(found))
+        }
+        (Value::Str(string), "split") => {
+            let separator = argument(&args, &keyword, 0, "sep").filter(|sep| *sep != Value::None);
+            let limit = argument(&args, &keyword, 1, "maxsplit")
+                .and_then(|limit| limit.as_i64())
+                .filter(|limit| *limit >= 0)
+                .map(|limit| limit as usize + 1);
+            let parts: Vec<&str> = match (&separator, limit) {
+                (Some(separator), Some(limit)) => {
+                    string.splitn(limit, separator.as_str()?).collect()
+                }
+                (Some(separator), None) => string.split(separator.as_str()?).collect(),
+                (None, _) => string.split_whitespace().collect(),
+            };
+            Ok(Value::List(
+                parts
+                    .into_iter()
+                    .map(|part| Value::Str(part.to_string()))
+                    .collect(),
+            ))
+        }
+        (Value::Str(string), "replace") => match &args[..] {
+            [Value::Str(old), Value::Str(new)] => Ok(Value::Str(string.replace(old, new))),
+            [Value::Str(old), Value::Str(new), Value::Int(count)] => Ok(Value::Str(
+                string.replacen(old, new, (*count).max(0) as usize),
+            )),
+            _ => Err(error("replace expects two strings")),
+        },
+        (Value::Str(separator), "join") => {
+            let items = args
+                .first()
+                .map(Value::iterate)
+                .transpose()?
+                .unwrap_or_default();
+            let items = items.iter().map(Value::render).collect::<Vec<_>>();
+            Ok(Value::Str(items.join(separator)))
+        }
+        (Value::Map(map), "items") => Ok(Value::List(
+            map.iter()
+                .map(|(key, value)| Value::List(vec![Value::Str(key.clone()), value.clone()]))
+                .collect(),
+        )),
+        (Value::Map(map), "keys") => Ok(Value::List(map.keys().cloned().map(Value::Str).collect())),
+        (Value::Map(map), "values") => Ok(Value::List(map.values().cloned().collect())),
+        (Value::Map(map), "get") => {
+            let key = args.first().map(Value::render).unwrap_or_default();
+            Ok(map
+                .get(&key)
+                .cloned()
+                .or_else(|| args.get(1).cloned())
+                .unwrap_or(Value::None))
+        }
+        (value, method) => Err(error(format!(
+            "unknown method {method:?} for {}",
+            value.type_name()
+        ))),
+    }
+}
+
+fn apply_filter(
+    value: Value,
+    name: &str,
+    args: Vec<Value>,
+    keyword: IndexMap<String, Value>,
+) -> Result<Value> {
+    match name {
+        "trim" => Ok(Value::Str(
+            strip_chars(&value.render(), args.first(), true, true).to_string(),
+        )),
+        "length" | "count" => Ok(Value::Int(value.length()? as i64)),
+        "upper" => Ok(Value::Str(value.render().to_uppercase())),
+        "lower" => Ok(Value::Str(value.render().to_lowercase())),
+        "capitalize" => Ok(Value::Str(capitalize(&value.render()))),
+        "title" => Ok(Value::Str(title(&value.render()))),
+        "string" => Ok(Value::Str(value.render())),
+        "safe" => Ok(value),
+        "int" => Ok(match &value {
+            Value::Str(string) => string
+                .trim()
+                .parse()
+                .map(Value::Int)
+                .unwrap_or(Value::Int(0)),
+            Value::Float(float) => Value::Int(float.trunc() as i64),
+            other => Value::Int(other.as_i64().unwrap_or(0)),
+        }),
+        "list" => Ok(Value::List(value.iterate()?)),
+        "first" => Ok(value
+            .iterate()?
+            .into_iter()
+            .next()
+            .unwrap_or(Value::Undefined)),
+        "last" => Ok(value.iterate()?.pop().unwrap_or(Value::Undefined)),
+        "reverse" => match value {
+            Value::Str(string) => Ok(Value::Str(string.chars().rev().collect())),
+            value => Ok(Value::List(value.iterate()?.into_iter().rev().collect())),
+        },
+        "join" => {
+            let separator = argument(&args, &keyword, 0, "d")
+                .map(|separator| separator.render())
+                .unwrap_or_default();
+            call_method(&Value::Str(separator), "join", vec![value], IndexMap::new())
+        }
+        "replace" => call_method(&Value::Str(value.render()), "replace", args, keyword),
+        "default" | "d" => {
+            let boolean = argument(&args, &keyword, 1, "boolean").is_some_and(|b| b.is_truthy());
+            let missing = matches!(value, Value::Undefined) || (boolean && !value.is_truthy());
+            if missing {
+                Ok(argument(&args, &keyword, 0, "default_value")
+                    .unwrap_or(Value::Str(String::new())))
+            } else {
+                Ok(value)
+            }
+        }
+        "items" => call_method(&value, "items", args, keyword),
+        "select" | "reject" => select(&value, &args, false, name == "select"),
+        "selectattr" | "rejectattr" => select(&value, &args, true, name == "selectattr"),
+        "map" => {
+            let items = value.iterate()?;
+            if let Some(attribute) = keyword.get("attribute") {
+                let default = keyword.get("default");
+                return Ok(Value::List(
+                    items
+                        .iter()
+                        .map(|item| match (get_path(item, attribute), default) {
+                            (Value::Undefined, Some(default)) => default.clone(),
+                            (value, _) => value,
+                        })
+                        .collect(),
+                ));
+            }
+            let (filter, args) = args
+                .split_first()
+                .ok_or_else(|| error("map expects a filter or an attribute"))?;
+            let filter = filter.as_str()?;
+            Ok(Value::List(
+                items
+                    .into_iter()
+                    .map(|item| apply_filter(item, filter, args.to_vec(), keyword.clone()))
+                    .collect::<Result<_>>()?,
+            ))
+        }
+        "unique" => {
+            let case_sensitive =
+                argument(&args, &keyword, 0, "case_sensitive").is_some_and(|b| b.is_truthy());
+            let attribute = argument(&args, &keyword, 1, "attribute");
+            let mut seen = vec![];
+            let mut items = vec![];
+            for item in value.iterate()? {
+                let key = match &attribute {
+                    Some(attribute) => get_path(&item, attribute),
+                    None => item.clone(),
+                };
+                let key = match key {
+                    Value::Str(key) if !case_sensitive => Value::Str(key.to_lowercase()),
+                    key => key,
+                };
+                if !seen.contains(&key) {
+                    seen.push(key);
+                    items.push(item);
+                }
+            }
+            Ok(Value::List(items))
+        }
+        "indent" => {
+            let indentation = match argument(&args, &keyword, 0, "width") {
+                Some(Value::Str(indentation)) => indentation,
+                Some(width) => " ".repeat(width.as_i64().unwrap_or(0).max(0) as usize),
+                None => " ".repeat(4),
+            };
+            let first = argument(&args, &keyword, 1, "first").is_some_and(|b| b.is_truthy());
+            let blank = argument(&args, &keyword, 2, "blank").is_some_and(|b| b.is_truthy());
+            Ok(Value::Str(indent(
+                &value.render(),
+                &indentation,
+                first,
+                blank,
+            )))
+        }
+        "tojson" => {
+            let indent = argument(&args, &keyword, 0, "indent")
+                .and_then(|indent| indent.as_i64())
+                .map(|indent| indent.max(0) as usize);
+            Ok(Value::Str(value.to_json(indent, 0)))
+        }
+        other => Err(error(format!("unknown filter {other:?}"))),
+    }
+}
+
+fn apply_test(value: &Value, name: &str, args: &[Value]) -> Result<bool> {
+    match name {
+        "defined" => Ok(!matches!(value, Value::Undefined)),
+        "undefined" => Ok(matches!(value, Value::Undefined)),
+        "none" => Ok(matches!(value, Value::None)),
+        "boolean" => Ok(matches!(value, Value::Bool(_))),
+        "true" => Ok(matches!(value, Value::Bool(true))),
+        "false" => Ok(matches!(value, Value::Bool(false))),
+        "string" => Ok(matches!(value, Value::Str(_))),
+        "number" => Ok(matches!(value, Value::Int(_) | Value::Float(_))),
+        "integer" => Ok(matches!(value, Value::Int(_))),
+        "float" => Ok(matches!(value, Value::Float(_))),
+        "mapping" => Ok(matches!(value, Value::Map(_))),
+        "sequence" => Ok(matches!(value, Value::List(_) | Value::Str(_))),
+        "iterable" => Ok(matches!(
+            value,
+            Value::List(_) | Value::Str(_) | Value::Map(_)
+        )),
+        "even" | "odd" => match value.as_i64() {
+            Some(number) => Ok((number % 2 == 0) == (name == "even")),
+            None => Err(error(format!("{name} expects an integer"))),
+        },
+        "eq" | "equalto" | "sameas" => match args.first() {
+            Some(other) => Ok(value == other),
+            None => Err(error(format!("{name} expects an argument"))),
+        },
+        other => Err(error(format!("unknown test {other:?}"))),
+    }
+}
+
+/// Renders the chat template with the given variables.
+pub fn render(source: &str, variables: HashMap<String, Value>) -> Result<String> {
+    // Parsing and rendering recurse up to MAX_DEPTH levels, which needs
+    // more stack than dirty schedulers have
+    stacker::grow(STACK_SIZE, || {
+        let nodes = parse(source)?;
+        let mut renderer = Renderer {
+            scopes: vec![variables],
+            macros: HashMap::new(),
+            depth: 0,
+        };
+        let mut output = String::new();
+        renderer.render(&nodes, &mut output)?;
+        Ok(output)
+    })
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Chat templates
+// /////////////////////////////////////////////////////////////////////////////
+
+#[derive(NifTaggedEnum)]
+pub enum ChatTemplateOption {
+    AddGenerationPrompt(bool),
+    ChatTemplate(String),
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn tokenizer_apply_chat_template(
+    tokenizer: ExTokenizersTokenizer,
+    messages: Vec<Value>,
+    options: Vec<ChatTemplateOption>,
+) -> Result<String> {
+    let config = &tokenizer.resource.1;
+    let mut add_generation_prompt = false;
+    let mut template = config.chat_template.clone();
+    for option in options {
+        match option {
+            ChatTemplateOption::AddGenerationPrompt(value) => add_generation_prompt = value,
+            ChatTemplateOption::ChatTemplate(value) => template = Some(value),
+        }
+    }
+    let template = template.ok_or_else(|| {
+        ExTokenizersError::Other("the tokenizer has no chat template".to_string())
+    })?;
+
+    let mut variables = config
+        .special_tokens
+        .iter()
+        .map(|(role, token)| (role.clone(), Value::Str(token.clone())))
+        .collect::<HashMap<_, _>>();
+    variables.insert("messages".to_string(), Value::List(messages));
+    variables.insert(
+        "add_generation_prompt".to_string(),
+        Value::Bool(add_generation_prompt),
+    );
+
+    render(&template, variables)
+}
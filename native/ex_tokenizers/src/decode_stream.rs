@@ -0,0 +1,209 @@
+use std::sync::Mutex;
+
+use rustler::{NifTaggedEnum, ResourceArc};
+
+use crate::error::ExTokenizersError;
+use crate::tokenizer::{DecodeOption, ExTokenizerImpl, ExTokenizersTokenizer};
+use crate::util::OffsetType;
+
+#[derive(Default)]
+struct DecodeState {
+    // Ids decoded again on each step, the ones before `prefix_index`
+    // only give context to the decoder
+    ids: Vec<u32>,
+    // Text already emitted for `ids`
+    prefix: String,
+    prefix_index: usize,
+}
+
+impl DecodeState {
+    /// Adds `id` to the stream and returns the text it completes, if any.
+    ///
+    /// Decoding the ids one by one doesn't work in general, since decoders
+    /// look at the surrounding tokens (to strip the leading space added by
+    /// Metaspace, join WordPiece suffixes, or merge the bytes of ByteLevel
+    /// and byte fallback tokens). Instead, we decode the window of ids since
+    /// the previously emitted token, and emit the text added to what the
+    /// window already decoded to. Text ending with a replacement character
+    /// is held back, as it's an incomplete UTF-8 sequence that the next ids
+    /// may complete.
+    fn step(
+        &mut self,
+        tokenizer: &ExTokenizerImpl,
+        skip_special_tokens: bool,
+        id: u32,
+    ) -> Result<Option<String>, ExTokenizersError> {
+        self.ids.push(id);
+        let text = tokenizer.decode(&self.ids, skip_special_tokens)?;
+        if text.len() <= self.prefix.len() || text.ends_with('\u{FFFD}') {
+            return Ok(None);
+        }
+
+        let new_text = text
+            .strip_prefix(self.prefix.as_str())
+            .ok_or_else(|| {
+                ExTokenizersError::Internal(format!(
+                    "decoded text {text:?} does not start with the previous text {:?}",
+                    self.prefix
+                ))
+            })?
+            .to_string();
+
+        let new_prefix_index = self.ids.len() - self.prefix_index;
+        self.ids.drain(..self.prefix_index);
+        self.prefix = tokenizer.decode(&self.ids, skip_special_tokens)?;
+        self.prefix_index = new_prefix_index;
+
+        Ok(Some(new_text))
+    }
+
+    /// Returns the text held back by `step`, such as an incomplete UTF-8
+    /// sequence at the end of the ids, and resets the stream.
+    fn finish(
+        &mut self,
+        tokenizer: &ExTokenizerImpl,
+        skip_special_tokens: bool,
+    ) -> Result<Option<String>, ExTokenizersError> {
+        let state = std::mem::take(self);
+        let text = tokenizer.decode(&state.ids, skip_special_tokens)?;
+        if text.len() <= state.prefix.len() {
+            return Ok(None);
+        }
+
+        let rest = text.strip_prefix(state.prefix.as_str()).ok_or_else(|| {
+            ExTokenizersError::Internal(format!(
+                "decoded text {text:?} does not start with the previous text {:?}",
+                state.prefix
+            ))
+        })?;
+        Ok(Some(rest.to_string()))
+    }
+}
+
+pub struct ExTokenizersDecodeStreamRef {
+    tokenizer: ExTokenizersTokenizer,
+    skip_special_tokens: bool,
+    state: Mutex<DecodeState>,
+}
+
+#[derive(rustler::NifStruct)]
+#[module = "Tokenizers.DecodeStream"]
+pub struct ExTokenizersDecodeStream {
+    pub resource: ResourceArc<ExTokenizersDecodeStreamRef>,
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Creators
+// /////////////////////////////////////////////////////////////////////////////
+
+#[rustler::nif]
+pub fn decode_stream_new(
+    tokenizer: ExTokenizersTokenizer,
+    options: Vec<DecodeOption>,
+) -> ExTokenizersDecodeStream {
+    let mut skip_special_tokens = true;
+    for option in options {
+        match option {
+            DecodeOption::SkipSpecialTokens(value) => skip_special_tokens = value,
+        }
+    }
+
+    ExTokenizersDecodeStream {
+        resource: ResourceArc::new(ExTokenizersDecodeStreamRef {
+            tokenizer,
+            skip_special_tokens,
+            state: Mutex::new(DecodeState::default()),
+        }),
+    }
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Decoding
+// /////////////////////////////////////////////////////////////////////////////
+
+/// Adds `id` to the stream and returns the text it completes, if any.
+#[rustler::nif]
+pub fn decode_stream_step(
+    stream: ExTokenizersDecodeStream,
+    id: u32,
+) -> Result<Option<String>, ExTokenizersError> {
+    let resource = &stream.resource;
+    resource.state.lock()?.step(
+        &resource.tokenizer.resource.0,
+        resource.skip_special_tokens,
+        id,
+    )
+}
+
+/// Returns the text held back at the end of the stream and resets it.
+#[rustler::nif]
+pub fn decode_stream_finish(
+    stream: ExTokenizersDecodeStream,
+) -> Result<Option<String>, ExTokenizersError> {
+    let resource = &stream.resource;
+    resource
+        .state
+        .lock()?
+        .finish(&resource.tokenizer.resource.0, resource.skip_special_tokens)
+}
+
+#[derive(NifTaggedEnum)]
+pub enum DecodeWithSpansOption {
+    SkipSpecialTokens(bool),
+    OffsetType(OffsetType),
+}
+
+/// Decodes `ids` along with the span of text each id contributed.
+///
+/// The ids are streamed through the decoder, so each span is the text
+/// emitted when its id was added. Ids that don't add any text, such as
+/// skipped special tokens or bytes of an incomplete character, get an
+/// empty span. Trailing bytes that never form a valid character are
+/// attributed to the last id.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn tokenizer_decode_with_spans(
+    tokenizer: ExTokenizersTokenizer,
+    ids: Vec<u32>,
+    options: Vec<DecodeWithSpansOption>,
+) -> Result<(String, Vec<(usize, usize)>), ExTokenizersError> {
+    let mut skip_special_tokens = true;
+    let mut offset_type = OffsetType::Bytes;
+    for option in options {
+        match option {
+            DecodeWithSpansOption::SkipSpecialTokens(value) => skip_special_tokens = value,
+            DecodeWithSpansOption::OffsetType(value) => offset_type = value,
+        }
+    }
+
+    let tokenizer = &tokenizer.resource.0;
+    let mut state = DecodeState::default();
+    let mut text = String::new();
+    let mut spans = Vec::with_capacity(ids.len());
+    let mut char_len = 0;
+    for id in &ids {
+        let start = (text.len(), char_len);
+        if let Some(new_text) = state.step(tokenizer, skip_special_tokens, *id)? {
+            text.push_str(&new_text);
+            char_len += new_text.chars().count();
+        }
+        spans.push(match offset_type {
+            OffsetType::Bytes => (start.0, text.len()),
+            OffsetType::Chars => (start.1, char_len),
+        });
+    }
+
+    let full_text = tokenizer.decode(&ids, skip_special_tokens)?;
+    let rest = full_text.strip_prefix(text.as_str()).ok_or_else(|| {
+        ExTokenizersError::Internal(format!(
+            "decoded text {full_text:?} does not start with the streamed text {text:?}"
+        ))
+    })?;
+    if let Some(span) = spans.last_mut() {
+        span.1 += match offset_type {
+            OffsetType::Bytes => rest.len(),
+            OffsetType::Chars => rest.chars().count(),
+        };
+    }
+
+    Ok((full_text, spans))
+}
@@ -2,7 +2,8 @@ use rustler::NifTaggedEnum;
 use serde::{Deserialize, Serialize};
 use tokenizers::{Decoder, DecoderWrapper};
 
-use crate::{new_info, util::Info, ExTokenizersError};
+use crate::util::{from_json, to_json, Info, JsonOption};
+use crate::{new_info, ExTokenizersError};
 
 pub struct ExTokenizersDecoderRef(pub DecoderWrapper);
 
@@ -135,6 +136,23 @@ fn decoders_info(decoder: ExTokenizersDecoder) -> Info {
     }
 }
 
+///////////////////////////////////////////////////////////////////////////////
+/// Serialization
+///////////////////////////////////////////////////////////////////////////////
+
+#[rustler::nif]
+pub fn decoders_to_json(
+    decoder: ExTokenizersDecoder,
+    options: Vec<JsonOption>,
+) -> Result<String, ExTokenizersError> {
+    to_json(&decoder, options)
+}
+
+#[rustler::nif]
+pub fn decoders_from_json(json: &str) -> Result<ExTokenizersDecoder, ExTokenizersError> {
+    from_json(json)
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Builders
 ///////////////////////////////////////////////////////////////////////////////
@@ -151,7 +169,7 @@ fn decoders_replace(
 ) -> Result<ExTokenizersDecoder, rustler::Error> {
     Ok(ExTokenizersDecoder::new(
         tokenizers::normalizers::Replace::new(pattern, content)
-            .map_err(|_| rustler::Error::BadArg)?,
+            .map_err(|error| ExTokenizersError::InvalidRegex(error.to_string()))?,
     ))
 }
 
@@ -199,7 +217,7 @@ fn decoders_strip(
     left: usize,
     right: usize,
 ) -> Result<ExTokenizersDecoder, rustler::Error> {
-    let content = std::char::from_u32(content).ok_or(rustler::Error::BadArg)?;
+    let content = std::char::from_u32(content).ok_or(ExTokenizersError::InvalidChar)?;
     Ok(ExTokenizersDecoder::new(
         tokenizers::decoders::strip::Strip::new(content, left, right),
     ))
@@ -233,7 +251,8 @@ fn decoders_metaspace(
     for opt in options {
         match opt {
             MetaspaceOption::Replacement(replacement) => {
-                opts.replacement = std::char::from_u32(replacement).ok_or(rustler::Error::BadArg)?
+                opts.replacement =
+                    std::char::from_u32(replacement).ok_or(ExTokenizersError::InvalidChar)?
             }
             MetaspaceOption::PrependScheme(prepend_scheme) => {
                 opts.prepend_scheme = match prepend_scheme {
@@ -1,6 +1,7 @@
-use rustler::{Binary, Env, NifTaggedEnum, ResourceArc};
+use rustler::{Atom, Binary, Env, NewBinary, NifResult, NifTaggedEnum, ResourceArc, Term};
 use tokenizers::Encoding;
 
+use crate::error::ExTokenizersError;
 use crate::util::Direction;
 
 pub struct ExTokenizersEncodingRef(pub Encoding);
@@ -275,6 +276,298 @@ fn slice_u32_to_u8(slice: &[u32]) -> &[u8] {
     unsafe { std::slice::from_raw_parts(slice.as_ptr() as *const u8, slice.len() * 4) }
 }
 
+///////////////////////////////////////////////////////////////////////////////
+/// Binary export
+///////////////////////////////////////////////////////////////////////////////
+
+// Integer type of exported binaries, given as Nx-style `{:u, 8}` tuples
+#[derive(Clone, Copy)]
+pub enum BinaryType {
+    U8,
+    U16,
+    U32,
+    S32,
+    S64,
+}
+
+mod atoms {
+    rustler::atoms! {
+        u,
+        s
+    }
+}
+
+impl<'a> rustler::Decoder<'a> for BinaryType {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let (kind, bits): (Atom, usize) = term.decode()?;
+        match (kind, bits) {
+            (kind, 8) if kind == atoms::u() => Ok(BinaryType::U8),
+            (kind, 16) if kind == atoms::u() => Ok(BinaryType::U16),
+            (kind, 32) if kind == atoms::u() => Ok(BinaryType::U32),
+            (kind, 32) if kind == atoms::s() => Ok(BinaryType::S32),
+            (kind, 64) if kind == atoms::s() => Ok(BinaryType::S64),
+            _ => Err(rustler::Error::BadArg),
+        }
+    }
+}
+
+impl rustler::Encoder for BinaryType {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self {
+            BinaryType::U8 => (atoms::u(), 8),
+            BinaryType::U16 => (atoms::u(), 16),
+            BinaryType::U32 => (atoms::u(), 32),
+            BinaryType::S32 => (atoms::s(), 32),
+            BinaryType::S64 => (atoms::s(), 64),
+        }
+        .encode(env)
+    }
+}
+
+impl BinaryType {
+    fn size(&self) -> usize {
+        match self {
+            BinaryType::U8 => 1,
+            BinaryType::U16 => 2,
+            BinaryType::U32 | BinaryType::S32 => 4,
+            BinaryType::S64 => 8,
+        }
+    }
+
+    fn max(&self) -> u64 {
+        match self {
+            BinaryType::U8 => u8::MAX as u64,
+            BinaryType::U16 => u16::MAX as u64,
+            BinaryType::U32 => u32::MAX as u64,
+            BinaryType::S32 => i32::MAX as u64,
+            BinaryType::S64 => i64::MAX as u64,
+        }
+    }
+}
+
+#[derive(rustler::NifUnitEnum, Clone, Copy)]
+pub enum Endianness {
+    Native,
+    Little,
+    Big,
+}
+
+#[derive(Clone, Copy)]
+pub struct BinaryFormat {
+    binary_type: BinaryType,
+    endianness: Endianness,
+}
+
+impl Default for BinaryFormat {
+    fn default() -> Self {
+        Self {
+            binary_type: BinaryType::U32,
+            endianness: Endianness::Native,
+        }
+    }
+}
+
+impl BinaryFormat {
+    /// Writes `value` into `out`, which must be exactly `binary_type.size()` long.
+    fn write(&self, value: u64, out: &mut [u8]) -> Result<(), ExTokenizersError> {
+        if value > self.binary_type.max() {
+            return Err(ExTokenizersError::Other(format!(
+                "value {value} does not fit in the requested binary type"
+            )));
+        }
+        // Values are non-negative, so signed types share the unsigned representation
+        let size = self.binary_type.size();
+        match self.endianness {
+            Endianness::Big => out.copy_from_slice(&value.to_be_bytes()[8 - size..]),
+            Endianness::Little => out.copy_from_slice(&value.to_le_bytes()[..size]),
+            Endianness::Native if cfg!(target_endian = "big") => {
+                out.copy_from_slice(&value.to_be_bytes()[8 - size..])
+            }
+            Endianness::Native => out.copy_from_slice(&value.to_le_bytes()[..size]),
+        }
+        Ok(())
+    }
+
+    /// Builds a binary out of `count` values in this format.
+    fn export<'a, I>(
+        &self,
+        env: Env<'a>,
+        count: usize,
+        values: I,
+    ) -> Result<Binary<'a>, ExTokenizersError>
+    where
+        I: Iterator<Item = u64>,
+    {
+        let size = self.binary_type.size();
+        let mut binary = NewBinary::new(env, count * size);
+        for (value, out) in values.zip(binary.as_mut_slice().chunks_exact_mut(size)) {
+            self.write(value, out)?;
+        }
+        Ok(binary.into())
+    }
+}
+
+#[derive(NifTaggedEnum)]
+pub enum BinaryOption {
+    Type(BinaryType),
+    Endianness(Endianness),
+}
+
+fn parse_binary_options(opts: Vec<BinaryOption>) -> BinaryFormat {
+    let mut format = BinaryFormat::default();
+    for opt in opts {
+        match opt {
+            BinaryOption::Type(binary_type) => format.binary_type = binary_type,
+            BinaryOption::Endianness(endianness) => format.endianness = endianness,
+        }
+    }
+    format
+}
+
+fn export_u32<'a>(
+    env: Env<'a>,
+    values: &[u32],
+    opts: Vec<BinaryOption>,
+) -> Result<Binary<'a>, ExTokenizersError> {
+    parse_binary_options(opts).export(env, values.len(), values.iter().map(|v| *v as u64))
+}
+
+#[rustler::nif]
+pub fn encoding_get_ids_binary(
+    env: Env,
+    encoding: ExTokenizersEncoding,
+    opts: Vec<BinaryOption>,
+) -> Result<Binary, ExTokenizersError> {
+    export_u32(env, encoding.resource.0.get_ids(), opts)
+}
+
+#[rustler::nif]
+pub fn encoding_get_type_ids_binary(
+    env: Env,
+    encoding: ExTokenizersEncoding,
+    opts: Vec<BinaryOption>,
+) -> Result<Binary, ExTokenizersError> {
+    export_u32(env, encoding.resource.0.get_type_ids(), opts)
+}
+
+#[rustler::nif]
+pub fn encoding_get_attention_mask_binary(
+    env: Env,
+    encoding: ExTokenizersEncoding,
+    opts: Vec<BinaryOption>,
+) -> Result<Binary, ExTokenizersError> {
+    export_u32(env, encoding.resource.0.get_attention_mask(), opts)
+}
+
+#[rustler::nif]
+pub fn encoding_get_special_tokens_mask_binary(
+    env: Env,
+    encoding: ExTokenizersEncoding,
+    opts: Vec<BinaryOption>,
+) -> Result<Binary, ExTokenizersError> {
+    export_u32(env, encoding.resource.0.get_special_tokens_mask(), opts)
+}
+
+#[derive(rustler::NifMap)]
+pub struct BatchBinaries<'a> {
+    ids: Binary<'a>,
+    type_ids: Binary<'a>,
+    attention_mask: Binary<'a>,
+    special_tokens_mask: Binary<'a>,
+    offsets: Binary<'a>,
+    shape: (usize, usize),
+}
+
+#[derive(NifTaggedEnum)]
+pub enum BatchFieldType {
+    Ids(BinaryType),
+    TypeIds(BinaryType),
+    AttentionMask(BinaryType),
+    SpecialTokensMask(BinaryType),
+    Offsets(BinaryType),
+}
+
+#[derive(NifTaggedEnum)]
+pub enum BatchBinaryOption {
+    Types(Vec<BatchFieldType>),
+    Endianness(Endianness),
+}
+
+/// Exports a batch of encodings as one binary per field, laid out as
+/// `{batch, seq_len}` (`{batch, seq_len, 2}` for offsets) row-major tensors.
+pub fn batch_binaries<'a>(
+    env: Env<'a>,
+    encodings: &[Encoding],
+    opts: Vec<BatchBinaryOption>,
+) -> Result<BatchBinaries<'a>, ExTokenizersError> {
+    let mut types: Vec<BatchFieldType> = Vec::new();
+    let mut endianness = Endianness::Native;
+    for opt in opts {
+        match opt {
+            BatchBinaryOption::Types(value) => types = value,
+            BatchBinaryOption::Endianness(value) => endianness = value,
+        }
+    }
+
+    let mut ids = BinaryFormat::default();
+    let mut type_ids = BinaryFormat::default();
+    let mut attention_mask = BinaryFormat::default();
+    let mut special_tokens_mask = BinaryFormat::default();
+    let mut offsets = BinaryFormat::default();
+    for field_type in types {
+        match field_type {
+            BatchFieldType::Ids(value) => ids.binary_type = value,
+            BatchFieldType::TypeIds(value) => type_ids.binary_type = value,
+            BatchFieldType::AttentionMask(value) => attention_mask.binary_type = value,
+            BatchFieldType::SpecialTokensMask(value) => special_tokens_mask.binary_type = value,
+            BatchFieldType::Offsets(value) => offsets.binary_type = value,
+        }
+    }
+    for format in [
+        &mut ids,
+        &mut type_ids,
+        &mut attention_mask,
+        &mut special_tokens_mask,
+        &mut offsets,
+    ] {
+        format.endianness = endianness;
+    }
+
+    let seq_len = encodings.first().map_or(0, |encoding| encoding.len());
+    if encodings.iter().any(|encoding| encoding.len() != seq_len) {
+        return Err(ExTokenizersError::Other(String::from(
+            "all encodings in the batch must have the same length, consider configuring padding",
+        )));
+    }
+    let size = encodings.len() * seq_len;
+
+    let export = |format: BinaryFormat, field: fn(&Encoding) -> &[u32]| {
+        format.export(
+            env,
+            size,
+            encodings
+                .iter()
+                .flat_map(|encoding| field(encoding).iter().map(|v| *v as u64)),
+        )
+    };
+
+    Ok(BatchBinaries {
+        ids: export(ids, Encoding::get_ids)?,
+        type_ids: export(type_ids, Encoding::get_type_ids)?,
+        attention_mask: export(attention_mask, Encoding::get_attention_mask)?,
+        special_tokens_mask: export(special_tokens_mask, Encoding::get_special_tokens_mask)?,
+        offsets: offsets.export(
+            env,
+            size * 2,
+            encodings
+                .iter()
+                .flat_map(|encoding| encoding.get_offsets())
+                .flat_map(|(start, end)| [*start as u64, *end as u64]),
+        )?,
+        shape: (encodings.len(), seq_len),
+    })
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Encoding transformations
 ///////////////////////////////////////////////////////////////////////////////
@@ -1,20 +1,50 @@
-use rustler::{Encoder, Env, Term};
+use rustler::{Atom, Encoder, Env, Term};
 use std::io;
+use std::sync::PoisonError;
 use thiserror::Error;
+use tokenizers::models::{bpe, unigram, wordlevel, wordpiece};
 
 rustler::atoms! {
     ok,
-    error
+    error,
+    __struct__,
+    __exception__,
+    kind,
+    message,
+    details,
+    line,
+    column,
+    invalid_char,
+    invalid_regex,
+    invalid_template,
+    unknown_token,
+    invalid_model_file,
+    io,
+    json_parse,
+    tokenizer,
+    internal,
+    other,
+    unknown,
 }
 
 #[derive(Error, Debug)]
 pub enum ExTokenizersError {
     #[error("Invalid Char")]
     InvalidChar,
-    #[error("Tokenizer Error")]
+    #[error("Invalid regex: {0}")]
+    InvalidRegex(String),
+    #[error("Invalid template: {0}")]
+    InvalidTemplate(String),
+    #[error("Unknown token: {0}")]
+    UnknownToken(String),
+    #[error("Invalid model file: {0}")]
+    InvalidModelFile(String),
+    #[error("{0}")]
     Tokenizer(#[from] Box<dyn std::error::Error + Send + Sync>),
-    #[error("IO Error")]
+    #[error("{0}")]
     Io(#[from] io::Error),
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
     #[error("Internal Error: {0}")]
     Internal(String),
     #[error("Other error: {0}")]
@@ -23,8 +53,104 @@ pub enum ExTokenizersError {
     Unknown(#[from] anyhow::Error),
 }
 
+impl ExTokenizersError {
+    /// Returns the error kind and any additional details.
+    ///
+    /// Errors coming from the tokenizers library are boxed, so we downcast
+    /// them to the known error types to recover their kind.
+    fn kind(&self) -> (Atom, Vec<(Atom, usize)>) {
+        match self {
+            ExTokenizersError::InvalidChar => (invalid_char(), vec![]),
+            ExTokenizersError::InvalidRegex(_) => (invalid_regex(), vec![]),
+            ExTokenizersError::InvalidTemplate(_) => (invalid_template(), vec![]),
+            ExTokenizersError::UnknownToken(_) => (unknown_token(), vec![]),
+            ExTokenizersError::InvalidModelFile(_) => (invalid_model_file(), vec![]),
+            ExTokenizersError::Tokenizer(error) => tokenizer_error_kind(error.as_ref()),
+            ExTokenizersError::Io(_) => (io(), vec![]),
+            ExTokenizersError::Json(error) => json_error_kind(error),
+            ExTokenizersError::Internal(_) => (internal(), vec![]),
+            ExTokenizersError::Other(_) => (other(), vec![]),
+            ExTokenizersError::Unknown(_) => (unknown(), vec![]),
+        }
+    }
+}
+
+fn json_error_kind(error: &serde_json::Error) -> (Atom, Vec<(Atom, usize)>) {
+    (
+        json_parse(),
+        vec![(line(), error.line()), (column(), error.column())],
+    )
+}
+
+fn tokenizer_error_kind(error: &(dyn std::error::Error + 'static)) -> (Atom, Vec<(Atom, usize)>) {
+    if let Some(error) = error.downcast_ref::<serde_json::Error>() {
+        json_error_kind(error)
+    } else if error.is::<io::Error>() {
+        (io(), vec![])
+    } else if let Some(error) = error.downcast_ref::<bpe::Error>() {
+        match error {
+            bpe::Error::Io(_) => (io(), vec![]),
+            bpe::Error::JsonError(error) => json_error_kind(error),
+            bpe::Error::MergeTokenOutOfVocabulary(_) | bpe::Error::UnkTokenOutOfVocabulary(_) => {
+                (unknown_token(), vec![])
+            }
+            bpe::Error::BadMerges(merge_line) => (tokenizer(), vec![(line(), *merge_line)]),
+            _ => (tokenizer(), vec![]),
+        }
+    } else if error.is::<wordpiece::Error>() || error.is::<unigram::UnigramError>() {
+        (unknown_token(), vec![])
+    } else if let Some(wordlevel::Error::MissingUnkToken) = error.downcast_ref::<wordlevel::Error>()
+    {
+        (unknown_token(), vec![])
+    } else {
+        (tokenizer(), vec![])
+    }
+}
+
+impl<T> From<PoisonError<T>> for ExTokenizersError {
+    fn from(_: PoisonError<T>) -> Self {
+        ExTokenizersError::Internal(String::from(
+            "lock poisoned by a panic in a previous operation",
+        ))
+    }
+}
+
+/// Encodes as a `Tokenizers.Error` exception struct.
 impl Encoder for ExTokenizersError {
     fn encode<'b>(&self, env: Env<'b>) -> Term<'b> {
-        format!("{self:?}").encode(env)
+        let (error_kind, error_details) = self.kind();
+        let error_details = Term::map_from_pairs(
+            env,
+            &error_details
+                .iter()
+                .map(|(key, value)| (key.encode(env), value.encode(env)))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        Term::map_from_pairs(
+            env,
+            &[
+                (
+                    __struct__().encode(env),
+                    Atom::from_str(env, "Elixir.Tokenizers.Error")
+                        .unwrap()
+                        .encode(env),
+                ),
+                (__exception__().encode(env), true.encode(env)),
+                (kind().encode(env), error_kind.encode(env)),
+                (message().encode(env), self.to_string().encode(env)),
+                (details().encode(env), error_details),
+            ],
+        )
+        .unwrap()
+    }
+}
+
+/// Raises the error as an exception, used by NIFs that return the
+/// created struct directly rather than an `{:ok, _}` tuple.
+impl From<ExTokenizersError> for rustler::Error {
+    fn from(error: ExTokenizersError) -> Self {
+        rustler::Error::RaiseTerm(Box::new(error))
     }
 }
@@ -1,4 +1,6 @@
 mod added_token;
+mod chat_template;
+mod decode_stream;
 mod decoders;
 mod encoding;
 mod error;
@@ -6,11 +8,17 @@ mod models;
 mod normalizers;
 mod post_processors;
 mod pre_tokenizers;
+mod sentencepiece;
+mod tiktoken;
 mod tokenizer;
+mod tokenizer_config;
 mod trainers;
+mod training_job;
 mod util;
 
 use added_token::*;
+use chat_template::*;
+use decode_stream::*;
 use decoders::*;
 use encoding::*;
 use models::*;
@@ -18,21 +26,27 @@ use normalizers::*;
 use post_processors::*;
 use pre_tokenizers::*;
 use rustler::{Env, Term};
+use sentencepiece::*;
+use tiktoken::*;
 use tokenizer::*;
 use trainers::*;
+use training_job::*;
 
 pub use error::ExTokenizersError;
 
 fn on_load(env: Env, _info: Term) -> bool {
     rustler::resource!(ExTokenizersAddedTokenRef, env);
     rustler::resource!(ExTokenizersDecoderRef, env);
+    rustler::resource!(ExTokenizersDecodeStreamRef, env);
     rustler::resource!(ExTokenizersTokenizerRef, env);
+    rustler::resource!(ExTokenizersIteratorTrainingRef, env);
     rustler::resource!(ExTokenizersEncodingRef, env);
     rustler::resource!(ExTokenizersTrainerRef, env);
     rustler::resource!(ExTokenizersModelRef, env);
     rustler::resource!(ExTokenizersNormalizerRef, env);
     rustler::resource!(ExTokenizersPostProcessorRef, env);
     rustler::resource!(ExTokenizersPreTokenizerRef, env);
+    rustler::resource!(ExTokenizersTrainingJobRef, env);
     true
 }
 
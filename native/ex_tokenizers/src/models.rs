@@ -1,18 +1,19 @@
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
-use std::sync::RwLock;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use rustler::NifTaggedEnum;
 use serde::{Deserialize, Serialize};
-use tokenizers::models::bpe::BpeBuilder;
+use tokenizers::models::bpe::{BpeBuilder, BPE};
 use tokenizers::models::wordlevel::WordLevelBuilder;
 use tokenizers::models::wordpiece::WordPieceBuilder;
 use tokenizers::{Model, ModelWrapper};
 
 use crate::error::ExTokenizersError;
+use crate::new_info;
 use crate::trainers::ExTokenizersTrainer;
-use crate::{new_info, util::Info};
+use crate::util::{from_json, to_json, Info, JsonOption};
 
 pub struct ExTokenizersModelRef(pub RwLock<ModelWrapper>);
 
@@ -27,7 +28,10 @@ impl Serialize for ExTokenizersModel {
     where
         S: serde::Serializer,
     {
-        self.resource.0.read().unwrap().serialize(serializer)
+        self.resource
+            .read()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
     }
 }
 
@@ -54,31 +58,31 @@ impl tokenizers::Model for ExTokenizersModel {
     type Trainer = ExTokenizersTrainer;
 
     fn tokenize(&self, sequence: &str) -> tokenizers::Result<Vec<tokenizers::Token>> {
-        self.resource.0.read().unwrap().tokenize(sequence)
+        self.resource.read()?.tokenize(sequence)
     }
 
     fn token_to_id(&self, token: &str) -> Option<u32> {
-        self.resource.0.read().unwrap().token_to_id(token)
+        self.resource.read_infallible().token_to_id(token)
     }
 
     fn id_to_token(&self, id: u32) -> Option<String> {
-        self.resource.0.read().unwrap().id_to_token(id)
+        self.resource.read_infallible().id_to_token(id)
     }
 
     fn get_vocab(&self) -> HashMap<String, u32> {
-        self.resource.0.read().unwrap().get_vocab()
+        self.resource.read_infallible().get_vocab()
     }
 
     fn get_vocab_size(&self) -> usize {
-        self.resource.0.read().unwrap().get_vocab_size()
+        self.resource.read_infallible().get_vocab_size()
     }
 
     fn save(&self, folder: &Path, name: Option<&str>) -> tokenizers::Result<Vec<PathBuf>> {
-        self.resource.0.read().unwrap().save(folder, name)
+        self.resource.read()?.save(folder, name)
     }
 
     fn get_trainer(&self) -> Self::Trainer {
-        ExTokenizersTrainer::new(self.resource.0.read().unwrap().get_trainer())
+        ExTokenizersTrainer::new(self.resource.read_infallible().get_trainer())
     }
 }
 
@@ -89,6 +93,22 @@ impl ExTokenizersModelRef {
     {
         Self(RwLock::new(data.into()))
     }
+
+    pub fn read(&self) -> Result<RwLockReadGuard<'_, ModelWrapper>, ExTokenizersError> {
+        Ok(self.0.read()?)
+    }
+
+    pub fn write(&self) -> Result<RwLockWriteGuard<'_, ModelWrapper>, ExTokenizersError> {
+        Ok(self.0.write()?)
+    }
+
+    /// Same as `read`, for trait methods that cannot return an error.
+    ///
+    /// Models are only written to while training a fresh copy, so even a
+    /// poisoned lock holds a consistent model.
+    fn read_infallible(&self) -> RwLockReadGuard<'_, ModelWrapper> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
 impl ExTokenizersModel {
@@ -102,6 +122,54 @@ impl ExTokenizersModel {
     }
 }
 
+/// A token as `{id, value, {start, end}}`, with byte offsets into the word.
+type TokenTuple = (u32, String, (usize, usize));
+
+/// Returns the merges of a BPE model, ordered by rank.
+///
+/// `BPE` doesn't expose its merges, so they are read back from the
+/// serialized model, where each merge is a single space-separated string.
+fn bpe_merges(model: &BPE) -> Result<Vec<(String, String)>, ExTokenizersError> {
+    let value = serde_json::to_value(model).map_err(anyhow::Error::from)?;
+    let merges = value["merges"]
+        .as_array()
+        .ok_or_else(|| ExTokenizersError::Internal("BPE merges are missing".to_string()))?;
+    merges
+        .iter()
+        .map(|merge| {
+            merge
+                .as_str()
+                .and_then(|merge| split_bpe_merge(model, merge))
+                .map(|(left, right)| (left.to_string(), right.to_string()))
+                .ok_or_else(|| ExTokenizersError::Internal(format!("Invalid BPE merge: {merge}")))
+        })
+        .collect()
+}
+
+/// Splits a serialized merge into its pair of tokens.
+///
+/// When the tokens contain spaces themselves, the split is ambiguous, so
+/// we pick the first one where both tokens and the token they merge into
+/// are in the vocabulary.
+fn split_bpe_merge<'a>(model: &BPE, merge: &'a str) -> Option<(&'a str, &'a str)> {
+    let prefix = model.continuing_subword_prefix.as_deref().unwrap_or("");
+    let in_vocab = |&(left, right): &(&str, &str)| {
+        let merged = format!("{left}{}", right.strip_prefix(prefix).unwrap_or(right));
+        [left, right, &merged]
+            .iter()
+            .all(|token| model.token_to_id(token).is_some())
+    };
+    let splits = merge
+        .match_indices(' ')
+        .map(|(index, _)| (&merge[..index], &merge[index + 1..]))
+        .collect::<Vec<_>>();
+    splits
+        .iter()
+        .find(|split| in_vocab(split))
+        .or(splits.first())
+        .copied()
+}
+
 #[derive(NifTaggedEnum)]
 pub enum ModelSaveOption {
     Prefix(String),
@@ -126,27 +194,58 @@ pub fn models_save(
 
     Ok(model
         .resource
-        .0
-        .read()
-        .unwrap()
+        .read()?
         .save(Path::new(&folder), opts.prefix.as_deref())?
         .iter()
-        .map(|path| {
-            path.to_str()
-                // Unwraping here, because we are sure that paths are valid
-                .unwrap()
-                .to_owned()
-        })
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Tokenization
+///////////////////////////////////////////////////////////////////////////////
+
+#[rustler::nif]
+pub fn models_tokenize(
+    model: ExTokenizersModel,
+    word: &str,
+) -> Result<Vec<TokenTuple>, ExTokenizersError> {
+    Ok(model
+        .resource
+        .read()?
+        .tokenize(word)?
+        .into_iter()
+        .map(|token| (token.id, token.value, token.offsets))
         .collect())
 }
 
+#[rustler::nif]
+pub fn models_token_to_id(model: ExTokenizersModel, token: &str) -> Option<u32> {
+    model.token_to_id(token)
+}
+
+#[rustler::nif]
+pub fn models_id_to_token(model: ExTokenizersModel, id: u32) -> Option<String> {
+    model.id_to_token(id)
+}
+
+#[rustler::nif]
+pub fn models_get_vocab(model: ExTokenizersModel) -> HashMap<String, u32> {
+    model.get_vocab()
+}
+
+#[rustler::nif]
+pub fn models_get_vocab_size(model: ExTokenizersModel) -> usize {
+    model.get_vocab_size()
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Inspection
 ///////////////////////////////////////////////////////////////////////////////
 
 #[rustler::nif]
-pub fn models_info(model: ExTokenizersModel) -> Info {
-    match &model.resource.0.read().unwrap().deref() {
+pub fn models_info(model: ExTokenizersModel) -> Result<Info, rustler::Error> {
+    Ok(match &model.resource.read()?.deref() {
         ModelWrapper::BPE(model) => new_info! {
             model_type: "bpe",
             dropout: model.dropout,
@@ -171,7 +270,24 @@ pub fn models_info(model: ExTokenizersModel) -> Info {
             min_score: model.min_score,
             byte_fallback: model.byte_fallback()
         },
-    }
+    })
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Serialization
+///////////////////////////////////////////////////////////////////////////////
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn models_to_json(
+    model: ExTokenizersModel,
+    options: Vec<JsonOption>,
+) -> Result<String, ExTokenizersError> {
+    to_json(&model, options)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn models_from_json(json: &str) -> Result<ExTokenizersModel, ExTokenizersError> {
+    from_json(json)
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -240,6 +356,18 @@ pub fn models_bpe_from_file(
     Ok(ExTokenizersModel::new(model))
 }
 
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn models_bpe_merges(
+    model: ExTokenizersModel,
+) -> Result<Vec<(String, String)>, ExTokenizersError> {
+    match model.resource.read()?.deref() {
+        ModelWrapper::BPE(bpe) => bpe_merges(bpe),
+        _ => Err(ExTokenizersError::Other(String::from(
+            "merges are only available for BPE models",
+        ))),
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// WordPiece
 ///////////////////////////////////////////////////////////////////////////////
@@ -371,26 +499,21 @@ pub fn models_unigram_init(
     vocab: Vec<(String, f64)>,
     options: Vec<UnigramOption>,
 ) -> Result<ExTokenizersModel, ExTokenizersError> {
-    let unk_id = match options
-        .iter()
-        .find(|opt| matches!(opt, UnigramOption::UnkId(_)))
-        .unwrap()
-    {
-        UnigramOption::UnkId(unk_id) => Some(*unk_id),
-        _ => None,
-    };
-
-    let byte_fallback = match options
-        .iter()
-        .find(|opt| matches!(opt, UnigramOption::ByteFallback(_)))
-        .unwrap()
-    {
-        UnigramOption::ByteFallback(byte_fallback) => *byte_fallback,
-        _ => false,
+    struct Opts {
+        unk_id: Option<usize>,
+        byte_fallback: bool,
+    }
+    let mut opts = Opts {
+        unk_id: None,
+        byte_fallback: false,
     };
+    options.into_iter().for_each(|option| match option {
+        UnigramOption::UnkId(unk_id) => opts.unk_id = Some(unk_id),
+        UnigramOption::ByteFallback(byte_fallback) => opts.byte_fallback = byte_fallback,
+    });
 
     Ok(ExTokenizersModel::new(
-        tokenizers::models::unigram::Unigram::from(vocab, unk_id, byte_fallback)?,
+        tokenizers::models::unigram::Unigram::from(vocab, opts.unk_id, opts.byte_fallback)?,
     ))
 }
 
@@ -400,3 +523,15 @@ pub fn models_unigram_empty() -> Result<ExTokenizersModel, ExTokenizersError> {
         tokenizers::models::unigram::Unigram::default(),
     ))
 }
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn models_unigram_vocab(
+    model: ExTokenizersModel,
+) -> Result<Vec<(String, f64)>, ExTokenizersError> {
+    match model.resource.read()?.deref() {
+        ModelWrapper::Unigram(unigram) => Ok(unigram.iter().cloned().collect()),
+        _ => Err(ExTokenizersError::Other(String::from(
+            "scores are only available for Unigram models",
+        ))),
+    }
+}
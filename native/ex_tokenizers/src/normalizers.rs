@@ -1,8 +1,10 @@
-use crate::{new_info, util::Info, ExTokenizersError};
+use crate::util::{from_json, serialized_config, to_json, Info, JsonOption};
+use crate::{new_info, ExTokenizersError};
 use rustler::NifTaggedEnum;
 use serde::{Deserialize, Serialize};
 use tokenizers::{
-    normalizers::replace::ReplacePattern, NormalizedString, Normalizer, NormalizerWrapper,
+    normalizers::{precompiled::Precompiled, replace::ReplacePattern},
+    NormalizedString, Normalizer, NormalizerWrapper,
 };
 
 pub struct ExTokenizersNormalizerRef(pub NormalizerWrapper);
@@ -82,13 +84,31 @@ fn normalizers_normalize(
 // /////////////////////////////////////////////////////////////////////////////
 
 #[rustler::nif]
-fn normalizers_info(normalizer: ExTokenizersNormalizer) -> Info {
-    match normalizer.resource.0 {
-        NormalizerWrapper::BertNormalizer(_) => new_info!(
-            normalizer_type: "BertNormalizer"
+fn normalizers_info(normalizer: ExTokenizersNormalizer) -> Result<Info, rustler::Error> {
+    Ok(normalizer_info(&normalizer.resource.0)?)
+}
+
+fn normalizer_info(normalizer: &NormalizerWrapper) -> Result<Info, ExTokenizersError> {
+    // Replace doesn't expose its configuration, so we read it back from
+    // its serialized form
+    #[derive(Deserialize)]
+    struct ReplaceConfig {
+        pattern: ReplacePattern,
+        content: String,
+    }
+
+    Ok(match normalizer {
+        NormalizerWrapper::BertNormalizer(bert) => new_info!(
+            normalizer_type: "BertNormalizer",
+            clean_text: bert.clean_text,
+            handle_chinese_chars: bert.handle_chinese_chars,
+            strip_accents: bert.strip_accents,
+            lowercase: bert.lowercase
         ),
-        NormalizerWrapper::StripNormalizer(_) => new_info!(
-            normalizer_type: "StripNormalizer"
+        NormalizerWrapper::StripNormalizer(strip) => new_info!(
+            normalizer_type: "StripNormalizer",
+            left: strip.strip_left,
+            right: strip.strip_right
         ),
         NormalizerWrapper::StripAccents(_) => new_info!(
             normalizer_type: "StripAccents"
@@ -105,8 +125,13 @@ fn normalizers_info(normalizer: ExTokenizersNormalizer) -> Info {
         NormalizerWrapper::NFKD(_) => new_info!(
             normalizer_type: "NFKD"
         ),
-        NormalizerWrapper::Sequence(_) => new_info!(
-            normalizer_type: "Sequence"
+        NormalizerWrapper::Sequence(sequence) => new_info!(
+            normalizer_type: "Sequence",
+            normalizers: sequence
+                .get_normalizers()
+                .iter()
+                .map(normalizer_info)
+                .collect::<Result<Vec<_>, _>>()?
         ),
         NormalizerWrapper::Lowercase(_) => new_info!(
             normalizer_type: "Lowercase"
@@ -117,13 +142,36 @@ fn normalizers_info(normalizer: ExTokenizersNormalizer) -> Info {
         NormalizerWrapper::Precompiled(_) => new_info!(
             normalizer_type: "Precompiled"
         ),
-        NormalizerWrapper::Replace(_) => new_info!(
-            normalizer_type: "Replace"
-        ),
-        NormalizerWrapper::Prepend(_) => new_info!(
-            normalizer_type: "Prepend"
+        NormalizerWrapper::Replace(replace) => {
+            let config: ReplaceConfig = serialized_config(replace)?;
+            new_info!(
+                normalizer_type: "Replace",
+                pattern: LocalReplacePattern::from(config.pattern),
+                content: config.content
+            )
+        }
+        NormalizerWrapper::Prepend(prepend) => new_info!(
+            normalizer_type: "Prepend",
+            prepend: prepend.prepend.clone()
         ),
-    }
+    })
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Serialization
+// /////////////////////////////////////////////////////////////////////////////
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn normalizers_to_json(
+    normalizer: ExTokenizersNormalizer,
+    options: Vec<JsonOption>,
+) -> Result<String, ExTokenizersError> {
+    to_json(&normalizer, options)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn normalizers_from_json(json: &str) -> Result<ExTokenizersNormalizer, ExTokenizersError> {
+    from_json(json)
 }
 
 // /////////////////////////////////////////////////////////////////////////////
@@ -249,6 +297,15 @@ pub enum LocalReplacePattern {
     Regex(String),
 }
 
+impl From<ReplacePattern> for LocalReplacePattern {
+    fn from(value: ReplacePattern) -> Self {
+        match value {
+            ReplacePattern::String(pattern) => LocalReplacePattern::String(pattern),
+            ReplacePattern::Regex(pattern) => LocalReplacePattern::Regex(pattern),
+        }
+    }
+}
+
 #[rustler::nif]
 pub fn normalizers_replace(
     pattern: LocalReplacePattern,
@@ -261,7 +318,7 @@ pub fn normalizers_replace(
 
     Ok(ExTokenizersNormalizer::new(
         tokenizers::normalizers::replace::Replace::new(final_pattern, content)
-            .map_err(|_| rustler::Error::BadArg)?,
+            .map_err(|error| ExTokenizersError::InvalidRegex(error.to_string()))?,
     ))
 }
 
@@ -270,10 +327,23 @@ pub fn normalizers_nmt() -> ExTokenizersNormalizer {
     ExTokenizersNormalizer::new(tokenizers::normalizers::unicode::Nmt)
 }
 
+/// Parses a SentencePiece precompiled charsmap.
+///
+/// The charsmap starts with the byte size of its trie, which upstream
+/// preallocates without checking it against the data, so a corrupted
+/// size aborts the VM instead of returning an error.
+pub fn precompiled(data: &[u8]) -> Result<Precompiled, ExTokenizersError> {
+    match data.first_chunk::<4>() {
+        Some(trie_size) if u32::from_le_bytes(*trie_size) as usize <= data.len() - 4 => {
+            Ok(Precompiled::from(data).map_err(anyhow::Error::from)?)
+        }
+        _ => Err(ExTokenizersError::Other(String::from(
+            "Cannot parse precompiled_charsmap",
+        ))),
+    }
+}
+
 #[rustler::nif]
 pub fn normalizers_precompiled(data: Vec<u8>) -> Result<ExTokenizersNormalizer, ExTokenizersError> {
-    Ok(ExTokenizersNormalizer::new(
-        tokenizers::normalizers::precompiled::Precompiled::from(&data)
-            .map_err(anyhow::Error::from)?,
-    ))
+    Ok(ExTokenizersNormalizer::new(precompiled(&data)?))
 }
@@ -1,8 +1,12 @@
+use std::collections::HashMap;
+
 use rustler::NifTaggedEnum;
 use serde::{Deserialize, Serialize};
+use tokenizers::processors::template::{Piece, Sequence, TemplateProcessing};
 use tokenizers::{Encoding, PostProcessorWrapper};
 
-use crate::{new_info, util::Info};
+use crate::util::{from_json, serialized_config, to_json, Info, JsonOption};
+use crate::{new_info, ExTokenizersError};
 
 pub struct ExTokenizersPostProcessorRef(pub PostProcessorWrapper);
 
@@ -82,14 +86,170 @@ type ProcessorPair = (String, u32);
 // / Inspection
 // /////////////////////////////////////////////////////////////////////////////
 #[rustler::nif]
-fn post_processors_info(post_processor: ExTokenizersPostProcessor) -> Info {
-    match &post_processor.resource.0 {
-        PostProcessorWrapper::Roberta(_) => new_info![post_processor_type: "roberta"],
-        PostProcessorWrapper::Bert(_) => new_info![post_processor_type: "bert"],
-        PostProcessorWrapper::ByteLevel(_) => new_info![post_processor_type: "byte_level"],
-        PostProcessorWrapper::Template(_) => new_info![post_processor_type: "template"],
-        PostProcessorWrapper::Sequence(_) => new_info![post_processor_type: "sequence"],
+fn post_processors_info(post_processor: ExTokenizersPostProcessor) -> Result<Info, rustler::Error> {
+    Ok(post_processor_info(&post_processor.resource.0)?)
+}
+
+fn post_processor_info(post_processor: &PostProcessorWrapper) -> Result<Info, ExTokenizersError> {
+    // Apart from ByteLevel, post-processors don't expose their
+    // configuration, so we read it back from their serialized form
+    #[derive(Deserialize)]
+    struct BertConfig {
+        sep: ProcessorPair,
+        cls: ProcessorPair,
+    }
+
+    #[derive(Deserialize)]
+    struct RobertaConfig {
+        sep: ProcessorPair,
+        cls: ProcessorPair,
+        trim_offsets: bool,
+        add_prefix_space: bool,
+    }
+
+    #[derive(Deserialize)]
+    struct SpecialTokenConfig {
+        ids: Vec<u32>,
+        tokens: Vec<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct TemplateConfig {
+        single: Vec<Piece>,
+        pair: Vec<Piece>,
+        special_tokens: HashMap<String, SpecialTokenConfig>,
+    }
+
+    #[derive(Deserialize)]
+    struct SequenceConfig {
+        processors: Vec<PostProcessorWrapper>,
     }
+
+    Ok(match post_processor {
+        PostProcessorWrapper::Roberta(roberta) => {
+            let config: RobertaConfig = serialized_config(roberta)?;
+            new_info![
+                post_processor_type: "roberta",
+                sep: config.sep,
+                cls: config.cls,
+                trim_offsets: config.trim_offsets,
+                add_prefix_space: config.add_prefix_space
+            ]
+        }
+        PostProcessorWrapper::Bert(bert) => {
+            let config: BertConfig = serialized_config(bert)?;
+            new_info![
+                post_processor_type: "bert",
+                sep: config.sep,
+                cls: config.cls
+            ]
+        }
+        PostProcessorWrapper::ByteLevel(byte_level) => new_info![
+            post_processor_type: "byte_level",
+            add_prefix_space: byte_level.add_prefix_space,
+            trim_offsets: byte_level.trim_offsets,
+            use_regex: byte_level.use_regex
+        ],
+        PostProcessorWrapper::Template(template) => {
+            let config: TemplateConfig = serialized_config(template)?;
+            new_info![
+                post_processor_type: "template",
+                single: template_to_string(&config.single),
+                pair: template_to_string(&config.pair),
+                special_tokens: config
+                    .special_tokens
+                    .into_iter()
+                    .map(|(token, special_token)| {
+                        let info = new_info![
+                            ids: special_token.ids,
+                            tokens: special_token.tokens
+                        ];
+                        (token, info)
+                    })
+                    .collect::<HashMap<_, _>>()
+            ]
+        }
+        PostProcessorWrapper::Sequence(sequence) => {
+            let config: SequenceConfig = serialized_config(sequence)?;
+            new_info![
+                post_processor_type: "sequence",
+                processors: config
+                    .processors
+                    .iter()
+                    .map(post_processor_info)
+                    .collect::<Result<Vec<_>, _>>()?
+            ]
+        }
+    })
+}
+
+/// Formats template pieces in the same notation accepted by `:single`
+/// and `:pair`, with explicit type ids.
+fn template_to_string(pieces: &[Piece]) -> String {
+    pieces
+        .iter()
+        .map(|piece| match piece {
+            Piece::Sequence {
+                id: Sequence::A,
+                type_id,
+            } => format!("$A:{type_id}"),
+            Piece::Sequence {
+                id: Sequence::B,
+                type_id,
+            } => format!("$B:{type_id}"),
+            Piece::SpecialToken { id, type_id } => format!("{id}:{type_id}"),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Builds a template surrounding each sequence with the given BOS and EOS
+/// tokens, as Llama-like tokenizers do.
+pub fn bos_eos_template(
+    bos: impl IntoIterator<Item = (String, u32)>,
+    eos: impl IntoIterator<Item = (String, u32)>,
+) -> Result<TemplateProcessing, ExTokenizersError> {
+    let (bos, eos) = (
+        bos.into_iter().collect::<Vec<_>>(),
+        eos.into_iter().collect::<Vec<_>>(),
+    );
+    let sequence = |sequence: &str, type_id: u32| {
+        bos.iter()
+            .map(|(token, _)| token.as_str())
+            .chain([sequence])
+            .chain(eos.iter().map(|(token, _)| token.as_str()))
+            .map(|piece| format!("{piece}:{type_id}"))
+            .collect::<Vec<_>>()
+    };
+    let single = sequence("$A", 0);
+    let pair = [single.clone(), sequence("$B", 1)].concat();
+
+    TemplateProcessing::builder()
+        .try_single(single.join(" "))
+        .and_then(|builder| builder.try_pair(pair.join(" ")))
+        .map_err(ExTokenizersError::InvalidTemplate)?
+        .special_tokens([bos, eos].concat())
+        .build()
+        .map_err(|error| ExTokenizersError::UnknownToken(error.to_string()))
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Serialization
+// /////////////////////////////////////////////////////////////////////////////
+
+#[rustler::nif]
+pub fn post_processors_to_json(
+    post_processor: ExTokenizersPostProcessor,
+    options: Vec<JsonOption>,
+) -> Result<String, ExTokenizersError> {
+    to_json(&post_processor, options)
+}
+
+#[rustler::nif]
+pub fn post_processors_from_json(
+    json: &str,
+) -> Result<ExTokenizersPostProcessor, ExTokenizersError> {
+    from_json(json)
 }
 
 // /////////////////////////////////////////////////////////////////////////////
@@ -152,16 +312,18 @@ pub fn post_processors_template(
     let mut builder = tokenizers::processors::template::TemplateProcessing::builder();
     for opt in opts {
         match opt {
-            TemplateOption::Single(v) => {
-                builder.try_single(v).map_err(|_| rustler::Error::BadArg)?
-            }
-            TemplateOption::Pair(v) => builder.try_pair(v).map_err(|_| rustler::Error::BadArg)?,
+            TemplateOption::Single(v) => builder
+                .try_single(v)
+                .map_err(|error| ExTokenizersError::InvalidTemplate(error.to_string()))?,
+            TemplateOption::Pair(v) => builder
+                .try_pair(v)
+                .map_err(|error| ExTokenizersError::InvalidTemplate(error.to_string()))?,
             TemplateOption::SpecialTokens(v) => builder.special_tokens(v),
         };
     }
-    Ok(ExTokenizersPostProcessor::new(
-        builder.build().map_err(|_| rustler::Error::BadArg)?,
-    ))
+    Ok(ExTokenizersPostProcessor::new(builder.build().map_err(
+        |error| ExTokenizersError::UnknownToken(error.to_string()),
+    )?))
 }
 
 #[rustler::nif]
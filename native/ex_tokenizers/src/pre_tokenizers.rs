@@ -1,4 +1,6 @@
-use crate::util::Info;
+use crate::util::{
+    from_json, serialized_config, to_json, Info, JsonOption, OffsetReferential, OffsetType,
+};
 use crate::{new_info, ExTokenizersError};
 use rustler::NifTaggedEnum;
 use serde::{Deserialize, Serialize};
@@ -68,20 +70,39 @@ impl ExTokenizersPreTokenizer {
     }
 }
 
+#[derive(NifTaggedEnum)]
+pub enum PreTokenizeOption {
+    OffsetType(OffsetType),
+    OffsetReferential(OffsetReferential),
+}
+
 #[rustler::nif]
 #[allow(clippy::type_complexity)]
 pub fn pre_tokenizers_pre_tokenize(
     pre_tokenizer: ExTokenizersPreTokenizer,
     sequence: String,
+    options: Vec<PreTokenizeOption>,
 ) -> Result<Vec<(String, (usize, usize))>, ExTokenizersError> {
+    struct Opts {
+        offset_type: OffsetType,
+        offset_referential: OffsetReferential,
+    }
+    let mut opts = Opts {
+        offset_type: OffsetType::Chars,
+        offset_referential: OffsetReferential::Original,
+    };
+    options.into_iter().for_each(|option| match option {
+        PreTokenizeOption::OffsetType(offset_type) => opts.offset_type = offset_type,
+        PreTokenizeOption::OffsetReferential(offset_referential) => {
+            opts.offset_referential = offset_referential
+        }
+    });
+
     let mut pretokenized = PreTokenizedString::from(sequence);
 
     pre_tokenizer.pre_tokenize(&mut pretokenized)?;
     let splits: Vec<(String, (usize, usize))> = pretokenized
-        .get_splits(
-            tokenizers::OffsetReferential::Original,
-            tokenizers::OffsetType::Char,
-        )
+        .get_splits(opts.offset_referential.into(), opts.offset_type.into())
         .into_iter()
         .map(|(s, o, _)| (s.to_owned(), o))
         .collect();
@@ -93,42 +114,100 @@ pub fn pre_tokenizers_pre_tokenize(
 // /////////////////////////////////////////////////////////////////////////////
 
 #[rustler::nif]
-fn pre_tokenizers_info(pre_tokenizer: ExTokenizersPreTokenizer) -> Info {
-    match pre_tokenizer.resource.0 {
+fn pre_tokenizers_info(pre_tokenizer: ExTokenizersPreTokenizer) -> Result<Info, rustler::Error> {
+    Ok(pre_tokenizer_info(&pre_tokenizer.resource.0)?)
+}
+
+fn pre_tokenizer_info(pre_tokenizer: &PreTokenizerWrapper) -> Result<Info, ExTokenizersError> {
+    // Split and Punctuation don't expose their configuration, so we read
+    // it back from their serialized form
+    #[derive(Deserialize)]
+    struct SplitConfig {
+        pattern: SplitPattern,
+        behavior: tokenizers::SplitDelimiterBehavior,
+        invert: bool,
+    }
+
+    #[derive(Deserialize)]
+    struct PunctuationConfig {
+        behavior: tokenizers::SplitDelimiterBehavior,
+    }
+
+    Ok(match pre_tokenizer {
         PreTokenizerWrapper::BertPreTokenizer(_) => new_info!(
             pre_tokenizer_type: "BertPreTokenizer"
         ),
-        PreTokenizerWrapper::ByteLevel(_) => new_info!(
-            pre_tokenizer_type: "ByteLevel"
+        PreTokenizerWrapper::ByteLevel(byte_level) => new_info!(
+            pre_tokenizer_type: "ByteLevel",
+            add_prefix_space: byte_level.add_prefix_space,
+            trim_offsets: byte_level.trim_offsets,
+            use_regex: byte_level.use_regex
         ),
-        PreTokenizerWrapper::Delimiter(_) => new_info!(
-            pre_tokenizer_type: "Delimiter"
+        PreTokenizerWrapper::Delimiter(delimiter) => new_info!(
+            pre_tokenizer_type: "Delimiter",
+            delimiter: delimiter.delimiter.to_string()
         ),
-        PreTokenizerWrapper::Metaspace(_) => new_info!(
-            pre_tokenizer_type: "Metaspace"
+        PreTokenizerWrapper::Metaspace(metaspace) => new_info!(
+            pre_tokenizer_type: "Metaspace",
+            replacement: metaspace.get_replacement().to_string(),
+            prepend_scheme: PrependScheme::from(metaspace.get_prepend_scheme()),
+            split: metaspace.get_split()
         ),
         PreTokenizerWrapper::Whitespace(_) => new_info!(
             pre_tokenizer_type: "Whitespace"
         ),
-        PreTokenizerWrapper::Sequence(_) => new_info!(
-            pre_tokenizer_type: "Sequence"
-        ),
-        PreTokenizerWrapper::Split(_) => new_info!(
-            pre_tokenizer_type: "Split"
-        ),
-        PreTokenizerWrapper::Punctuation(_) => new_info!(
-            pre_tokenizer_type: "Punctuation"
+        PreTokenizerWrapper::Sequence(sequence) => new_info!(
+            pre_tokenizer_type: "Sequence",
+            pre_tokenizers: sequence
+                .get_pre_tokenizers()
+                .iter()
+                .map(pre_tokenizer_info)
+                .collect::<Result<Vec<_>, _>>()?
         ),
+        PreTokenizerWrapper::Split(split) => {
+            let config: SplitConfig = serialized_config(split)?;
+            new_info!(
+                pre_tokenizer_type: "Split",
+                pattern: LocalSplitPattern::from(config.pattern),
+                behavior: SplitDelimiterBehavior::from(config.behavior),
+                invert: config.invert
+            )
+        }
+        PreTokenizerWrapper::Punctuation(punctuation) => {
+            let config: PunctuationConfig = serialized_config(punctuation)?;
+            new_info!(
+                pre_tokenizer_type: "Punctuation",
+                behavior: SplitDelimiterBehavior::from(config.behavior)
+            )
+        }
         PreTokenizerWrapper::WhitespaceSplit(_) => new_info!(
             pre_tokenizer_type: "WhitespaceSplit"
         ),
-        PreTokenizerWrapper::Digits(_) => new_info!(
-            pre_tokenizer_type: "Digits"
+        PreTokenizerWrapper::Digits(digits) => new_info!(
+            pre_tokenizer_type: "Digits",
+            individual_digits: digits.individual_digits
         ),
         PreTokenizerWrapper::UnicodeScripts(_) => new_info!(
             pre_tokenizer_type: "UnicodeScripts"
         ),
-    }
+    })
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Serialization
+// /////////////////////////////////////////////////////////////////////////////
+
+#[rustler::nif]
+pub fn pre_tokenizers_to_json(
+    pre_tokenizer: ExTokenizersPreTokenizer,
+    options: Vec<JsonOption>,
+) -> Result<String, ExTokenizersError> {
+    to_json(&pre_tokenizer, options)
+}
+
+#[rustler::nif]
+pub fn pre_tokenizers_from_json(json: &str) -> Result<ExTokenizersPreTokenizer, ExTokenizersError> {
+    from_json(json)
 }
 
 // /////////////////////////////////////////////////////////////////////////////
@@ -191,6 +270,16 @@ pub enum PrependScheme {
     Always,
 }
 
+impl From<tokenizers::pre_tokenizers::metaspace::PrependScheme> for PrependScheme {
+    fn from(value: tokenizers::pre_tokenizers::metaspace::PrependScheme) -> Self {
+        match value {
+            tokenizers::pre_tokenizers::metaspace::PrependScheme::First => PrependScheme::First,
+            tokenizers::pre_tokenizers::metaspace::PrependScheme::Never => PrependScheme::Never,
+            tokenizers::pre_tokenizers::metaspace::PrependScheme::Always => PrependScheme::Always,
+        }
+    }
+}
+
 #[rustler::nif]
 pub fn pre_tokenizers_metaspace(
     options: Vec<MetaspaceOption>,
@@ -198,8 +287,9 @@ pub fn pre_tokenizers_metaspace(
     let mut metaspace = tokenizers::pre_tokenizers::metaspace::Metaspace::default();
     for option in options {
         match option {
-            MetaspaceOption::Replacement(replacement) => metaspace
-                .set_replacement(std::char::from_u32(replacement).ok_or(rustler::Error::BadArg)?),
+            MetaspaceOption::Replacement(replacement) => metaspace.set_replacement(
+                std::char::from_u32(replacement).ok_or(ExTokenizersError::InvalidChar)?,
+            ),
             MetaspaceOption::PrependScheme(prepend_scheme) => {
                 metaspace.prepend_scheme = match prepend_scheme {
                     PrependScheme::First => {
@@ -224,7 +314,7 @@ pub fn pre_tokenizers_char_delimiter_split(
 ) -> Result<ExTokenizersPreTokenizer, rustler::Error> {
     Ok(ExTokenizersPreTokenizer::new(
         tokenizers::pre_tokenizers::delimiter::CharDelimiterSplit::new(
-            std::char::from_u32(delimiter).ok_or(rustler::Error::BadArg)?,
+            std::char::from_u32(delimiter).ok_or(ExTokenizersError::InvalidChar)?,
         ),
     ))
 }
@@ -254,6 +344,22 @@ impl From<SplitDelimiterBehavior> for tokenizers::SplitDelimiterBehavior {
     }
 }
 
+impl From<tokenizers::SplitDelimiterBehavior> for SplitDelimiterBehavior {
+    fn from(value: tokenizers::SplitDelimiterBehavior) -> Self {
+        match value {
+            tokenizers::SplitDelimiterBehavior::Removed => SplitDelimiterBehavior::Removed,
+            tokenizers::SplitDelimiterBehavior::Isolated => SplitDelimiterBehavior::Isolated,
+            tokenizers::SplitDelimiterBehavior::MergedWithPrevious => {
+                SplitDelimiterBehavior::MergedWithPrevious
+            }
+            tokenizers::SplitDelimiterBehavior::MergedWithNext => {
+                SplitDelimiterBehavior::MergedWithNext
+            }
+            tokenizers::SplitDelimiterBehavior::Contiguous => SplitDelimiterBehavior::Contiguous,
+        }
+    }
+}
+
 #[derive(NifTaggedEnum)]
 pub enum SplitOption {
     Invert(bool),
@@ -265,6 +371,15 @@ pub enum LocalSplitPattern {
     Regex(String),
 }
 
+impl From<SplitPattern> for LocalSplitPattern {
+    fn from(value: SplitPattern) -> Self {
+        match value {
+            SplitPattern::String(pattern) => LocalSplitPattern::String(pattern),
+            SplitPattern::Regex(pattern) => LocalSplitPattern::Regex(pattern),
+        }
+    }
+}
+
 #[rustler::nif]
 pub fn pre_tokenizers_split(
     pattern: LocalSplitPattern,
@@ -288,7 +403,7 @@ pub fn pre_tokenizers_split(
 
     Ok(ExTokenizersPreTokenizer::new(
         tokenizers::pre_tokenizers::split::Split::new(final_pattern, behavior.into(), opts.invert)
-            .map_err(|_| rustler::Error::BadArg)?,
+            .map_err(|error| ExTokenizersError::InvalidRegex(error.to_string()))?,
     ))
 }
 
@@ -337,3 +452,10 @@ pub fn pre_tokenizers_digits(options: Vec<DigitsOption>) -> ExTokenizersPreToken
         opts.individual_digits,
     ))
 }
+
+#[rustler::nif]
+pub fn pre_tokenizers_unicode_scripts() -> ExTokenizersPreTokenizer {
+    ExTokenizersPreTokenizer::new(
+        tokenizers::pre_tokenizers::unicode_scripts::UnicodeScripts::new(),
+    )
+}
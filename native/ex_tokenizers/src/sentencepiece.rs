@@ -0,0 +1,504 @@
+use std::collections::HashMap;
+
+use rustler::{Binary, NifTaggedEnum};
+use tokenizers::decoders::byte_fallback::ByteFallback;
+use tokenizers::decoders::fuse::Fuse;
+use tokenizers::decoders::strip::Strip as StripDecoder;
+use tokenizers::models::bpe::BPE;
+use tokenizers::models::unigram::Unigram;
+use tokenizers::normalizers::replace::{Replace, ReplacePattern};
+use tokenizers::normalizers::strip::Strip;
+use tokenizers::pre_tokenizers::metaspace::{Metaspace, PrependScheme};
+use tokenizers::processors::template::TemplateProcessing;
+use tokenizers::tokenizer::AddedToken;
+use tokenizers::{DecoderWrapper, ModelWrapper, NormalizerWrapper, TokenizerImpl};
+
+use crate::decoders::ExTokenizersDecoder;
+use crate::error::ExTokenizersError;
+use crate::models::ExTokenizersModel;
+use crate::normalizers::{precompiled, ExTokenizersNormalizer};
+use crate::post_processors::{bos_eos_template, ExTokenizersPostProcessor};
+use crate::pre_tokenizers::ExTokenizersPreTokenizer;
+use crate::tokenizer::{ExTokenizerImpl, ExTokenizersTokenizer};
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Protobuf
+// /////////////////////////////////////////////////////////////////////////////
+
+// SentencePiece models are serialized `ModelProto` messages. We only need
+// a handful of fields, so rather than pulling a protobuf implementation we
+// decode the wire format directly.
+
+enum WireValue<'a> {
+    Varint(u64),
+    Fixed64,
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+struct WireReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> WireReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn read_varint(&mut self) -> Result<u64, ExTokenizersError> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| invalid_model("unexpected end of varint"))?;
+            self.position += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid_model("varint is too long"))
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Result<&'a [u8], ExTokenizersError> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| invalid_model("unexpected end of message"))?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Reads the next field as `(field_number, value)`.
+    fn next_field(&mut self) -> Result<Option<(u64, WireValue<'a>)>, ExTokenizersError> {
+        if self.position >= self.data.len() {
+            return Ok(None);
+        }
+        let key = self.read_varint()?;
+        let value = match key & 0x7 {
+            0 => WireValue::Varint(self.read_varint()?),
+            1 => {
+                self.read_bytes(8)?;
+                WireValue::Fixed64
+            }
+            2 => {
+                let length = self.read_varint()? as usize;
+                WireValue::Bytes(self.read_bytes(length)?)
+            }
+            5 => {
+                let bytes = self.read_bytes(4)?;
+                WireValue::Fixed32(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+            wire_type => {
+                return Err(invalid_model(&format!(
+                    "unsupported protobuf wire type {wire_type}"
+                )))
+            }
+        };
+        Ok(Some((key >> 3, value)))
+    }
+}
+
+fn invalid_model(message: &str) -> ExTokenizersError {
+    ExTokenizersError::InvalidModelFile(format!("SentencePiece model: {message}"))
+}
+
+fn decode_string(bytes: &[u8]) -> Result<String, ExTokenizersError> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| invalid_model("string is not valid UTF-8"))
+}
+
+#[derive(PartialEq)]
+enum PieceType {
+    Normal,
+    Unknown,
+    Control,
+    UserDefined,
+    Other,
+}
+
+struct Piece {
+    piece: String,
+    score: f32,
+    piece_type: PieceType,
+}
+
+impl Piece {
+    fn decode(data: &[u8]) -> Result<Self, ExTokenizersError> {
+        let mut piece = Piece {
+            piece: String::new(),
+            score: 0.0,
+            piece_type: PieceType::Normal,
+        };
+        let mut reader = WireReader::new(data);
+        while let Some(field) = reader.next_field()? {
+            match field {
+                (1, WireValue::Bytes(bytes)) => piece.piece = decode_string(bytes)?,
+                (2, WireValue::Fixed32(bits)) => piece.score = f32::from_bits(bits),
+                (3, WireValue::Varint(value)) => {
+                    piece.piece_type = match value {
+                        1 => PieceType::Normal,
+                        2 => PieceType::Unknown,
+                        3 => PieceType::Control,
+                        4 => PieceType::UserDefined,
+                        _ => PieceType::Other,
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(piece)
+    }
+}
+
+struct TrainerSpec {
+    model_type: u64,
+    byte_fallback: bool,
+    unk_id: i32,
+    bos_id: i32,
+    eos_id: i32,
+}
+
+impl TrainerSpec {
+    fn decode(data: &[u8]) -> Result<Self, ExTokenizersError> {
+        // Defaults as declared in sentencepiece_model.proto
+        let mut spec = TrainerSpec {
+            model_type: 1,
+            byte_fallback: false,
+            unk_id: 0,
+            bos_id: 1,
+            eos_id: 2,
+        };
+        let mut reader = WireReader::new(data);
+        while let Some(field) = reader.next_field()? {
+            match field {
+                (3, WireValue::Varint(value)) => spec.model_type = value,
+                (35, WireValue::Varint(value)) => spec.byte_fallback = value != 0,
+                // int32 fields are sign-extended to 64 bits on the wire
+                (40, WireValue::Varint(value)) => spec.unk_id = value as i32,
+                (41, WireValue::Varint(value)) => spec.bos_id = value as i32,
+                (42, WireValue::Varint(value)) => spec.eos_id = value as i32,
+                _ => (),
+            }
+        }
+        Ok(spec)
+    }
+}
+
+struct NormalizerSpec {
+    precompiled_charsmap: Vec<u8>,
+    add_dummy_prefix: bool,
+    remove_extra_whitespaces: bool,
+}
+
+impl NormalizerSpec {
+    fn decode(data: &[u8]) -> Result<Self, ExTokenizersError> {
+        let mut spec = NormalizerSpec::default();
+        let mut reader = WireReader::new(data);
+        while let Some(field) = reader.next_field()? {
+            match field {
+                (2, WireValue::Bytes(bytes)) => spec.precompiled_charsmap = bytes.to_vec(),
+                (3, WireValue::Varint(value)) => spec.add_dummy_prefix = value != 0,
+                (4, WireValue::Varint(value)) => spec.remove_extra_whitespaces = value != 0,
+                _ => (),
+            }
+        }
+        Ok(spec)
+    }
+}
+
+impl Default for NormalizerSpec {
+    fn default() -> Self {
+        Self {
+            precompiled_charsmap: vec![],
+            add_dummy_prefix: true,
+            remove_extra_whitespaces: true,
+        }
+    }
+}
+
+struct ModelProto {
+    pieces: Vec<Piece>,
+    trainer_spec: TrainerSpec,
+    normalizer_spec: NormalizerSpec,
+}
+
+impl ModelProto {
+    fn decode(data: &[u8]) -> Result<Self, ExTokenizersError> {
+        let mut pieces = vec![];
+        let mut trainer_spec = None;
+        let mut normalizer_spec = None;
+        let mut reader = WireReader::new(data);
+        while let Some(field) = reader.next_field()? {
+            match field {
+                (1, WireValue::Bytes(bytes)) => pieces.push(Piece::decode(bytes)?),
+                (2, WireValue::Bytes(bytes)) => trainer_spec = Some(TrainerSpec::decode(bytes)?),
+                (3, WireValue::Bytes(bytes)) => {
+                    normalizer_spec = Some(NormalizerSpec::decode(bytes)?)
+                }
+                _ => (),
+            }
+        }
+        if pieces.is_empty() {
+            return Err(invalid_model("the model has no pieces"));
+        }
+        Ok(ModelProto {
+            pieces,
+            trainer_spec: match trainer_spec {
+                Some(trainer_spec) => trainer_spec,
+                None => TrainerSpec::decode(&[])?,
+            },
+            normalizer_spec: normalizer_spec.unwrap_or_default(),
+        })
+    }
+
+    fn piece(&self, id: i32) -> Option<&str> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|id| self.pieces.get(id))
+            .map(|piece| piece.piece.as_str())
+    }
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Conversion
+// /////////////////////////////////////////////////////////////////////////////
+
+/// Rebuilds the BPE merges from the vocabulary, following the
+/// transformers `SentencePieceExtractor`: every split of a piece into two
+/// pieces of the vocabulary is a merge, ranked by the piece score.
+fn bpe_merges(proto: &ModelProto, vocab: &HashMap<String, u32>) -> Vec<(String, String)> {
+    let mut merges: Vec<(String, String, f32)> = vec![];
+    for piece in &proto.pieces {
+        let mut local = piece
+            .piece
+            .char_indices()
+            .skip(1)
+            .filter_map(|(index, _)| {
+                let (left, right) = piece.piece.split_at(index);
+                Some((*vocab.get(left)?, *vocab.get(right)?, left, right))
+            })
+            .collect::<Vec<_>>();
+        local.sort_by_key(|(left_id, right_id, _, _)| (*left_id, *right_id));
+        merges.extend(
+            local
+                .into_iter()
+                .map(|(_, _, left, right)| (left.to_string(), right.to_string(), piece.score)),
+        );
+    }
+    merges.sort_by(|a, b| b.2.total_cmp(&a.2));
+    merges
+        .into_iter()
+        .map(|(left, right, _)| (left, right))
+        .collect()
+}
+
+fn build_model(proto: &ModelProto) -> Result<ModelWrapper, ExTokenizersError> {
+    let spec = &proto.trainer_spec;
+    match spec.model_type {
+        1 => {
+            let vocab = proto
+                .pieces
+                .iter()
+                .map(|piece| (piece.piece.clone(), f64::from(piece.score)))
+                .collect();
+            let unk_id = usize::try_from(spec.unk_id).ok();
+            Ok(Unigram::from(vocab, unk_id, spec.byte_fallback)?.into())
+        }
+        2 => {
+            let vocab = proto
+                .pieces
+                .iter()
+                .enumerate()
+                .map(|(id, piece)| (piece.piece.clone(), id as u32))
+                .collect::<HashMap<_, _>>();
+            let merges = bpe_merges(proto, &vocab);
+            let mut builder = BPE::builder()
+                .vocab_and_merges(vocab, merges)
+                .fuse_unk(true)
+                .byte_fallback(spec.byte_fallback);
+            if let Some(unk_token) = proto.piece(spec.unk_id) {
+                builder = builder.unk_token(unk_token.to_string());
+            }
+            Ok(builder.build()?.into())
+        }
+        model_type => Err(ExTokenizersError::InvalidModelFile(format!(
+            "SentencePiece model type {model_type} is not supported, \
+             only Unigram and BPE models can be loaded"
+        ))),
+    }
+}
+
+fn build_normalizer(proto: &ModelProto) -> Result<Option<NormalizerWrapper>, ExTokenizersError> {
+    let spec = &proto.normalizer_spec;
+    let mut normalizers: Vec<NormalizerWrapper> = vec![];
+    if !spec.precompiled_charsmap.is_empty() {
+        normalizers.push(precompiled(&spec.precompiled_charsmap)?.into());
+    }
+    if spec.remove_extra_whitespaces {
+        normalizers.push(Strip::new(false, true).into());
+        normalizers.push(
+            Replace::new(ReplacePattern::Regex(" {2,}".to_string()), " ")
+                .map_err(|error| ExTokenizersError::InvalidRegex(error.to_string()))?
+                .into(),
+        );
+    }
+    Ok(match normalizers.len() {
+        0 => None,
+        1 => normalizers.pop(),
+        _ => Some(tokenizers::normalizers::Sequence::new(normalizers).into()),
+    })
+}
+
+fn build_decoder(
+    proto: &ModelProto,
+    metaspace: Metaspace,
+) -> Result<DecoderWrapper, ExTokenizersError> {
+    if proto.trainer_spec.byte_fallback {
+        // Byte pieces such as <0x0A> must be fused before we can restore
+        // spaces, so the Metaspace decoder can't be used here
+        let mut decoders: Vec<DecoderWrapper> = vec![
+            Replace::new("▁", " ")
+                .map_err(|error| ExTokenizersError::InvalidRegex(error.to_string()))?
+                .into(),
+            ByteFallback::new().into(),
+            Fuse::new().into(),
+        ];
+        if proto.normalizer_spec.add_dummy_prefix {
+            decoders.push(StripDecoder::new(' ', 1, 0).into());
+        }
+        Ok(tokenizers::decoders::sequence::Sequence::new(decoders).into())
+    } else {
+        Ok(metaspace.into())
+    }
+}
+
+fn build_post_processor(
+    proto: &ModelProto,
+    opts: &Opts,
+) -> Result<Option<TemplateProcessing>, ExTokenizersError> {
+    let spec = &proto.trainer_spec;
+
+    // BPE models are converted like Llama tokenizers in transformers, which
+    // add the BOS token unless told otherwise
+    let add_bos_token = opts
+        .add_bos_token
+        .unwrap_or(spec.model_type == 2 && proto.piece(spec.bos_id).is_some());
+    let bos = if add_bos_token {
+        let bos = proto.piece(spec.bos_id).ok_or_else(|| {
+            ExTokenizersError::UnknownToken("the model has no BOS token".to_string())
+        })?;
+        Some((bos.to_string(), spec.bos_id as u32))
+    } else {
+        None
+    };
+    let eos = if opts.add_eos_token.unwrap_or(false) {
+        let eos = proto.piece(spec.eos_id).ok_or_else(|| {
+            ExTokenizersError::UnknownToken("the model has no EOS token".to_string())
+        })?;
+        Some((eos.to_string(), spec.eos_id as u32))
+    } else {
+        None
+    };
+
+    if bos.is_none() && eos.is_none() {
+        return Ok(None);
+    }
+
+    Ok(Some(bos_eos_template(bos, eos)?))
+}
+
+fn build_tokenizer(data: &[u8], opts: Opts) -> Result<ExTokenizerImpl, ExTokenizersError> {
+    let proto = ModelProto::decode(data)?;
+
+    let prepend_scheme = if proto.normalizer_spec.add_dummy_prefix {
+        PrependScheme::Always
+    } else {
+        PrependScheme::Never
+    };
+    // SentencePiece encodes BPE models over the whole text, so pieces such
+    // as "▁▁" can span several spaces, while Unigram models are split into
+    // words first
+    let split = proto.trainer_spec.model_type != 2;
+    let metaspace = Metaspace::new('▁', prepend_scheme, split);
+
+    let mut tokenizer = TokenizerImpl::new(ExTokenizersModel::new(build_model(&proto)?));
+    if let Some(normalizer) = build_normalizer(&proto)? {
+        tokenizer.with_normalizer(ExTokenizersNormalizer::new(normalizer));
+    }
+    tokenizer.with_pre_tokenizer(ExTokenizersPreTokenizer::new(metaspace.clone()));
+    tokenizer.with_decoder(ExTokenizersDecoder::new(build_decoder(&proto, metaspace)?));
+    if let Some(post_processor) = build_post_processor(&proto, &opts)? {
+        tokenizer.with_post_processor(ExTokenizersPostProcessor::new(post_processor));
+    }
+
+    // Control and unknown pieces are never produced from raw text, while
+    // user defined pieces must be matched as a whole
+    let special_tokens = proto
+        .pieces
+        .iter()
+        .filter(|piece| matches!(piece.piece_type, PieceType::Control | PieceType::Unknown))
+        .map(|piece| AddedToken::from(piece.piece.clone(), true))
+        .collect::<Vec<_>>();
+    tokenizer.add_special_tokens(&special_tokens);
+    let user_defined = proto
+        .pieces
+        .iter()
+        .filter(|piece| piece.piece_type == PieceType::UserDefined)
+        .map(|piece| AddedToken::from(piece.piece.clone(), false).normalized(false))
+        .collect::<Vec<_>>();
+    tokenizer.add_tokens(&user_defined);
+
+    Ok(tokenizer)
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Loaders
+// /////////////////////////////////////////////////////////////////////////////
+
+#[derive(NifTaggedEnum)]
+pub enum SentencePieceOption {
+    AddBosToken(bool),
+    AddEosToken(bool),
+}
+
+struct Opts {
+    add_bos_token: Option<bool>,
+    add_eos_token: Option<bool>,
+}
+
+impl Opts {
+    fn new(options: Vec<SentencePieceOption>) -> Self {
+        let mut opts = Opts {
+            add_bos_token: None,
+            add_eos_token: None,
+        };
+        for option in options {
+            match option {
+                SentencePieceOption::AddBosToken(value) => opts.add_bos_token = Some(value),
+                SentencePieceOption::AddEosToken(value) => opts.add_eos_token = Some(value),
+            }
+        }
+        opts
+    }
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn tokenizer_from_sentencepiece_file(
+    path: &str,
+    options: Vec<SentencePieceOption>,
+) -> Result<ExTokenizersTokenizer, ExTokenizersError> {
+    let data = std::fs::read(path)?;
+    Ok(build_tokenizer(&data, Opts::new(options))?.into())
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn tokenizer_from_sentencepiece_buffer(
+    data: Binary,
+    options: Vec<SentencePieceOption>,
+) -> Result<ExTokenizersTokenizer, ExTokenizersError> {
+    Ok(build_tokenizer(data.as_slice(), Opts::new(options))?.into())
+}
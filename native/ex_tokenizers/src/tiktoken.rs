@@ -0,0 +1,280 @@
+use std::collections::{HashMap, HashSet};
+
+use rustler::{Binary, NifTaggedEnum};
+use tokenizers::models::bpe::BPE;
+use tokenizers::pre_tokenizers::byte_level::ByteLevel;
+use tokenizers::pre_tokenizers::split::{Split, SplitPattern};
+use tokenizers::tokenizer::AddedToken;
+use tokenizers::{PreTokenizerWrapper, SplitDelimiterBehavior, TokenizerImpl};
+
+use crate::decoders::ExTokenizersDecoder;
+use crate::error::ExTokenizersError;
+use crate::models::ExTokenizersModel;
+use crate::post_processors::ExTokenizersPostProcessor;
+use crate::pre_tokenizers::ExTokenizersPreTokenizer;
+use crate::tokenizer::{ExTokenizerImpl, ExTokenizersTokenizer};
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Patterns
+// /////////////////////////////////////////////////////////////////////////////
+
+// Split patterns of the encodings shipped with tiktoken
+
+const R50K_PATTERN: &str =
+    r"'(?:[sdmt]|ll|ve|re)| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+(?!\S)|\s+";
+
+const CL100K_PATTERN: &str = r"'(?i:[sdmt]|ll|ve|re)|[^\r\n\p{L}\p{N}]?+\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]++[\r\n]*|\s*[\r\n]|\s+(?!\S)|\s+";
+
+const O200K_PATTERN: &str = concat!(
+    r"[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]*[\p{Ll}\p{Lm}\p{Lo}\p{M}]+(?i:'s|'t|'re|'ve|'m|'ll|'d)?",
+    r"|[^\r\n\p{L}\p{N}]?[\p{Lu}\p{Lt}\p{Lm}\p{Lo}\p{M}]+[\p{Ll}\p{Lm}\p{Lo}\p{M}]*(?i:'s|'t|'re|'ve|'m|'ll|'d)?",
+    r"|\p{N}{1,3}",
+    r"| ?[^\s\p{L}\p{N}]+[\r\n/]*",
+    r"|\s*[\r\n]+",
+    r"|\s+(?!\S)",
+    r"|\s+",
+);
+
+#[derive(NifTaggedEnum)]
+pub enum TiktokenPattern {
+    Named(String),
+    Regex(String),
+}
+
+impl TiktokenPattern {
+    fn regex(self) -> Result<String, ExTokenizersError> {
+        match self {
+            TiktokenPattern::Named(name) => match name.as_str() {
+                "r50k_base" | "p50k_base" => Ok(R50K_PATTERN.to_string()),
+                "cl100k_base" => Ok(CL100K_PATTERN.to_string()),
+                "o200k_base" => Ok(O200K_PATTERN.to_string()),
+                _ => Err(ExTokenizersError::Other(format!(
+                    "unknown tiktoken encoding: {name}"
+                ))),
+            },
+            TiktokenPattern::Regex(pattern) => Ok(pattern),
+        }
+    }
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Conversion
+// /////////////////////////////////////////////////////////////////////////////
+
+fn invalid_ranks(line: usize, message: &str) -> ExTokenizersError {
+    ExTokenizersError::InvalidModelFile(format!("tiktoken ranks, line {line}: {message}"))
+}
+
+fn decode_base64(data: &str) -> Option<Vec<u8>> {
+    let data = data.trim_end_matches('=');
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for char in data.bytes() {
+        let value = match char {
+            b'A'..=b'Z' => char - b'A',
+            b'a'..=b'z' => char - b'a' + 26,
+            b'0'..=b'9' => char - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// Parses `<base64 token> <rank>` lines into a token to rank map.
+fn parse_ranks(data: &[u8]) -> Result<HashMap<Vec<u8>, u32>, ExTokenizersError> {
+    let data =
+        std::str::from_utf8(data).map_err(|_| invalid_ranks(1, "file is not valid UTF-8"))?;
+    let mut ranks = HashMap::new();
+    let mut seen = HashSet::new();
+    for (index, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (token, rank) = line
+            .split_once(' ')
+            .ok_or_else(|| invalid_ranks(index + 1, "expected a token and a rank"))?;
+        let token =
+            decode_base64(token).ok_or_else(|| invalid_ranks(index + 1, "invalid base64 token"))?;
+        let rank = rank
+            .trim()
+            .parse()
+            .map_err(|_| invalid_ranks(index + 1, "invalid rank"))?;
+        if !seen.insert(rank) {
+            return Err(invalid_ranks(index + 1, "duplicate rank"));
+        }
+        if ranks.insert(token, rank).is_some() {
+            return Err(invalid_ranks(index + 1, "duplicate token"));
+        }
+    }
+    Ok(ranks)
+}
+
+/// Maps bytes to printable characters, the same way the ByteLevel
+/// pre-tokenizer does.
+fn byte_chars() -> Vec<char> {
+    let mut bytes: Vec<u8> = (b'!'..=b'~').chain(b'\xA1'..=b'\xAC').collect();
+    bytes.extend(b'\xAE'..=b'\xFF');
+    let mut chars = vec!['\0'; 256];
+    for byte in &bytes {
+        chars[*byte as usize] = char::from(*byte);
+    }
+    let mut next = 256;
+    for byte in 0..=255u8 {
+        if !bytes.contains(&byte) {
+            chars[byte as usize] = char::from_u32(next).unwrap_or('\0');
+            next += 1;
+        }
+    }
+    chars
+}
+
+/// Rebuilds the BPE merges from the ranks, following the transformers
+/// tiktoken converter: every split of a token into two tokens is a merge,
+/// ranked by the rank of the merged token.
+///
+/// Special tokens are not part of the rank file, so they are added to the
+/// vocabulary to keep the ids given by tiktoken. Their ids must not be
+/// used by any other token.
+fn build_model(
+    ranks: &HashMap<Vec<u8>, u32>,
+    special_tokens: &[(String, u32)],
+) -> Result<BPE, ExTokenizersError> {
+    let chars = byte_chars();
+    let to_string = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .map(|byte| chars[*byte as usize])
+            .collect::<String>()
+    };
+
+    let mut tokens = ranks.iter().collect::<Vec<_>>();
+    tokens.sort_by_key(|(_, rank)| **rank);
+
+    let mut vocab = HashMap::with_capacity(tokens.len());
+    let mut merges = vec![];
+    for (token, rank) in tokens {
+        vocab.insert(to_string(token), *rank);
+        let mut local = (1..token.len())
+            .filter_map(|index| {
+                let (left, right) = token.split_at(index);
+                Some((*ranks.get(left)?, *ranks.get(right)?, left, right))
+            })
+            .collect::<Vec<_>>();
+        local.sort_by_key(|(left_rank, right_rank, _, _)| (*left_rank, *right_rank));
+        merges.extend(
+            local
+                .into_iter()
+                .map(|(_, _, left, right)| (to_string(left), to_string(right))),
+        );
+    }
+    let mut ids = vocab.values().copied().collect::<HashSet<_>>();
+    for (token, id) in special_tokens {
+        if !ids.insert(*id) || vocab.contains_key(token) {
+            return Err(ExTokenizersError::Other(format!(
+                "special token {token:?} with id {id} collides with an existing token"
+            )));
+        }
+        vocab.insert(token.clone(), *id);
+    }
+
+    // tiktoken looks up whole pieces in the ranks before merging, but
+    // BPE's `ignore_merges` loses the token offsets, so we rely on the
+    // merges alone. Both yield the same tokens as long as every token of
+    // the ranks can be built by merging two other tokens, which holds for
+    // ranks learned by BPE
+    Ok(BPE::builder().vocab_and_merges(vocab, merges).build()?)
+}
+
+fn build_tokenizer(
+    data: &[u8],
+    pattern: TiktokenPattern,
+    opts: Opts,
+) -> Result<ExTokenizerImpl, ExTokenizersError> {
+    let ranks = parse_ranks(data)?;
+    let model = build_model(&ranks, &opts.special_tokens)?;
+
+    let split = Split::new(
+        SplitPattern::Regex(pattern.regex()?),
+        SplitDelimiterBehavior::Isolated,
+        false,
+    )
+    .map_err(|error| ExTokenizersError::InvalidRegex(error.to_string()))?;
+    let byte_level = ByteLevel::new(false, false, false);
+
+    let mut tokenizer = TokenizerImpl::new(ExTokenizersModel::new(model));
+    tokenizer.with_pre_tokenizer(ExTokenizersPreTokenizer::new(
+        tokenizers::pre_tokenizers::sequence::Sequence::new(vec![
+            PreTokenizerWrapper::Split(split),
+            PreTokenizerWrapper::ByteLevel(byte_level),
+        ]),
+    ));
+    tokenizer.with_decoder(ExTokenizersDecoder::new(byte_level));
+    tokenizer.with_post_processor(ExTokenizersPostProcessor::new(byte_level));
+    tokenizer.add_special_tokens(
+        &opts
+            .special_tokens
+            .into_iter()
+            .map(|(token, _)| AddedToken::from(token, true))
+            .collect::<Vec<_>>(),
+    );
+
+    Ok(tokenizer)
+}
+
+// /////////////////////////////////////////////////////////////////////////////
+// / Loaders
+// /////////////////////////////////////////////////////////////////////////////
+
+#[derive(NifTaggedEnum)]
+pub enum TiktokenOption {
+    SpecialTokens(Vec<(String, u32)>),
+}
+
+struct Opts {
+    special_tokens: Vec<(String, u32)>,
+}
+
+impl Opts {
+    fn new(options: Vec<TiktokenOption>) -> Self {
+        let mut opts = Opts {
+            special_tokens: vec![],
+        };
+        for option in options {
+            match option {
+                TiktokenOption::SpecialTokens(special_tokens) => {
+                    opts.special_tokens = special_tokens
+                }
+            }
+        }
+        opts
+    }
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn tokenizer_from_tiktoken_file(
+    path: &str,
+    pattern: TiktokenPattern,
+    options: Vec<TiktokenOption>,
+) -> Result<ExTokenizersTokenizer, ExTokenizersError> {
+    let data = std::fs::read(path)?;
+    Ok(build_tokenizer(&data, pattern, Opts::new(options))?.into())
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn tokenizer_from_tiktoken_buffer(
+    data: Binary,
+    pattern: TiktokenPattern,
+    options: Vec<TiktokenOption>,
+) -> Result<ExTokenizersTokenizer, ExTokenizersError> {
+    Ok(build_tokenizer(data.as_slice(), pattern, Opts::new(options))?.into())
+}
@@ -1,27 +1,44 @@
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
 
-use rustler::{NifTaggedEnum, Term};
+use rustler::{Atom, Env, LocalPid, NifTaggedEnum, ResourceArc, Term};
 
 use tokenizers::models::wordpiece::WordPieceTrainerBuilder;
 use tokenizers::models::TrainerWrapper;
 use tokenizers::tokenizer::AddedToken;
-use tokenizers::Model;
-use tokenizers::{EncodeInput, TokenizerImpl};
+use tokenizers::utils::padding::pad_encodings;
+use tokenizers::utils::parallelism::MaybeParallelIterator;
+use tokenizers::{EncodeInput, InputSequence, PreTokenizedString, TokenizerImpl};
+use tokenizers::{Model, PostProcessor, PreTokenizer};
 
 use crate::added_token::{AddedSpecialTokenInput, AddedTokenInput};
 use crate::decoders::ExTokenizersDecoder;
-use crate::encoding::{apply_transformations, ExTokenizersEncoding, TransformationElement};
+use crate::encoding::{
+    apply_transformations, batch_binaries, BatchBinaries, BatchBinaryOption, ExTokenizersEncoding,
+    TransformationElement,
+};
 use crate::error::ExTokenizersError;
 use crate::models::ExTokenizersModel;
 use crate::normalizers::ExTokenizersNormalizer;
 use crate::post_processors::ExTokenizersPostProcessor;
 use crate::pre_tokenizers::ExTokenizersPreTokenizer;
-use crate::trainers::ExTokenizersTrainer;
-use crate::util::Direction;
+use crate::tokenizer_config::{apply_config, TokenizerConfig};
+use crate::trainers::{ExTokenizersTrainer, ReportingTrainer};
+use crate::util::{Direction, OffsetReferential, OffsetType};
+
+mod atoms {
+    rustler::atoms! {
+        ok,
+        closed,
+    }
+}
 
-type ExTokenizerImpl = TokenizerImpl<
+pub type ExTokenizerImpl = TokenizerImpl<
     ExTokenizersModel,
     ExTokenizersNormalizer,
     ExTokenizersPreTokenizer,
@@ -29,7 +46,7 @@ type ExTokenizerImpl = TokenizerImpl<
     ExTokenizersDecoder,
 >;
 
-pub struct ExTokenizersTokenizerRef(ExTokenizerImpl);
+pub struct ExTokenizersTokenizerRef(pub ExTokenizerImpl, pub TokenizerConfig);
 
 #[derive(rustler::NifStruct)]
 #[module = "Tokenizers.Tokenizer"]
@@ -37,12 +54,23 @@ pub struct ExTokenizersTokenizer {
     pub resource: rustler::ResourceArc<ExTokenizersTokenizerRef>,
 }
 
-impl From<ExTokenizerImpl> for ExTokenizersTokenizer {
-    fn from(data: ExTokenizerImpl) -> Self {
+impl ExTokenizersTokenizer {
+    pub fn new(data: ExTokenizerImpl, config: TokenizerConfig) -> Self {
         Self {
-            resource: rustler::ResourceArc::new(ExTokenizersTokenizerRef(data)),
+            resource: rustler::ResourceArc::new(ExTokenizersTokenizerRef(data, config)),
         }
     }
+
+    // Wraps a modified copy of this tokenizer, keeping its config
+    fn derive(&self, data: ExTokenizerImpl) -> Self {
+        Self::new(data, self.resource.1.clone())
+    }
+}
+
+impl From<ExTokenizerImpl> for ExTokenizersTokenizer {
+    fn from(data: ExTokenizerImpl) -> Self {
+        Self::new(data, TokenizerConfig::default())
+    }
 }
 
 // /////////////////////////////////////////////////////////////////////////////
@@ -63,6 +91,8 @@ pub enum LoadOption {
     // Currently only :none is supported
     Padding(rustler::Atom),
     Truncation(rustler::Atom),
+    TokenizerConfig(String),
+    SpecialTokensMap(String),
 }
 
 #[rustler::nif(schedule = "DirtyIo")]
@@ -70,32 +100,37 @@ pub fn tokenizer_from_file(
     path: &str,
     options: Vec<LoadOption>,
 ) -> Result<ExTokenizersTokenizer, ExTokenizersError> {
-    let mut tokenizer = TokenizerImpl::from_file(path)?;
-    tokenizer = apply_load_options(tokenizer, options);
-    Ok(tokenizer.into())
+    let tokenizer = TokenizerImpl::from_file(path)?;
+    apply_load_options(tokenizer, options)
 }
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn tokenizer_from_buffer(
     data: String,
     options: Vec<LoadOption>,
 ) -> Result<ExTokenizersTokenizer, ExTokenizersError> {
-    let mut tokenizer: ExTokenizerImpl = data.parse()?;
-    tokenizer = apply_load_options(tokenizer, options);
-    Ok(tokenizer.into())
+    let tokenizer: ExTokenizerImpl = data.parse()?;
+    apply_load_options(tokenizer, options)
 }
 
-fn apply_load_options(mut tokenizer: ExTokenizerImpl, options: Vec<LoadOption>) -> ExTokenizerImpl {
+fn apply_load_options(
+    mut tokenizer: ExTokenizerImpl,
+    options: Vec<LoadOption>,
+) -> Result<ExTokenizersTokenizer, ExTokenizersError> {
     struct Opts {
         additional_special_tokens: Vec<AddedSpecialTokenInput>,
         disable_padding: bool,
         disable_truncation: bool,
+        tokenizer_config: Option<String>,
+        special_tokens_map: Option<String>,
     }
 
     let mut opts = Opts {
         additional_special_tokens: vec![],
         disable_padding: false,
         disable_truncation: false,
+        tokenizer_config: None,
+        special_tokens_map: None,
     };
 
     for opt in options {
@@ -109,6 +144,12 @@ fn apply_load_options(mut tokenizer: ExTokenizerImpl, options: Vec<LoadOption>)
             LoadOption::Truncation(_) => {
                 opts.disable_truncation = true;
             }
+            LoadOption::TokenizerConfig(path) => {
+                opts.tokenizer_config = Some(path);
+            }
+            LoadOption::SpecialTokensMap(path) => {
+                opts.special_tokens_map = Some(path);
+            }
         }
     }
 
@@ -120,15 +161,22 @@ fn apply_load_options(mut tokenizer: ExTokenizerImpl, options: Vec<LoadOption>)
             .as_ref(),
     );
 
+    let config = apply_config(
+        &mut tokenizer,
+        opts.tokenizer_config.as_deref(),
+        opts.special_tokens_map.as_deref(),
+    )?;
+
     if opts.disable_padding {
         tokenizer.with_padding(None);
     }
 
     if opts.disable_truncation {
-        tokenizer.with_truncation(None).unwrap();
+        // Disabling truncation never fails
+        let _ = tokenizer.with_truncation(None);
     }
 
-    tokenizer
+    Ok(ExTokenizersTokenizer::new(tokenizer, config))
 }
 
 #[derive(NifTaggedEnum)]
@@ -157,6 +205,24 @@ pub fn tokenizer_save(
     Ok(path.to_string())
 }
 
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn tokenizer_to_buffer(
+    tokenizer: ExTokenizersTokenizer,
+    options: Vec<SaveOption>,
+) -> Result<String, ExTokenizersError> {
+    struct Opts {
+        pretty: bool,
+    }
+    let mut opts = Opts { pretty: false };
+    for opt in options {
+        match opt {
+            SaveOption::Pretty(pretty) => opts.pretty = pretty,
+        }
+    }
+
+    Ok(tokenizer.resource.0.to_string(opts.pretty)?)
+}
+
 // tokenizer_from_pretrained IS SKIPPED as implemented in elixir.
 // It uses tokeniser_from_file underneeth.
 
@@ -176,7 +242,7 @@ pub fn tokenizer_set_model(
 ) -> ExTokenizersTokenizer {
     let mut new_tokenizer = tokenizer.resource.0.clone();
     new_tokenizer.with_model(model);
-    new_tokenizer.into()
+    tokenizer.derive(new_tokenizer)
 }
 
 // Generate all setters and getters for pre_tokenizer, normalizer and so on - not as a macro:
@@ -195,7 +261,7 @@ pub fn tokenizer_set_normalizer(
 ) -> ExTokenizersTokenizer {
     let mut new_tokenizer = tokenizer.resource.0.clone();
     new_tokenizer.with_normalizer(normalizer);
-    new_tokenizer.into()
+    tokenizer.derive(new_tokenizer)
 }
 
 #[rustler::nif]
@@ -214,7 +280,7 @@ pub fn tokenizer_set_pre_tokenizer(
 ) -> ExTokenizersTokenizer {
     let mut new_tokenizer = tokenizer.resource.0.clone();
     new_tokenizer.with_pre_tokenizer(pre_tokenizer);
-    new_tokenizer.into()
+    tokenizer.derive(new_tokenizer)
 }
 
 #[rustler::nif]
@@ -233,7 +299,7 @@ pub fn tokenizer_set_post_processor(
 ) -> ExTokenizersTokenizer {
     let mut new_tokenizer = tokenizer.resource.0.clone();
     new_tokenizer.with_post_processor(post_processor);
-    new_tokenizer.into()
+    tokenizer.derive(new_tokenizer)
 }
 
 #[rustler::nif]
@@ -249,7 +315,12 @@ pub fn tokenizer_set_decoder(
 ) -> ExTokenizersTokenizer {
     let mut new_tokenizer = tokenizer.resource.0.clone();
     new_tokenizer.with_decoder(decoder);
-    new_tokenizer.into()
+    tokenizer.derive(new_tokenizer)
+}
+
+#[rustler::nif]
+pub fn tokenizer_get_special_tokens(tokenizer: ExTokenizersTokenizer) -> HashMap<String, String> {
+    tokenizer.resource.1.special_tokens.clone()
 }
 
 #[rustler::nif]
@@ -275,7 +346,7 @@ pub fn tokenizer_add_tokens(
 ) -> ExTokenizersTokenizer {
     let mut new_tokenizer = tokenizer.resource.0.clone();
     new_tokenizer.add_tokens(&tokens.iter().map(|t| t.into()).collect::<Vec<AddedToken>>());
-    new_tokenizer.into()
+    tokenizer.derive(new_tokenizer)
 }
 
 #[rustler::nif]
@@ -285,7 +356,7 @@ pub fn tokenizer_add_special_tokens(
 ) -> ExTokenizersTokenizer {
     let mut new_tokenizer = tokenizer.resource.0.clone();
     new_tokenizer.add_special_tokens(&tokens.iter().map(|t| t.into()).collect::<Vec<AddedToken>>());
-    new_tokenizer.into()
+    tokenizer.derive(new_tokenizer)
 }
 
 #[derive(NifTaggedEnum)]
@@ -326,7 +397,7 @@ impl From<&TruncateStrategy> for tokenizers::TruncationStrategy {
 pub fn tokenizer_set_truncation(
     tokenizer: ExTokenizersTokenizer,
     opts: Vec<TruncationOption>,
-) -> ExTokenizersTokenizer {
+) -> Result<ExTokenizersTokenizer, rustler::Error> {
     let mut truncation: tokenizers::TruncationParams = Default::default();
     opts.iter().for_each(|option| match option {
         TruncationOption::MaxLength(max_length) => truncation.max_length = *max_length,
@@ -335,15 +406,27 @@ pub fn tokenizer_set_truncation(
         TruncationOption::Direction(direction) => truncation.direction = direction.into(),
     });
     let mut new_tokenizer = tokenizer.resource.0.clone();
-    new_tokenizer.with_truncation(Some(truncation)).unwrap();
-    new_tokenizer.into()
+    let n_added_tokens = new_tokenizer
+        .get_post_processor()
+        .map_or(0, |post_processor| post_processor.added_tokens(false));
+    if truncation.max_length < n_added_tokens {
+        return Err(ExTokenizersError::Other(format!(
+            "max_length must be at least {n_added_tokens} to fit the special tokens"
+        ))
+        .into());
+    }
+    new_tokenizer
+        .with_truncation(Some(truncation))
+        .map_err(ExTokenizersError::from)?;
+    Ok(tokenizer.derive(new_tokenizer))
 }
 
 #[rustler::nif]
 pub fn tokenizer_disable_truncation(tokenizer: ExTokenizersTokenizer) -> ExTokenizersTokenizer {
     let mut new_tokenizer = tokenizer.resource.0.clone();
-    new_tokenizer.with_truncation(None).unwrap();
-    new_tokenizer.into()
+    // Disabling truncation never fails
+    let _ = new_tokenizer.with_truncation(None);
+    tokenizer.derive(new_tokenizer)
 }
 
 #[derive(NifTaggedEnum)]
@@ -399,36 +482,207 @@ pub fn tokenizer_set_padding(
     });
     let mut new_tokenizer = tokenizer.resource.0.clone();
     new_tokenizer.with_padding(Some(padding));
-    new_tokenizer.into()
+    tokenizer.derive(new_tokenizer)
 }
 
 #[rustler::nif]
 pub fn tokenizer_disable_padding(tokenizer: ExTokenizersTokenizer) -> ExTokenizersTokenizer {
     let mut new_tokenizer = tokenizer.resource.0.clone();
     new_tokenizer.with_padding(None);
-    new_tokenizer.into()
+    tokenizer.derive(new_tokenizer)
 }
 
 // /////////////////////////////////////////////////////////////////////////////
 // / Inference
 // /////////////////////////////////////////////////////////////////////////////
 
-fn term_to_encode_input<'a, 'b>(term: &'a Term<'b>) -> Result<EncodeInput<'b>, ExTokenizersError> {
+fn term_to_input_sequence<'b>(term: Term<'b>) -> Option<InputSequence<'b>> {
     if let Ok(seq) = term.decode::<&'b str>() {
-        Ok(EncodeInput::Single(seq.into()))
-    } else if let Ok((seq1, seq2)) = term.decode::<(&'b str, &'b str)>() {
-        Ok(EncodeInput::Dual(seq1.into(), seq2.into()))
+        Some(seq.into())
+    } else if let Ok(words) = term.decode::<Vec<&'b str>>() {
+        Some(words.into())
     } else {
-        Err(ExTokenizersError::Other(String::from(
-            "input must be either a string or a tuple",
-        )))
+        None
     }
 }
 
+fn term_to_encode_input<'a, 'b>(term: &'a Term<'b>) -> Result<EncodeInput<'b>, ExTokenizersError> {
+    if let Some(seq) = term_to_input_sequence(*term) {
+        return Ok(EncodeInput::Single(seq));
+    }
+    if let Ok((term1, term2)) = term.decode::<(Term<'b>, Term<'b>)>() {
+        if let (Some(seq1), Some(seq2)) =
+            (term_to_input_sequence(term1), term_to_input_sequence(term2))
+        {
+            return Ok(EncodeInput::Dual(seq1, seq2));
+        }
+    }
+    Err(ExTokenizersError::Other(String::from(
+        "input must be either a string, a list of words or a tuple of those",
+    )))
+}
+
 #[derive(NifTaggedEnum)]
 pub enum EncodeOption {
     AddSpecialTokens(bool),
     EncodingTransformations(Vec<TransformationElement>),
+    OffsetType(OffsetType),
+    OffsetReferential(OffsetReferential),
+}
+
+struct EncodeOpts {
+    add_special_tokens: bool,
+    encoding_transformations: Vec<TransformationElement>,
+    offset_type: OffsetType,
+    offset_referential: OffsetReferential,
+}
+
+impl EncodeOpts {
+    fn new(options: Vec<EncodeOption>) -> Self {
+        let mut opts = Self {
+            add_special_tokens: true,
+            encoding_transformations: Vec::new(),
+            offset_type: OffsetType::Bytes,
+            offset_referential: OffsetReferential::Original,
+        };
+        options.into_iter().for_each(|option| match option {
+            EncodeOption::AddSpecialTokens(add_special_tokens) => {
+                opts.add_special_tokens = add_special_tokens
+            }
+            EncodeOption::EncodingTransformations(encoding_transformations) => {
+                opts.encoding_transformations = encoding_transformations
+            }
+            EncodeOption::OffsetType(offset_type) => opts.offset_type = offset_type,
+            EncodeOption::OffsetReferential(offset_referential) => {
+                opts.offset_referential = offset_referential
+            }
+        });
+        opts
+    }
+}
+
+fn encode_batch_terms(
+    tokenizer: &ExTokenizersTokenizer,
+    inputs: &[Term],
+    opts: &EncodeOpts,
+) -> Result<Vec<tokenizers::Encoding>, ExTokenizersError> {
+    let inputs = inputs
+        .iter()
+        .map(term_to_encode_input)
+        .collect::<Result<Vec<EncodeInput>, ExTokenizersError>>()?;
+    let tokenizer = &tokenizer.resource.0;
+    let mut encodings = match (opts.offset_referential, opts.offset_type) {
+        (OffsetReferential::Original, OffsetType::Bytes) => {
+            tokenizer.encode_batch(inputs, opts.add_special_tokens)?
+        }
+        (OffsetReferential::Original, OffsetType::Chars) => {
+            tokenizer.encode_batch_char_offsets(inputs, opts.add_special_tokens)?
+        }
+        (OffsetReferential::Normalized, offset_type) => {
+            let add_special_tokens = opts.add_special_tokens;
+            let mut encodings = inputs
+                .into_maybe_par_iter()
+                .map(|input| encode_normalized(tokenizer, input, add_special_tokens, offset_type))
+                .collect::<tokenizers::Result<Vec<_>>>()?;
+            if let Some(params) = tokenizer.get_padding() {
+                pad_encodings(&mut encodings, params)?;
+            }
+            encodings
+        }
+    };
+
+    // Applying transformations (if any)
+    for encoding in encodings.iter_mut() {
+        apply_transformations(encoding, &opts.encoding_transformations);
+    }
+    Ok(encodings)
+}
+
+/// Same as `TokenizerImpl::encode`, except that the offsets refer to the
+/// normalized sequence instead of the original one.
+fn encode_normalized(
+    tokenizer: &ExTokenizerImpl,
+    input: EncodeInput,
+    add_special_tokens: bool,
+    offset_type: OffsetType,
+) -> tokenizers::Result<tokenizers::Encoding> {
+    let (sequence, pair) = match input {
+        EncodeInput::Single(sequence) => (sequence, None),
+        EncodeInput::Dual(sequence, pair) => (sequence, Some(pair)),
+    };
+    let encoding = encode_normalized_sequence(tokenizer, sequence, 0, offset_type)?;
+    let pair_encoding = pair
+        .map(|pair| encode_normalized_sequence(tokenizer, pair, 1, offset_type))
+        .transpose()?;
+    tokenizer.post_process(encoding, pair_encoding, add_special_tokens)
+}
+
+fn encode_normalized_sequence(
+    tokenizer: &ExTokenizerImpl,
+    sequence: InputSequence,
+    type_id: u32,
+    offset_type: OffsetType,
+) -> tokenizers::Result<tokenizers::Encoding> {
+    let encode = |word_idx: Option<u32>, subsequence: &str| {
+        let pre_tokenized = tokenize_sequence(tokenizer, subsequence)?;
+        Ok(normalized_encoding(
+            &pre_tokenized,
+            word_idx,
+            type_id,
+            offset_type,
+        ))
+    };
+
+    // Pre-tokenized words are encoded one by one, with offsets relative to
+    // each word, just like `TokenizerImpl::encode` does
+    match sequence {
+        InputSequence::Raw(sequence) => encode(None, &sequence),
+        InputSequence::PreTokenized(words) => words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| encode(Some(i as u32), word))
+            .collect(),
+        InputSequence::PreTokenizedOwned(words) => words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| encode(Some(i as u32), word))
+            .collect(),
+        InputSequence::PreTokenizedCow(words) => words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| encode(Some(i as u32), word))
+            .collect(),
+    }
+}
+
+fn normalized_encoding(
+    pre_tokenized: &PreTokenizedString,
+    word_idx: Option<u32>,
+    type_id: u32,
+    offset_type: OffsetType,
+) -> tokenizers::Encoding {
+    let splits = pre_tokenized.get_splits(
+        OffsetReferential::Normalized.into(),
+        OffsetType::Bytes.into(),
+    );
+    let mut tokens = Vec::new();
+    let mut split_char_start = 0;
+    for (idx, (split, (split_start, _), split_tokens)) in splits.into_iter().enumerate() {
+        for token in split_tokens.iter().flatten() {
+            let (start, end) = token.offsets;
+            let offsets = match offset_type {
+                OffsetType::Bytes => (split_start + start, split_start + end),
+                OffsetType::Chars => (
+                    split_char_start + split[..start].chars().count(),
+                    split_char_start + split[..end].chars().count(),
+                ),
+            };
+            let word = word_idx.or(Some(idx as u32));
+            tokens.push((token.id, token.value.clone(), offsets, word, type_id));
+        }
+        split_char_start += split.chars().count();
+    }
+    tokens.into_iter().collect()
 }
 
 #[rustler::nif(schedule = "DirtyCpu")]
@@ -437,28 +691,20 @@ pub fn tokenizer_encode(
     input: Term,
     options: Vec<EncodeOption>,
 ) -> Result<ExTokenizersEncoding, ExTokenizersError> {
-    struct Opts {
-        add_special_tokens: bool,
-        encoding_transformations: Vec<TransformationElement>,
-    }
-    let mut opts = Opts {
-        add_special_tokens: true,
-        encoding_transformations: Vec::new(),
-    };
-    options.into_iter().for_each(|option| match option {
-        EncodeOption::AddSpecialTokens(add_special_tokens) => {
-            opts.add_special_tokens = add_special_tokens
+    let opts = EncodeOpts::new(options);
+    let input = term_to_encode_input(&input)?;
+    let tokenizer = &tokenizer.resource.0;
+    let mut encoding = match (opts.offset_referential, opts.offset_type) {
+        (OffsetReferential::Original, OffsetType::Bytes) => {
+            tokenizer.encode(input, opts.add_special_tokens)?
         }
-        EncodeOption::EncodingTransformations(encoding_transformations) => {
-            opts.encoding_transformations = encoding_transformations
+        (OffsetReferential::Original, OffsetType::Chars) => {
+            tokenizer.encode_char_offsets(input, opts.add_special_tokens)?
         }
-    });
-
-    let input = term_to_encode_input(&input)?;
-    let mut encoding = tokenizer
-        .resource
-        .0
-        .encode(input, opts.add_special_tokens)?;
+        (OffsetReferential::Normalized, offset_type) => {
+            encode_normalized(tokenizer, input, opts.add_special_tokens, offset_type)?
+        }
+    };
     apply_transformations(&mut encoding, &opts.encoding_transformations);
     Ok(encoding.into())
 }
@@ -468,43 +714,242 @@ pub fn tokenizer_encode_batch(
     tokenizer: ExTokenizersTokenizer,
     inputs: Vec<Term>,
     options: Vec<EncodeOption>,
-    // add_special_tokens: bool,
 ) -> Result<Vec<ExTokenizersEncoding>, ExTokenizersError> {
-    struct Opts {
-        add_special_tokens: bool,
-        encoding_transformations: Vec<TransformationElement>,
+    let opts = EncodeOpts::new(options);
+    let encodings = encode_batch_terms(&tokenizer, &inputs, &opts)?;
+    let ex_encodings = encodings
+        .into_iter()
+        .map(|encoding| encoding.into())
+        .collect();
+    Ok(ex_encodings)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn tokenizer_encode_batch_binaries<'a>(
+    env: Env<'a>,
+    tokenizer: ExTokenizersTokenizer,
+    inputs: Vec<Term<'a>>,
+    options: Vec<EncodeOption>,
+    binary_options: Vec<BatchBinaryOption>,
+) -> Result<BatchBinaries<'a>, ExTokenizersError> {
+    let opts = EncodeOpts::new(options);
+    let encodings = encode_batch_terms(&tokenizer, &inputs, &opts)?;
+    batch_binaries(env, &encodings, binary_options)
+}
+
+/// Runs the normalizer, pre-tokenizer and model on `sequence`, without
+/// the post-processing, truncation and padding steps of `encode`.
+fn tokenize_sequence(
+    tokenizer: &ExTokenizerImpl,
+    sequence: &str,
+) -> tokenizers::Result<PreTokenizedString> {
+    let mut pre_tokenized = tokenizer
+        .get_added_vocabulary()
+        .extract_and_normalize(tokenizer.get_normalizer(), sequence);
+    if let Some(pre_tokenizer) = tokenizer.get_pre_tokenizer() {
+        pre_tokenizer.pre_tokenize(&mut pre_tokenized)?;
     }
-    let mut opts = Opts {
-        add_special_tokens: true,
-        encoding_transformations: Vec::new(),
-    };
-    options.into_iter().for_each(|option| match option {
-        EncodeOption::AddSpecialTokens(add_special_tokens) => {
-            opts.add_special_tokens = add_special_tokens
+    pre_tokenized.tokenize(|normalized| tokenizer.get_model().tokenize(normalized.get()))?;
+    Ok(pre_tokenized)
+}
+
+fn encode_sequence(
+    tokenizer: &ExTokenizerImpl,
+    sequence: &str,
+    offset_type: OffsetType,
+) -> tokenizers::Result<tokenizers::Encoding> {
+    tokenize_sequence(tokenizer, sequence)?.into_encoding(None, 0, offset_type.into())
+}
+
+#[derive(NifTaggedEnum)]
+pub enum WindowOption {
+    Stride(usize),
+    AddSpecialTokens(bool),
+    OffsetType(OffsetType),
+}
+
+struct WindowOpts {
+    max_length: usize,
+    stride: usize,
+    add_special_tokens: bool,
+    offset_type: OffsetType,
+}
+
+impl WindowOpts {
+    fn new(
+        tokenizer: &ExTokenizerImpl,
+        max_length: usize,
+        options: Vec<WindowOption>,
+    ) -> Result<Self, ExTokenizersError> {
+        let mut opts = Self {
+            max_length,
+            stride: 0,
+            add_special_tokens: true,
+            offset_type: OffsetType::Bytes,
+        };
+        options.into_iter().for_each(|option| match option {
+            WindowOption::Stride(stride) => opts.stride = stride,
+            WindowOption::AddSpecialTokens(add_special_tokens) => {
+                opts.add_special_tokens = add_special_tokens
+            }
+            WindowOption::OffsetType(offset_type) => opts.offset_type = offset_type,
+        });
+
+        // Windows hold max_length tokens including the special tokens,
+        // the same as with truncation
+        if opts.add_special_tokens {
+            let n_added_tokens = tokenizer
+                .get_post_processor()
+                .map_or(0, |post_processor| post_processor.added_tokens(false));
+            if opts.max_length <= n_added_tokens {
+                return Err(ExTokenizersError::Other(format!(
+                    "max_length must be greater than {n_added_tokens} to fit the special tokens"
+                )));
+            }
+            opts.max_length -= n_added_tokens;
         }
-        EncodeOption::EncodingTransformations(encoding_transformations) => {
-            opts.encoding_transformations = encoding_transformations
+        if opts.stride >= opts.max_length {
+            return Err(ExTokenizersError::Other(format!(
+                "stride must be less than {}, the number of tokens per window \
+                 excluding special tokens",
+                opts.max_length
+            )));
         }
-    });
-    let inputs = inputs
-        .iter()
-        .map(term_to_encode_input)
-        .collect::<Result<Vec<EncodeInput>, ExTokenizersError>>()?;
-    let mut encodings = tokenizer
-        .resource
-        .0
-        .encode_batch(inputs, opts.add_special_tokens)?;
+        Ok(opts)
+    }
+}
 
-    // Applying transformations (if any)
-    for encoding in encodings.iter_mut() {
-        apply_transformations(encoding, &opts.encoding_transformations);
+fn encode_windows(
+    tokenizer: &ExTokenizerImpl,
+    sequence: &str,
+    opts: &WindowOpts,
+) -> tokenizers::Result<Vec<(usize, ExTokenizersEncoding)>> {
+    let mut encoding = encode_sequence(tokenizer, sequence, opts.offset_type)?;
+    encoding.truncate(
+        opts.max_length,
+        opts.stride,
+        tokenizers::TruncationDirection::Right,
+    );
+    let overflowing = encoding.take_overflowing();
+
+    std::iter::once(encoding)
+        .chain(overflowing)
+        .enumerate()
+        .map(|(index, window)| {
+            let window = match tokenizer.get_post_processor() {
+                Some(post_processor) => {
+                    post_processor.process(window, None, opts.add_special_tokens)?
+                }
+                None => window,
+            };
+            Ok((index, window.into()))
+        })
+        .collect()
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn tokenizer_encode_windows(
+    tokenizer: ExTokenizersTokenizer,
+    input: &str,
+    max_length: usize,
+    options: Vec<WindowOption>,
+) -> Result<Vec<(usize, ExTokenizersEncoding)>, ExTokenizersError> {
+    let tokenizer = &tokenizer.resource.0;
+    let opts = WindowOpts::new(tokenizer, max_length, options)?;
+    Ok(encode_windows(tokenizer, input, &opts)?)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn tokenizer_encode_windows_batch(
+    tokenizer: ExTokenizersTokenizer,
+    inputs: Vec<String>,
+    max_length: usize,
+    options: Vec<WindowOption>,
+) -> Result<Vec<Vec<(usize, ExTokenizersEncoding)>>, ExTokenizersError> {
+    let tokenizer = &tokenizer.resource.0;
+    let opts = WindowOpts::new(tokenizer, max_length, options)?;
+    let windows = inputs
+        .into_maybe_par_iter()
+        .map(|input| encode_windows(tokenizer, &input, &opts))
+        .collect::<tokenizers::Result<Vec<_>>>()?;
+    Ok(windows)
+}
+
+#[derive(NifTaggedEnum)]
+pub enum CountOption {
+    AddSpecialTokens(bool),
+    Truncate(bool),
+}
+
+struct CountOpts {
+    add_special_tokens: bool,
+    truncate: bool,
+}
+
+impl Default for CountOpts {
+    fn default() -> Self {
+        Self {
+            add_special_tokens: true,
+            truncate: true,
+        }
     }
+}
 
-    let ex_encodings = encodings
+fn count_tokens(
+    tokenizer: &ExTokenizerImpl,
+    sequence: &str,
+    opts: &CountOpts,
+) -> tokenizers::Result<usize> {
+    let pre_tokenized = tokenize_sequence(tokenizer, sequence)?;
+    let n_tokens: usize = pre_tokenized
+        .get_splits(OffsetReferential::Original.into(), OffsetType::Bytes.into())
         .into_iter()
-        .map(|encoding| encoding.into())
-        .collect();
-    Ok(ex_encodings)
+        .map(|(_, _, tokens)| tokens.as_ref().map_or(0, Vec::len))
+        .sum();
+    let n_added_tokens = match tokenizer.get_post_processor() {
+        Some(post_processor) if opts.add_special_tokens => post_processor.added_tokens(false),
+        _ => 0,
+    };
+    // Truncation keeps at most `max_length` tokens, special tokens included
+    let count = n_tokens + n_added_tokens;
+    match tokenizer.get_truncation() {
+        Some(truncation) if opts.truncate => Ok(count.min(truncation.max_length)),
+        _ => Ok(count),
+    }
+}
+
+fn count_opts(options: Vec<CountOption>) -> CountOpts {
+    let mut opts = CountOpts::default();
+    options.into_iter().for_each(|option| match option {
+        CountOption::AddSpecialTokens(value) => opts.add_special_tokens = value,
+        CountOption::Truncate(value) => opts.truncate = value,
+    });
+    opts
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn tokenizer_count_tokens(
+    tokenizer: ExTokenizersTokenizer,
+    input: &str,
+    options: Vec<CountOption>,
+) -> Result<usize, ExTokenizersError> {
+    let opts = count_opts(options);
+    Ok(count_tokens(&tokenizer.resource.0, input, &opts)?)
+}
+
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn tokenizer_count_tokens_batch(
+    tokenizer: ExTokenizersTokenizer,
+    inputs: Vec<String>,
+    options: Vec<CountOption>,
+) -> Result<Vec<usize>, ExTokenizersError> {
+    let opts = count_opts(options);
+    let tokenizer = &tokenizer.resource.0;
+    let counts = inputs
+        .into_maybe_par_iter()
+        .map(|input| count_tokens(tokenizer, &input, &opts))
+        .collect::<tokenizers::Result<Vec<_>>>()?;
+    Ok(counts)
 }
 
 #[derive(NifTaggedEnum)]
@@ -593,83 +1038,173 @@ pub fn tokenizer_post_processing(
 // / Training
 // /////////////////////////////////////////////////////////////////////////////
 
-#[rustler::nif]
+#[rustler::nif(schedule = "DirtyCpu")]
 pub fn tokenizer_train_from_files(
     tokenizer: ExTokenizersTokenizer,
     files: Vec<String>,
     trainer: Option<ExTokenizersTrainer>,
+    progress: Option<LocalPid>,
 ) -> Result<ExTokenizersTokenizer, ExTokenizersError> {
+    train_tokenizer(
+        &tokenizer,
+        trainer,
+        progress,
+        None,
+        |new_tokenizer, trainer| {
+            new_tokenizer.train_from_files(trainer, files)?;
+            Ok(())
+        },
+    )
+}
+
+/// How many chunks of sequences can be queued before feeding blocks.
+const BUFFERED_CHUNKS: usize = 2;
+
+/// Training on sequences fed in chunks, see
+/// `tokenizer_train_from_iterator_start`.
+pub struct ExTokenizersIteratorTrainingRef {
+    // Dropping the sender ends the input
+    input: Mutex<Option<SyncSender<Vec<String>>>>,
+    cancel: Arc<AtomicBool>,
+    thread: Mutex<Option<JoinHandle<Result<ExTokenizersTokenizer, ExTokenizersError>>>>,
+}
+
+impl ExTokenizersIteratorTrainingRef {
+    fn close_input(&self) {
+        self.input
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+}
+
+impl Drop for ExTokenizersIteratorTrainingRef {
+    // Training nobody waits for anymore, for example because the caller
+    // exited while feeding it, is stopped instead of running on
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Starts training a copy of `tokenizer` on the sequences given to
+/// `tokenizer_train_from_iterator_feed`, until the result is requested
+/// with `tokenizer_train_from_iterator`.
+///
+/// The training thread reads the chunks as they come, so only a bounded
+/// number of them is kept in memory.
+#[rustler::nif]
+pub fn tokenizer_train_from_iterator_start(
+    tokenizer: ExTokenizersTokenizer,
+    trainer: Option<ExTokenizersTrainer>,
+    progress: Option<LocalPid>,
+) -> ResourceArc<ExTokenizersIteratorTrainingRef> {
+    let (sender, receiver) = mpsc::sync_channel::<Vec<String>>(BUFFERED_CHUNKS);
+    let cancel = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let cancel = cancel.clone();
+        std::thread::spawn(move || {
+            train_tokenizer(
+                &tokenizer,
+                trainer,
+                progress,
+                Some(cancel),
+                |new_tokenizer, trainer| {
+                    new_tokenizer.train(trainer, receiver.into_iter().flatten())?;
+                    Ok(())
+                },
+            )
+        })
+    };
+    ResourceArc::new(ExTokenizersIteratorTrainingRef {
+        input: Mutex::new(Some(sender)),
+        cancel,
+        thread: Mutex::new(Some(thread)),
+    })
+}
+
+/// Sends a chunk of sequences to the training thread, waiting while
+/// previous chunks are still queued. Returns `:closed` if training no
+/// longer reads input, because it was cancelled or has failed.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn tokenizer_train_from_iterator_feed(
+    training: ResourceArc<ExTokenizersIteratorTrainingRef>,
+    sequences: Vec<String>,
+) -> Atom {
+    // The sender is cloned so that closing the input doesn't wait for
+    // a blocked feed
+    let input = training
+        .input
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    match input.map(|sender| sender.send(sequences)) {
+        Some(Ok(())) => atoms::ok(),
+        _ => atoms::closed(),
+    }
+}
+
+#[rustler::nif]
+pub fn tokenizer_train_from_iterator_cancel(
+    training: ResourceArc<ExTokenizersIteratorTrainingRef>,
+) -> Atom {
+    training.cancel.store(true, Ordering::Relaxed);
+    // Wakes up the training thread if it is waiting for input
+    training.close_input();
+    atoms::ok()
+}
+
+/// Ends the input and waits for training to finish.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn tokenizer_train_from_iterator(
+    training: ResourceArc<ExTokenizersIteratorTrainingRef>,
+) -> Result<ExTokenizersTokenizer, ExTokenizersError> {
+    training.close_input();
+    let thread = training
+        .thread
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+        .ok_or_else(|| ExTokenizersError::Other("training has already finished".to_string()))?;
+    thread
+        .join()
+        .map_err(|_| ExTokenizersError::Internal("training thread panicked".to_string()))?
+}
+
+/// Trains a copy of `tokenizer`, leaving the original model untouched.
+///
+/// `train` receives the copied tokenizer together with a fresh trainer
+/// (either cloned from `trainer` or the model's default one) and is
+/// expected to feed it with the training data. When `progress` is given,
+/// the trainer reports its progress to that process, and when `cancel` is
+/// given, training stops early once it is set.
+pub fn train_tokenizer<F>(
+    tokenizer: &ExTokenizersTokenizer,
+    trainer: Option<ExTokenizersTrainer>,
+    progress: Option<LocalPid>,
+    cancel: Option<Arc<AtomicBool>>,
+    train: F,
+) -> Result<ExTokenizersTokenizer, ExTokenizersError>
+where
+    F: FnOnce(&mut ExTokenizerImpl, &mut ReportingTrainer) -> tokenizers::Result<()>,
+{
     // Current version of rust lib panics on retrainging with another trainer.
     // This leads to unpredicted nif behaviour.
     // Unwind can be removed after fixes https://github.com/huggingface/tokenizers/issues/525
 
-    let result = panic::catch_unwind(|| {
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
         let mut new_tokenizer = tokenizer.resource.0.clone();
-        let new_model = ExTokenizersModel::new(
-            tokenizer
-                .resource
-                .0
-                .get_model()
-                .resource
-                .0
-                .read()
-                .unwrap()
-                .clone(),
-        );
+        let new_model =
+            ExTokenizersModel::new(tokenizer.resource.0.get_model().resource.read()?.clone());
         new_tokenizer.with_model(new_model);
-        match trainer {
-            Some(trainer) => {
-                // TODO: call clone on trainer wrapper once available (tokenizers > 0.13.3)
-                // see https://github.com/huggingface/tokenizers/pull/1317
-                let trainer = match trainer.resource.0.read().unwrap().deref() {
-                    TrainerWrapper::BpeTrainer(trainer) => {
-                        TrainerWrapper::BpeTrainer(trainer.clone())
-                    }
-                    TrainerWrapper::WordPieceTrainer(trainer) => {
-                        // WordPieceTrainer does not derive clone so we re-build by hand
-                        let mut builder = WordPieceTrainerBuilder::default()
-                            .min_frequency(trainer.min_frequency())
-                            .vocab_size(trainer.vocab_size())
-                            .show_progress(trainer.show_progress())
-                            .special_tokens(trainer.special_tokens().to_vec())
-                            .initial_alphabet(trainer.initial_alphabet().clone());
-                        builder = match trainer.limit_alphabet() {
-                            Some(limit_alphabet) => builder.limit_alphabet(limit_alphabet),
-                            None => builder,
-                        };
-                        builder = match trainer.continuing_subword_prefix() {
-                            Some(continuing_subword_prefix) => builder
-                                .continuing_subword_prefix(continuing_subword_prefix.to_string()),
-                            None => builder,
-                        };
-                        builder = match trainer.end_of_word_suffix() {
-                            Some(end_of_word_suffix) => {
-                                builder.end_of_word_suffix(end_of_word_suffix.to_string())
-                            }
-                            None => builder,
-                        };
-                        TrainerWrapper::WordPieceTrainer(builder.build())
-                    }
-                    TrainerWrapper::WordLevelTrainer(trainer) => {
-                        TrainerWrapper::WordLevelTrainer(trainer.clone())
-                    }
-                    TrainerWrapper::UnigramTrainer(trainer) => {
-                        TrainerWrapper::UnigramTrainer(trainer.clone())
-                    }
-                };
-
-                let mut trainer = ExTokenizersTrainer::new(trainer);
-
-                new_tokenizer.train_from_files(&mut trainer, files)
-            }
-            None => {
-                // Trainer is not defined, using default
-                let mut default_trainer = new_tokenizer.get_model().get_trainer();
-                new_tokenizer.train_from_files(&mut default_trainer, files)
-            }
-        }?;
+        let trainer = match trainer {
+            Some(trainer) => clone_trainer(&trainer)?,
+            // Trainer is not defined, using default
+            None => new_tokenizer.get_model().get_trainer(),
+        };
+        let mut trainer = ReportingTrainer::new(trainer, progress, cancel);
+        train(&mut new_tokenizer, &mut trainer)?;
         Ok(new_tokenizer)
-    });
+    }));
     let new_tokenizer = match result {
         Ok(value) => value,
         Err(panic) => {
@@ -681,5 +1216,45 @@ pub fn tokenizer_train_from_files(
         }
     }?;
 
-    Ok(new_tokenizer.into())
+    Ok(tokenizer.derive(new_tokenizer))
+}
+
+fn clone_trainer(trainer: &ExTokenizersTrainer) -> Result<ExTokenizersTrainer, ExTokenizersError> {
+    // TODO: call clone on trainer wrapper once available (tokenizers > 0.13.3)
+    // see https://github.com/huggingface/tokenizers/pull/1317
+    let trainer = match trainer.resource.read()?.deref() {
+        TrainerWrapper::BpeTrainer(trainer) => TrainerWrapper::BpeTrainer(trainer.clone()),
+        TrainerWrapper::WordPieceTrainer(trainer) => {
+            // WordPieceTrainer does not derive clone so we re-build by hand
+            let mut builder = WordPieceTrainerBuilder::default()
+                .min_frequency(trainer.min_frequency())
+                .vocab_size(trainer.vocab_size())
+                .show_progress(trainer.show_progress())
+                .special_tokens(trainer.special_tokens().to_vec())
+                .initial_alphabet(trainer.initial_alphabet().clone());
+            builder = match trainer.limit_alphabet() {
+                Some(limit_alphabet) => builder.limit_alphabet(limit_alphabet),
+                None => builder,
+            };
+            builder = match trainer.continuing_subword_prefix() {
+                Some(continuing_subword_prefix) => {
+                    builder.continuing_subword_prefix(continuing_subword_prefix.to_string())
+                }
+                None => builder,
+            };
+            builder = match trainer.end_of_word_suffix() {
+                Some(end_of_word_suffix) => {
+                    builder.end_of_word_suffix(end_of_word_suffix.to_string())
+                }
+                None => builder,
+            };
+            TrainerWrapper::WordPieceTrainer(builder.build())
+        }
+        TrainerWrapper::WordLevelTrainer(trainer) => {
+            TrainerWrapper::WordLevelTrainer(trainer.clone())
+        }
+        TrainerWrapper::UnigramTrainer(trainer) => TrainerWrapper::UnigramTrainer(trainer.clone()),
+    };
+
+    Ok(ExTokenizersTrainer::new(trainer))
 }
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+use tokenizers::tokenizer::{AddedToken, Token};
+use tokenizers::{Encoding, PostProcessor};
+
+use crate::error::ExTokenizersError;
+use crate::post_processors::{bos_eos_template, ExTokenizersPostProcessor};
+use crate::tokenizer::ExTokenizerImpl;
+use crate::util::Direction;
+
+// Special token roles, as named in tokenizer_config.json and
+// special_tokens_map.json
+const ROLES: [&str; 7] = [
+    "bos_token",
+    "eos_token",
+    "unk_token",
+    "sep_token",
+    "pad_token",
+    "cls_token",
+    "mask_token",
+];
+
+// transformers uses int(1e30) as model_max_length when there is no limit
+const NO_MAX_LENGTH: u64 = 1_000_000_000_000_000_000;
+
+/// Settings from tokenizer_config.json that are not part of the
+/// tokenizer pipeline.
+#[derive(Clone, Default)]
+pub struct TokenizerConfig {
+    /// Special token content by role, such as `"bos_token" => "<s>"`.
+    pub special_tokens: HashMap<String, String>,
+    /// Jinja template formatting chat messages into a prompt.
+    pub chat_template: Option<String>,
+}
+
+fn read_json(path: &str) -> Result<Map<String, Value>, ExTokenizersError> {
+    let data = std::fs::read_to_string(path)?;
+    match serde_json::from_str(&data)? {
+        Value::Object(map) => Ok(map),
+        _ => Err(ExTokenizersError::Other(format!(
+            "expected {path} to contain a JSON object"
+        ))),
+    }
+}
+
+/// Reads a special token, given either as a string or as a serialized
+/// `AddedToken` object.
+fn parse_token(value: &Value) -> Option<AddedToken> {
+    match value {
+        Value::String(content) => Some(AddedToken::from(content.clone(), true)),
+        Value::Object(token) => {
+            let flag = |key: &str, default: bool| {
+                token.get(key).and_then(Value::as_bool).unwrap_or(default)
+            };
+            let content = token.get("content")?.as_str()?;
+            Some(
+                AddedToken::from(content, flag("special", true))
+                    .single_word(flag("single_word", false))
+                    .lstrip(flag("lstrip", false))
+                    .rstrip(flag("rstrip", false))
+                    .normalized(flag("normalized", false)),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Reads the chat template, given either as a string or as a list of
+/// named templates, in which case the "default" one is used.
+fn parse_chat_template(value: &Value) -> Option<String> {
+    match value {
+        Value::String(template) => Some(template.clone()),
+        Value::Array(templates) => {
+            templates
+                .iter()
+                .find_map(|template| match template.get("name")?.as_str()? {
+                    "default" => Some(template.get("template")?.as_str()?.to_string()),
+                    _ => None,
+                })
+        }
+        _ => None,
+    }
+}
+
+fn parse_side(config: &Map<String, Value>, key: &str) -> Option<Direction> {
+    match config.get(key)?.as_str()? {
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+fn role_token(
+    tokenizer: &ExTokenizerImpl,
+    config: &TokenizerConfig,
+    role: &str,
+) -> Result<(String, u32), ExTokenizersError> {
+    let token = config.special_tokens.get(role).ok_or_else(|| {
+        ExTokenizersError::UnknownToken(format!("the tokenizer config has no {role}"))
+    })?;
+    let id = tokenizer
+        .token_to_id(token)
+        .ok_or_else(|| ExTokenizersError::UnknownToken(token.clone()))?;
+    Ok((token.clone(), id))
+}
+
+type SpecialTokens = Vec<(String, u32)>;
+
+/// Returns the tokens added by the post-processor before and after a
+/// single sequence.
+fn post_processor_tokens(
+    tokenizer: &ExTokenizerImpl,
+) -> Result<(SpecialTokens, SpecialTokens), ExTokenizersError> {
+    let Some(post_processor) = tokenizer.get_post_processor() else {
+        return Ok((vec![], vec![]));
+    };
+    // A single token with an id no vocabulary uses marks the sequence
+    let sequence = Encoding::from_tokens(vec![Token::new(u32::MAX, String::new(), (0, 0))], 0);
+    let encoding = post_processor.process(sequence, None, true)?;
+    let mut tokens = encoding
+        .get_tokens()
+        .iter()
+        .cloned()
+        .zip(encoding.get_ids().iter().copied());
+    let bos = tokens
+        .by_ref()
+        .take_while(|(_, id)| *id != u32::MAX)
+        .collect();
+    Ok((bos, tokens.collect()))
+}
+
+/// Applies tokenizer_config.json and special_tokens_map.json to the
+/// tokenizer, following what transformers does when loading a fast
+/// tokenizer:
+///
+///   * special tokens are registered under their roles, entries from
+///     tokenizer_config.json taking precedence
+///   * `pad_token` and `padding_side` configure padding
+///   * `model_max_length` and `truncation_side` configure truncation
+///   * `add_bos_token` and `add_eos_token` add or remove the BOS and EOS
+///     tokens, replacing the post-processor when it doesn't match them
+///
+pub fn apply_config(
+    tokenizer: &mut ExTokenizerImpl,
+    tokenizer_config: Option<&str>,
+    special_tokens_map: Option<&str>,
+) -> Result<TokenizerConfig, ExTokenizersError> {
+    let mut settings = match special_tokens_map {
+        Some(path) => read_json(path)?,
+        None => Map::new(),
+    };
+    if let Some(path) = tokenizer_config {
+        settings.extend(read_json(path)?);
+    }
+
+    let mut config = TokenizerConfig::default();
+    let mut special_tokens = vec![];
+    for role in ROLES {
+        if let Some(token) = settings.get(role).and_then(parse_token) {
+            config
+                .special_tokens
+                .insert(role.to_string(), token.content.clone());
+            special_tokens.push(token);
+        }
+    }
+    config.chat_template = settings.get("chat_template").and_then(parse_chat_template);
+
+    if let Some(Value::Array(tokens)) = settings.get("additional_special_tokens") {
+        special_tokens.extend(tokens.iter().filter_map(parse_token));
+    }
+
+    // Tokens already defined by tokenizer.json keep their settings
+    let added_tokens = tokenizer.get_added_tokens_decoder();
+    special_tokens.retain(|token| {
+        !added_tokens
+            .values()
+            .any(|added| added.content == token.content)
+    });
+    tokenizer.add_special_tokens(&special_tokens);
+
+    if let Some(pad_token) = config.special_tokens.get("pad_token") {
+        if let Some(pad_id) = tokenizer.token_to_id(pad_token) {
+            let mut padding = tokenizer.get_padding().cloned().unwrap_or_default();
+            padding.pad_token = pad_token.clone();
+            padding.pad_id = pad_id;
+            if let Some(direction) = parse_side(&settings, "padding_side") {
+                padding.direction = direction.into();
+            }
+            tokenizer.with_padding(Some(padding));
+        }
+    }
+
+    let max_length = settings.get("model_max_length").and_then(Value::as_u64);
+    if let Some(max_length) = max_length.filter(|length| *length < NO_MAX_LENGTH) {
+        let mut truncation = tokenizer.get_truncation().cloned().unwrap_or_default();
+        truncation.max_length = max_length as usize;
+        if let Some(direction) = parse_side(&settings, "truncation_side") {
+            truncation.direction = direction.into();
+        }
+        tokenizer.with_truncation(Some(truncation))?;
+    }
+
+    // The post-processor from tokenizer.json is kept unless it doesn't
+    // already add the requested tokens, since it may also set offsets
+    // or other special tokens. Otherwise only the configured sides are
+    // replaced, keeping the tokens it adds on the other side
+    let (current_bos, current_eos) = post_processor_tokens(tokenizer)?;
+    let side = |key: &str, role: &str, current: &SpecialTokens| -> Result<_, ExTokenizersError> {
+        match settings.get(key).and_then(Value::as_bool) {
+            Some(true) => Ok(vec![role_token(tokenizer, &config, role)?]),
+            Some(false) => Ok(current
+                .iter()
+                .filter(|(token, _)| config.special_tokens.get(role) != Some(token))
+                .cloned()
+                .collect()),
+            None => Ok(current.clone()),
+        }
+    };
+    let bos = side("add_bos_token", "bos_token", &current_bos)?;
+    let eos = side("add_eos_token", "eos_token", &current_eos)?;
+    if bos != current_bos || eos != current_eos {
+        let template = bos_eos_template(bos, eos)?;
+        tokenizer.with_post_processor(ExTokenizersPostProcessor::new(template));
+    }
+
+    Ok(config)
+}
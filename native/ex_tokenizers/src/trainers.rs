@@ -1,15 +1,16 @@
 use std::collections::HashSet;
 use std::ops::Deref;
-use std::sync::RwLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use rustler::NifTaggedEnum;
-use rustler::ResourceArc;
+use rustler::{Encoder, Env, LocalPid, NifTaggedEnum, OwnedEnv, ResourceArc, Term};
 use tokenizers::models::bpe::BpeTrainerBuilder;
 use tokenizers::models::unigram::UnigramTrainerBuilder;
 use tokenizers::models::wordlevel::WordLevelTrainerBuilder;
 use tokenizers::models::wordpiece::WordPieceTrainerBuilder;
-use tokenizers::models::TrainerWrapper;
-use tokenizers::AddedToken;
+use tokenizers::models::{ModelWrapper, TrainerWrapper};
+use tokenizers::{AddedToken, Model};
 
 use crate::added_token::AddedTokenInput;
 use crate::error::ExTokenizersError;
@@ -29,16 +30,12 @@ impl tokenizers::Trainer for ExTokenizersTrainer {
     type Model = ExTokenizersModel;
 
     fn should_show_progress(&self) -> bool {
-        self.resource.0.read().unwrap().should_show_progress()
+        self.resource.read_infallible().should_show_progress()
     }
 
     fn train(&self, model: &mut Self::Model) -> tokenizers::Result<Vec<tokenizers::AddedToken>> {
-        let special_tokens = self
-            .resource
-            .0
-            .read()
-            .unwrap()
-            .train(&mut model.resource.0.write().unwrap())?;
+        let mut model = model.resource.write()?;
+        let special_tokens = self.resource.read()?.train(&mut model)?;
 
         Ok(special_tokens)
     }
@@ -49,7 +46,7 @@ impl tokenizers::Trainer for ExTokenizersTrainer {
         S: AsRef<str> + Send,
         F: Fn(&str) -> tokenizers::Result<Vec<String>> + Sync,
     {
-        self.resource.0.write().unwrap().feed(iterator, process)
+        self.resource.write()?.feed(iterator, process)
     }
 }
 
@@ -60,6 +57,19 @@ impl ExTokenizersTrainerRef {
     {
         Self(RwLock::new(data.into()))
     }
+
+    pub fn read(&self) -> Result<RwLockReadGuard<'_, TrainerWrapper>, ExTokenizersError> {
+        Ok(self.0.read()?)
+    }
+
+    pub fn write(&self) -> Result<RwLockWriteGuard<'_, TrainerWrapper>, ExTokenizersError> {
+        Ok(self.0.write()?)
+    }
+
+    /// Same as `read`, for trait methods that cannot return an error.
+    fn read_infallible(&self) -> RwLockReadGuard<'_, TrainerWrapper> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
 impl ExTokenizersTrainer {
@@ -73,13 +83,253 @@ impl ExTokenizersTrainer {
     }
 }
 
+///////////////////////////////////////////////////////////////////////////////
+/// Progress
+///////////////////////////////////////////////////////////////////////////////
+
+#[derive(rustler::NifUnitEnum, Clone, Copy)]
+pub enum TrainingPhase {
+    PreProcessing,
+    Training,
+    Done,
+}
+
+mod atoms {
+    rustler::atoms! {
+        training_progress,
+        phase,
+        sequences,
+        words,
+        vocab_size,
+        merges,
+    }
+}
+
+/// How many sequences are fed between two pre-processing events.
+const PROGRESS_INTERVAL: u64 = 10_000;
+
+pub struct TrainingProgressEvent {
+    phase: TrainingPhase,
+    sequences: u64,
+    words: u64,
+    // Only known once the model is trained
+    trained: Option<TrainedModel>,
+}
+
+pub struct TrainedModel {
+    vocab_size: usize,
+    merges: Option<usize>,
+}
+
+impl Encoder for TrainingProgressEvent {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let mut pairs = vec![
+            (atoms::phase().encode(env), self.phase.encode(env)),
+            (atoms::sequences().encode(env), self.sequences.encode(env)),
+            (atoms::words().encode(env), self.words.encode(env)),
+        ];
+        if let Some(trained) = &self.trained {
+            pairs.push((
+                atoms::vocab_size().encode(env),
+                trained.vocab_size.encode(env),
+            ));
+            if let Some(merges) = trained.merges {
+                pairs.push((atoms::merges().encode(env), merges.encode(env)));
+            }
+        }
+        Term::map_from_pairs(env, &pairs).expect("progress event keys are unique")
+    }
+}
+
+/// Sends `{:training_progress, event}` messages to an Elixir process.
+pub struct TrainingProgress {
+    sender: mpsc::Sender<TrainingProgressEvent>,
+    sequences: AtomicU64,
+    words: AtomicU64,
+}
+
+impl TrainingProgress {
+    pub fn new(pid: LocalPid) -> Self {
+        let (sender, receiver) = mpsc::channel::<TrainingProgressEvent>();
+
+        // Training runs on scheduler and rayon threads, and only threads
+        // unmanaged by the VM can send from an `OwnedEnv`, so messages are
+        // forwarded from a dedicated thread. It stops once the sender is dropped.
+        std::thread::spawn(move || {
+            let mut env = OwnedEnv::new();
+            for event in receiver {
+                // The receiving process may be gone, in which case we just drop the event
+                let _ =
+                    env.send_and_clear(&pid, |env| (atoms::training_progress(), event).encode(env));
+            }
+        });
+
+        Self {
+            sender,
+            sequences: AtomicU64::new(0),
+            words: AtomicU64::new(0),
+        }
+    }
+
+    fn report(&self, phase: TrainingPhase, trained: Option<TrainedModel>) {
+        let _ = self.sender.send(TrainingProgressEvent {
+            phase,
+            sequences: self.sequences.load(Ordering::Relaxed),
+            words: self.words.load(Ordering::Relaxed),
+            trained,
+        });
+    }
+
+    fn add_sequence(&self) {
+        let sequences = self.sequences.fetch_add(1, Ordering::Relaxed) + 1;
+        if sequences.is_multiple_of(PROGRESS_INTERVAL) {
+            self.report(TrainingPhase::PreProcessing, None);
+        }
+    }
+
+    fn add_words(&self, words: usize) {
+        self.words.fetch_add(words as u64, Ordering::Relaxed);
+    }
+}
+
+/// Trainer reporting its progress to an Elixir process, if any, and
+/// stopping early once `cancel` is set.
+///
+/// Cancellation is only observed while feeding the trainer and before the
+/// training itself starts, as the underlying trainers can't be interrupted.
+pub struct ReportingTrainer {
+    trainer: ExTokenizersTrainer,
+    progress: Option<TrainingProgress>,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl ReportingTrainer {
+    pub fn new(
+        trainer: ExTokenizersTrainer,
+        progress: Option<LocalPid>,
+        cancel: Option<Arc<AtomicBool>>,
+    ) -> Self {
+        Self {
+            trainer,
+            progress: progress.map(TrainingProgress::new),
+            cancel,
+        }
+    }
+}
+
+/// Counts the merges learned by BPE models. WordPiece models are trained
+/// as BPE models whose merges are then dropped, so for them this counts
+/// the tokens those merges added instead.
+fn learned_merges(trainer: &TrainerWrapper, model: &ExTokenizersModel) -> Option<usize> {
+    match model.resource.read().ok()?.deref() {
+        ModelWrapper::BPE(bpe) => serde_json::to_value(bpe).ok()?["merges"]
+            .as_array()
+            .map(Vec::len),
+        ModelWrapper::WordPiece(wordpiece) => {
+            let TrainerWrapper::WordPieceTrainer(trainer) = trainer else {
+                return None;
+            };
+            let prefix = wordpiece.continuing_subword_prefix.as_str();
+            let suffix = trainer.end_of_word_suffix().as_deref().unwrap_or_default();
+            // The alphabet holds single characters, with the prefix when
+            // they continue a word and the suffix when they end one
+            let is_alphabet = |token: &str| {
+                let symbol = token.strip_prefix(prefix).unwrap_or(token);
+                let symbol = symbol.strip_suffix(suffix).unwrap_or(symbol);
+                token.chars().count() == 1 || symbol.chars().count() == 1
+            };
+            let merged = wordpiece
+                .get_vocab()
+                .into_keys()
+                .filter(|token| {
+                    !is_alphabet(token)
+                        && !trainer
+                            .special_tokens()
+                            .iter()
+                            .any(|special| &special.content == token)
+                })
+                .count();
+            Some(merged)
+        }
+        _ => None,
+    }
+}
+
+fn check_cancelled(cancel: Option<&AtomicBool>) -> tokenizers::Result<()> {
+    match cancel {
+        Some(cancel) if cancel.load(Ordering::Relaxed) => Err("Training cancelled".into()),
+        _ => Ok(()),
+    }
+}
+
+impl tokenizers::Trainer for ReportingTrainer {
+    type Model = ExTokenizersModel;
+
+    fn should_show_progress(&self) -> bool {
+        self.trainer.should_show_progress()
+    }
+
+    fn train(&self, model: &mut Self::Model) -> tokenizers::Result<Vec<tokenizers::AddedToken>> {
+        check_cancelled(self.cancel.as_deref())?;
+
+        let progress = match &self.progress {
+            Some(progress) => progress,
+            None => return self.trainer.train(model),
+        };
+
+        // The trainers of tokenizers 0.19 don't expose their merge or
+        // pruning loops, so nothing can be reported while the model trains,
+        // and the merges are only counted once it is done
+        progress.report(TrainingPhase::Training, None);
+        let special_tokens = self.trainer.train(model)?;
+        let trained = TrainedModel {
+            vocab_size: model.get_vocab_size(),
+            merges: learned_merges(&self.trainer.resource.read_infallible(), model),
+        };
+        progress.report(TrainingPhase::Done, Some(trained));
+
+        Ok(special_tokens)
+    }
+
+    fn feed<I, S, F>(&mut self, iterator: I, process: F) -> tokenizers::Result<()>
+    where
+        I: Iterator<Item = S> + Send,
+        S: AsRef<str> + Send,
+        F: Fn(&str) -> tokenizers::Result<Vec<String>> + Sync,
+    {
+        let progress = self.progress.as_ref();
+        let cancel = self.cancel.as_deref();
+
+        self.trainer.feed(
+            iterator.inspect(|_| {
+                if let Some(progress) = progress {
+                    progress.add_sequence()
+                }
+            }),
+            |sequence| {
+                check_cancelled(cancel)?;
+                let words = process(sequence)?;
+                if let Some(progress) = progress {
+                    progress.add_words(words.len())
+                }
+                Ok(words)
+            },
+        )?;
+        if let Some(progress) = progress {
+            progress.report(TrainingPhase::PreProcessing, None);
+        }
+
+        Ok(())
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 /// Inspection
 ///////////////////////////////////////////////////////////////////////////////
 
 #[rustler::nif]
-pub fn trainers_info(trainer: ExTokenizersTrainer) -> Info {
-    match &trainer.resource.0.read().unwrap().deref() {
+pub fn trainers_info(trainer: ExTokenizersTrainer) -> Result<Info, rustler::Error> {
+    Ok(match &trainer.resource.read()?.deref() {
         TrainerWrapper::BpeTrainer(trainer) => new_info!(
             trainer_type: "bpe",
             min_frequency: trainer.min_frequency,
@@ -112,7 +362,7 @@ pub fn trainers_info(trainer: ExTokenizersTrainer) -> Info {
             unk_token: trainer.unk_token.clone(),
             max_piece_length: trainer.max_piece_length
         ),
-    }
+    })
 }
 
 ///////////////////////////////////////////////////////////////////////////////
@@ -322,7 +572,7 @@ pub fn trainers_unigram_trainer(
     options: Vec<UnigramOption>,
 ) -> Result<ExTokenizersTrainer, ExTokenizersError> {
     let mut builder = tokenizers::models::unigram::UnigramTrainer::builder();
-    populate_unigram_options_to_builder(&mut builder, options).map_err(anyhow::Error::from)?;
+    populate_unigram_options_to_builder(&mut builder, options)?;
     let model = builder.build().map_err(anyhow::Error::from)?;
     Ok(ExTokenizersTrainer::new(model))
 }
@@ -0,0 +1,222 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use rustler::{Encoder, Env, LocalPid, OwnedEnv, ResourceArc, Term};
+
+use crate::error::ExTokenizersError;
+use crate::tokenizer::{train_tokenizer, ExTokenizerImpl, ExTokenizersTokenizer};
+use crate::trainers::{ExTokenizersTrainer, ReportingTrainer};
+
+mod atoms {
+    rustler::atoms! {
+        ok,
+        error,
+        running,
+        cancelled,
+        closed,
+        training_job_done,
+    }
+}
+
+pub enum TrainingJobStatus {
+    Running,
+    Done(ExTokenizersTokenizer),
+    Failed(ExTokenizersError),
+    Cancelled,
+}
+
+impl Encoder for TrainingJobStatus {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self {
+            TrainingJobStatus::Running => atoms::running().encode(env),
+            TrainingJobStatus::Done(tokenizer) => (atoms::ok(), tokenizer).encode(env),
+            TrainingJobStatus::Failed(error) => (atoms::error(), error).encode(env),
+            TrainingJobStatus::Cancelled => (atoms::error(), atoms::cancelled()).encode(env),
+        }
+    }
+}
+
+/// How many chunks of sequences can be queued before `feed` blocks.
+const BUFFERED_CHUNKS: usize = 2;
+
+pub struct ExTokenizersTrainingJobRef {
+    status: Mutex<TrainingJobStatus>,
+    cancel: Arc<AtomicBool>,
+    // Sends chunks of sequences to the training thread. Jobs training
+    // from files have no input, and dropping the sender ends the input
+    input: Mutex<Option<SyncSender<Vec<String>>>>,
+}
+
+#[derive(rustler::NifStruct)]
+#[module = "Tokenizers.TrainingJob"]
+pub struct ExTokenizersTrainingJob {
+    pub resource: ResourceArc<ExTokenizersTrainingJobRef>,
+}
+
+impl ExTokenizersTrainingJob {
+    fn new(input: Option<SyncSender<Vec<String>>>) -> Self {
+        Self {
+            resource: ResourceArc::new(ExTokenizersTrainingJobRef {
+                status: Mutex::new(TrainingJobStatus::Running),
+                cancel: Arc::new(AtomicBool::new(false)),
+                input: Mutex::new(input),
+            }),
+        }
+    }
+
+    fn close_input(&self) {
+        self.resource
+            .input
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+}
+
+/// Trains `tokenizer` on a background thread.
+///
+/// Once training finishes, the job status is updated and `notify` receives
+/// `{:training_job_done, job, status}`.
+fn spawn_training<F>(
+    tokenizer: ExTokenizersTokenizer,
+    trainer: Option<ExTokenizersTrainer>,
+    progress: Option<LocalPid>,
+    notify: LocalPid,
+    input: Option<SyncSender<Vec<String>>>,
+    train: F,
+) -> ExTokenizersTrainingJob
+where
+    F: FnOnce(&mut ExTokenizerImpl, &mut ReportingTrainer) -> tokenizers::Result<()>
+        + Send
+        + 'static,
+{
+    let job = ExTokenizersTrainingJob::new(input);
+    let resource = job.resource.clone();
+
+    std::thread::spawn(move || {
+        let cancel = resource.cancel.clone();
+        let result = train_tokenizer(&tokenizer, trainer, progress, Some(cancel), train);
+
+        let status = if resource.cancel.load(Ordering::Relaxed) {
+            TrainingJobStatus::Cancelled
+        } else {
+            match result {
+                Ok(tokenizer) => TrainingJobStatus::Done(tokenizer),
+                Err(error) => TrainingJobStatus::Failed(error),
+            }
+        };
+
+        let mut current = resource
+            .status
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        *current = status;
+        let job = ExTokenizersTrainingJob {
+            resource: resource.clone(),
+        };
+        // The caller may be gone already, in which case there is nobody to notify
+        let _ = OwnedEnv::new().send_and_clear(&notify, |env| {
+            (atoms::training_job_done(), job, &*current).encode(env)
+        });
+    });
+
+    job
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Creators
+///////////////////////////////////////////////////////////////////////////////
+
+#[rustler::nif]
+pub fn training_job_start_from_files(
+    env: Env,
+    tokenizer: ExTokenizersTokenizer,
+    files: Vec<String>,
+    trainer: Option<ExTokenizersTrainer>,
+    progress: Option<LocalPid>,
+) -> ExTokenizersTrainingJob {
+    spawn_training(
+        tokenizer,
+        trainer,
+        progress,
+        env.pid(),
+        None,
+        |new_tokenizer, trainer| {
+            new_tokenizer.train_from_files(trainer, files)?;
+            Ok(())
+        },
+    )
+}
+
+/// Starts a job training on the sequences given to `training_job_feed`,
+/// until `training_job_close_input` is called.
+///
+/// The training thread reads the chunks as they come, so only a bounded
+/// number of them is kept in memory.
+#[rustler::nif]
+pub fn training_job_start_from_iterator(
+    env: Env,
+    tokenizer: ExTokenizersTokenizer,
+    trainer: Option<ExTokenizersTrainer>,
+    progress: Option<LocalPid>,
+) -> ExTokenizersTrainingJob {
+    let (sender, receiver) = mpsc::sync_channel::<Vec<String>>(BUFFERED_CHUNKS);
+    spawn_training(
+        tokenizer,
+        trainer,
+        progress,
+        env.pid(),
+        Some(sender),
+        move |new_tokenizer, trainer| {
+            new_tokenizer.train(trainer, receiver.into_iter().flatten())?;
+            Ok(())
+        },
+    )
+}
+
+///////////////////////////////////////////////////////////////////////////////
+/// Control
+///////////////////////////////////////////////////////////////////////////////
+
+#[rustler::nif]
+pub fn training_job_status(env: Env, job: ExTokenizersTrainingJob) -> Term {
+    job.resource
+        .status
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .encode(env)
+}
+
+/// Sends a chunk of sequences to the training thread, waiting while
+/// previous chunks are still queued. Returns `:closed` if the job no
+/// longer reads input, because it was cancelled or has failed.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn training_job_feed(job: ExTokenizersTrainingJob, sequences: Vec<String>) -> rustler::Atom {
+    // The sender is cloned so that closing the input doesn't wait for
+    // a blocked feed
+    let input = job
+        .resource
+        .input
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    match input.map(|sender| sender.send(sequences)) {
+        Some(Ok(())) => atoms::ok(),
+        _ => atoms::closed(),
+    }
+}
+
+#[rustler::nif]
+pub fn training_job_close_input(job: ExTokenizersTrainingJob) -> rustler::Atom {
+    job.close_input();
+    atoms::ok()
+}
+
+#[rustler::nif]
+pub fn training_job_cancel(job: ExTokenizersTrainingJob) -> rustler::Atom {
+    job.resource.cancel.store(true, Ordering::Relaxed);
+    // Wakes up the training thread if it is waiting for input
+    job.close_input();
+    atoms::ok()
+}
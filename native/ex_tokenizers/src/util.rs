@@ -1,6 +1,10 @@
-use rustler::Encoder;
+use rustler::{Encoder, NifTaggedEnum};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use tokenizers::{PaddingDirection, TruncationDirection};
 
+use crate::ExTokenizersError;
+
 #[macro_export]
 macro_rules! new_info {
     [$($a:ident : $b:expr),*] => {{
@@ -66,3 +70,73 @@ impl From<&Direction> for TruncationDirection {
         }
     }
 }
+
+#[derive(rustler::NifUnitEnum, Clone, Copy)]
+pub enum OffsetType {
+    Bytes,
+    Chars,
+}
+
+impl From<OffsetType> for tokenizers::OffsetType {
+    fn from(val: OffsetType) -> Self {
+        match val {
+            OffsetType::Bytes => tokenizers::OffsetType::Byte,
+            OffsetType::Chars => tokenizers::OffsetType::Char,
+        }
+    }
+}
+
+#[derive(rustler::NifUnitEnum, Clone, Copy)]
+pub enum OffsetReferential {
+    Original,
+    Normalized,
+}
+
+impl From<OffsetReferential> for tokenizers::OffsetReferential {
+    fn from(val: OffsetReferential) -> Self {
+        match val {
+            OffsetReferential::Original => tokenizers::OffsetReferential::Original,
+            OffsetReferential::Normalized => tokenizers::OffsetReferential::Normalized,
+        }
+    }
+}
+
+#[derive(NifTaggedEnum)]
+pub enum JsonOption {
+    Pretty(bool),
+}
+
+/// Serializes a pipeline component into its tokenizer.json representation.
+pub fn to_json<T: Serialize>(
+    value: &T,
+    options: Vec<JsonOption>,
+) -> Result<String, ExTokenizersError> {
+    struct Opts {
+        pretty: bool,
+    }
+    let mut opts = Opts { pretty: false };
+    for opt in options {
+        match opt {
+            JsonOption::Pretty(pretty) => opts.pretty = pretty,
+        }
+    }
+
+    if opts.pretty {
+        Ok(serde_json::to_string_pretty(value)?)
+    } else {
+        Ok(serde_json::to_string(value)?)
+    }
+}
+
+/// Deserializes a pipeline component from its tokenizer.json representation.
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, ExTokenizersError> {
+    Ok(serde_json::from_str(json)?)
+}
+
+/// Reads the configuration of a component that doesn't expose its fields
+/// back from its serialized form.
+pub fn serialized_config<T: Serialize, C: DeserializeOwned>(
+    value: &T,
+) -> Result<C, ExTokenizersError> {
+    Ok(serde_json::from_value(serde_json::to_value(value)?)?)
+}
@@ -38,4 +38,14 @@ defmodule Tokenizers.AddedTokenTest do
              } = Tokenizers.AddedToken.info(token)
     end
   end
+
+  describe "JSON serialization" do
+    test "round trips through JSON" do
+      token = Tokenizers.AddedToken.new("[MASK]", special: true, lstrip: true)
+      {:ok, json} = Tokenizers.AddedToken.to_json(token)
+
+      assert {:ok, decoded} = Tokenizers.AddedToken.from_json(json)
+      assert Tokenizers.AddedToken.info(decoded) == Tokenizers.AddedToken.info(token)
+    end
+  end
 end
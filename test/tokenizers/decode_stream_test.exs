@@ -0,0 +1,61 @@
+defmodule Tokenizers.DecodeStreamTest do
+  use ExUnit.Case, async: true
+  doctest Tokenizers.DecodeStream
+
+  alias Tokenizers.DecodeStream
+  alias Tokenizers.Encoding
+  alias Tokenizers.Tokenizer
+
+  setup do
+    {:ok, tokenizer} = Tokenizer.from_file("test/fixtures/bert-base-cased.json")
+    {:ok, tokenizer: tokenizer}
+  end
+
+  defp stream_ids(stream, ids) do
+    for id <- ids do
+      {:ok, text} = DecodeStream.step(stream, id)
+      text
+    end
+  end
+
+  test "matches decoding all ids at once", %{tokenizer: tokenizer} do
+    text = "The quick brown fox doesn't jump over the lazy dog."
+    {:ok, encoding} = Tokenizer.encode(tokenizer, text)
+    ids = Encoding.get_ids(encoding)
+
+    streamed = tokenizer |> DecodeStream.new() |> stream_ids(ids) |> Enum.join()
+    assert {:ok, ^streamed} = Tokenizer.decode(tokenizer, ids)
+  end
+
+  test "can keep special tokens", %{tokenizer: tokenizer} do
+    {:ok, encoding} = Tokenizer.encode(tokenizer, "Hello")
+    stream = DecodeStream.new(tokenizer, skip_special_tokens: false)
+
+    assert stream_ids(stream, Encoding.get_ids(encoding)) == ["[CLS]", " Hello", " [SEP]"]
+  end
+
+  describe "byte-level tokens" do
+    setup do
+      # "é" is split into its two bytes, "Ã" and "©" in byte-level form
+      {:ok, model} = Tokenizers.Model.BPE.init(%{"a" => 0, "Ã" => 1, "©" => 2, "Ġb" => 3}, [])
+      {:ok, tokenizer} = Tokenizer.init(model)
+      {:ok, tokenizer: Tokenizer.set_decoder(tokenizer, Tokenizers.Decoder.byte_level())}
+    end
+
+    test "holds back incomplete characters", %{tokenizer: tokenizer} do
+      stream = DecodeStream.new(tokenizer)
+      assert stream_ids(stream, [0, 1, 2, 3, 1, 2]) == ["a", nil, "é", " b", nil, "é"]
+      assert DecodeStream.finish(stream) == {:ok, nil}
+    end
+
+    test "returns the held back text when finishing", %{tokenizer: tokenizer} do
+      stream = DecodeStream.new(tokenizer)
+      assert stream_ids(stream, [0, 3, 1]) == ["a", " b", nil]
+      assert DecodeStream.finish(stream) == {:ok, "\uFFFD"}
+      assert {:ok, "a b\uFFFD"} = Tokenizer.decode(tokenizer, [0, 3, 1])
+
+      # The stream is reset
+      assert stream_ids(stream, [3, 0]) == [" b", "a"]
+    end
+  end
+end
@@ -68,7 +68,7 @@ defmodule Tokenizers.DecoderTest do
     end
 
     test "can't be initialized with invalid char" do
-      assert_raise ArgumentError, fn ->
+      assert_raise Tokenizers.Error, fn ->
         Tokenizers.Decoder.strip(61_126_999, 0, 0)
       end
     end
@@ -144,4 +144,14 @@ defmodule Tokenizers.DecoderTest do
                {:ok, "Hi you"}
     end
   end
+
+  describe "JSON serialization" do
+    test "round trips through JSON" do
+      {:ok, json} = Tokenizers.Decoder.to_json(Tokenizers.Decoder.fuse())
+      assert json == ~s({"type":"Fuse"})
+
+      assert {:ok, decoder} = Tokenizers.Decoder.from_json(json)
+      assert Tokenizers.Decoder.decode(decoder, ["Hel", "lo"]) == {:ok, "Hello"}
+    end
+  end
 end
@@ -0,0 +1,190 @@
+defmodule Tokenizers.InvalidInputTest do
+  use ExUnit.Case, async: true
+
+  # Invalid input must be reported as an error, never as a NIF panic
+
+  @invalid_char 0xD800
+
+  describe "models" do
+    test "BPE rejects an invalid dropout" do
+      assert {:error, %Tokenizers.Error{}} =
+               Tokenizers.Model.BPE.init(%{"a" => 0, "b" => 1}, [], dropout: 2.0)
+    end
+
+    test "BPE rejects merges out of vocabulary" do
+      assert {:error, %Tokenizers.Error{kind: :unknown_token}} =
+               Tokenizers.Model.BPE.init(%{"a" => 0}, [{"a", "b"}])
+    end
+
+    test "WordPiece rejects a missing vocabulary file" do
+      assert {:error, %Tokenizers.Error{kind: :io}} =
+               Tokenizers.Model.WordPiece.from_file("test/fixtures/missing.txt")
+    end
+
+    test "WordLevel rejects a missing vocabulary file" do
+      assert {:error, %Tokenizers.Error{}} =
+               Tokenizers.Model.WordLevel.from_file("test/fixtures/missing.json")
+    end
+
+    test "WordPiece rejects invalid options" do
+      assert_raise ArgumentError, fn ->
+        Tokenizers.Model.WordPiece.init(%{"a" => 0}, max_input_chars_per_word: -1)
+      end
+
+      assert_raise ArgumentError, fn ->
+        Tokenizers.Model.WordPiece.init(%{"a" => 0}, unknown: true)
+      end
+    end
+
+    test "WordLevel rejects invalid options" do
+      assert_raise ArgumentError, fn ->
+        Tokenizers.Model.WordLevel.init(%{"a" => 0}, unk_token: 0)
+      end
+    end
+
+    test "Unigram rejects an unknown token id out of vocabulary" do
+      assert {:error, %Tokenizers.Error{kind: :unknown_token}} =
+               Tokenizers.Model.Unigram.init([{"a", 0.0}], unk_id: 3)
+
+      assert {:error, %Tokenizers.Error{kind: :unknown_token}} =
+               Tokenizers.Model.Unigram.init([], unk_id: 0)
+
+      assert_raise ArgumentError, fn ->
+        Tokenizers.Model.Unigram.init([{"a", 0.0}], unk_id: -1)
+      end
+    end
+  end
+
+  describe "trainers" do
+    test "reject invalid chars in the initial alphabet" do
+      assert {:error, %Tokenizers.Error{kind: :invalid_char}} =
+               Tokenizers.Trainer.bpe(initial_alphabet: [@invalid_char])
+
+      assert {:error, %Tokenizers.Error{kind: :invalid_char}} =
+               Tokenizers.Trainer.wordpiece(initial_alphabet: [@invalid_char])
+
+      assert {:error, %Tokenizers.Error{kind: :invalid_char}} =
+               Tokenizers.Trainer.unigram(initial_alphabet: [@invalid_char])
+    end
+
+    test "reject invalid options" do
+      for {trainer, options} <- [
+            bpe: [vocab_size: -1],
+            bpe: [special_tokens: [:unk]],
+            bpe: [unknown: true],
+            wordpiece: [min_frequency: -1],
+            wordpiece: [continuing_subword_prefix: 1],
+            wordlevel: [vocab_size: -1],
+            wordlevel: [special_tokens: "[UNK]"],
+            unigram: [shrinking_factor: "half"],
+            unigram: [max_piece_length: -1]
+          ] do
+        assert_raise ArgumentError, fn -> apply(Tokenizers.Trainer, trainer, [options]) end
+      end
+    end
+  end
+
+  describe "pipeline components" do
+    test "pre-tokenizers reject invalid chars" do
+      assert_raise Tokenizers.Error, fn ->
+        Tokenizers.PreTokenizer.metaspace(replacement: @invalid_char)
+      end
+
+      assert_raise Tokenizers.Error, fn ->
+        Tokenizers.PreTokenizer.char_delimiter_split(@invalid_char)
+      end
+    end
+
+    test "decoders reject invalid chars" do
+      assert_raise Tokenizers.Error, fn ->
+        Tokenizers.Decoder.metaspace(replacement: @invalid_char)
+      end
+
+      assert_raise Tokenizers.Error, fn ->
+        Tokenizers.Decoder.strip(@invalid_char, 0, 0)
+      end
+    end
+
+    test "normalizers reject invalid options" do
+      assert_raise ArgumentError, fn ->
+        Tokenizers.Normalizer.bert_normalizer(lowercase: "yes")
+      end
+
+      assert_raise ArgumentError, fn ->
+        Tokenizers.Normalizer.strip(left: 1)
+      end
+
+      assert_raise ArgumentError, fn ->
+        Tokenizers.Normalizer.prepend(:space)
+      end
+    end
+
+    test "normalizers reject invalid precompiled charsmaps" do
+      assert {:error, %Tokenizers.Error{}} = Tokenizers.Normalizer.precompiled(<<>>)
+
+      # A trie size larger than the data itself
+      assert {:error, %Tokenizers.Error{}} =
+               Tokenizers.Normalizer.precompiled(:binary.copy(<<255>>, 16))
+    end
+
+    test "post-processors reject invalid tokens and options" do
+      assert_raise ArgumentError, fn ->
+        Tokenizers.PostProcessor.bert({"[SEP]", -1}, {"[CLS]", 101})
+      end
+
+      assert_raise ArgumentError, fn ->
+        Tokenizers.PostProcessor.roberta({"</s>", 2}, {"<s>", 0}, trim_offsets: 1)
+      end
+    end
+
+    test "template post-processors reject invalid templates" do
+      assert %Tokenizers.Error{kind: :invalid_template} =
+               catch_error(Tokenizers.PostProcessor.template(single: "$Z"))
+
+      assert %Tokenizers.Error{kind: :invalid_template} =
+               catch_error(Tokenizers.PostProcessor.template(pair: "$A:x $B"))
+
+      assert %Tokenizers.Error{kind: :unknown_token} =
+               catch_error(Tokenizers.PostProcessor.template(single: "[CLS] $A"))
+
+      assert_raise ArgumentError, fn ->
+        Tokenizers.PostProcessor.template(special_tokens: [{"[CLS]", -1}])
+      end
+    end
+
+    test "normalizers reject invalid regular expressions" do
+      assert %Tokenizers.Error{kind: :invalid_regex} =
+               catch_error(Tokenizers.Normalizer.replace_regex("(", ""))
+    end
+
+    test "pre-tokenizers reject invalid regular expressions" do
+      assert %Tokenizers.Error{kind: :invalid_regex} =
+               catch_error(Tokenizers.PreTokenizer.split_regex("(", :removed))
+    end
+  end
+
+  describe "tokenizer" do
+    setup do
+      {:ok, tokenizer} = Tokenizers.Tokenizer.from_file("test/fixtures/bert-base-cased.json")
+      {:ok, tokenizer: tokenizer}
+    end
+
+    test "set_truncation rejects a stride larger than max_length", %{tokenizer: tokenizer} do
+      assert_raise Tokenizers.Error, ~r/stride/, fn ->
+        Tokenizers.Tokenizer.set_truncation(tokenizer, max_length: 10, stride: 20)
+      end
+    end
+
+    test "set_truncation rejects max_length shorter than special tokens",
+         %{tokenizer: tokenizer} do
+      assert_raise Tokenizers.Error, ~r/max_length/, fn ->
+        Tokenizers.Tokenizer.set_truncation(tokenizer, max_length: 1)
+      end
+    end
+
+    test "from_buffer rejects invalid JSON" do
+      assert {:error, %Tokenizers.Error{kind: :json_parse}} =
+               Tokenizers.Tokenizer.from_buffer("not json")
+    end
+  end
+end
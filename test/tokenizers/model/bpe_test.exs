@@ -49,4 +49,25 @@ defmodule Tokenizers.Model.BPETest do
                )
     end
   end
+
+  describe "merges/1" do
+    test "returns merges ordered by rank" do
+      vocab = %{"a" => 0, "b" => 1, "c" => 2, "ab" => 3, "abc" => 4}
+      {:ok, model} = Tokenizers.Model.BPE.init(vocab, [{"a", "b"}, {"ab", "c"}])
+
+      assert {:ok, [{"a", "b"}, {"ab", "c"}]} = Tokenizers.Model.BPE.merges(model)
+    end
+
+    test "splits merges of tokens containing spaces" do
+      vocab = %{"a" => 0, " " => 1, "b" => 2, "a " => 3, "a b" => 4}
+      {:ok, model} = Tokenizers.Model.BPE.init(vocab, [{"a", " "}, {"a ", "b"}])
+
+      assert {:ok, [{"a", " "}, {"a ", "b"}]} = Tokenizers.Model.BPE.merges(model)
+    end
+
+    test "returns an error for other models" do
+      {:ok, model} = Tokenizers.Model.Unigram.empty()
+      assert {:error, %Tokenizers.Error{kind: :other}} = Tokenizers.Model.BPE.merges(model)
+    end
+  end
 end
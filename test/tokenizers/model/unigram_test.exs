@@ -0,0 +1,40 @@
+defmodule Tokenizers.Model.UnigramTest do
+  use ExUnit.Case, async: true
+  doctest Tokenizers.Model.Unigram
+
+  describe "initialized from memory" do
+    test "returns loaded model" do
+      assert {:ok, %Tokenizers.Model{}} =
+               Tokenizers.Model.Unigram.init([{"<unk>", 0}, {"Hello", -1}, {"there", -2}],
+                 unk_id: 0
+               )
+    end
+
+    test "accepts any subset of options" do
+      vocab = [{"<unk>", 0}, {"Hello", -1}, {"there", -2}]
+      assert {:ok, %Tokenizers.Model{}} = Tokenizers.Model.Unigram.init(vocab)
+
+      assert {:ok, %Tokenizers.Model{}} =
+               Tokenizers.Model.Unigram.init(vocab, byte_fallback: true)
+    end
+
+    test "returns an error when unk_id is out of vocabulary" do
+      assert {:error, %Tokenizers.Error{kind: :unknown_token}} =
+               Tokenizers.Model.Unigram.init([{"<unk>", 0}], unk_id: 10)
+    end
+  end
+
+  describe "vocab/1" do
+    test "returns pieces with their scores" do
+      vocab = [{"<unk>", 0.0}, {"Hello", -1.0}, {"there", -2.5}]
+      {:ok, model} = Tokenizers.Model.Unigram.init(vocab, unk_id: 0)
+
+      assert {:ok, ^vocab} = Tokenizers.Model.Unigram.vocab(model)
+    end
+
+    test "returns an error for other models" do
+      {:ok, model} = Tokenizers.Model.BPE.empty()
+      assert {:error, %Tokenizers.Error{kind: :other}} = Tokenizers.Model.Unigram.vocab(model)
+    end
+  end
+end
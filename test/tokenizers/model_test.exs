@@ -29,4 +29,42 @@ defmodule Tokenizers.ModelTest do
       assert Tokenizers.Model.info(original_model) == Tokenizers.Model.info(loaded_model)
     end
   end
+
+  describe "tokenization and vocabulary" do
+    setup do
+      vocab = %{"[UNK]" => 0, "un" => 1, "##aff" => 2, "##able" => 3}
+      {:ok, model} = Tokenizers.Model.WordPiece.init(vocab)
+      %{model: model, vocab: vocab}
+    end
+
+    test "tokenizes a single word", %{model: model} do
+      assert {:ok, [{1, "un", {0, 2}}, {2, "##aff", {2, 5}}, {3, "##able", {5, 9}}]} =
+               Tokenizers.Model.tokenize(model, "unaffable")
+
+      assert {:ok, [{0, "[UNK]", {0, 3}}]} = Tokenizers.Model.tokenize(model, "xyz")
+    end
+
+    test "returns the vocabulary", %{model: model, vocab: vocab} do
+      assert Tokenizers.Model.get_vocab(model) == vocab
+      assert Tokenizers.Model.get_vocab_size(model) == 4
+    end
+
+    test "converts between tokens and ids", %{model: model} do
+      assert Tokenizers.Model.token_to_id(model, "##aff") == 2
+      assert Tokenizers.Model.token_to_id(model, "missing") == nil
+      assert Tokenizers.Model.id_to_token(model, 3) == "##able"
+      assert Tokenizers.Model.id_to_token(model, 100) == nil
+    end
+  end
+
+  describe "JSON serialization" do
+    test "round trips through JSON" do
+      {:ok, model} = Tokenizers.Model.BPE.init(%{"a" => 0, "b" => 1, "ab" => 2}, [{"a", "b"}])
+      {:ok, json} = Tokenizers.Model.to_json(model)
+
+      assert {:ok, decoded} = Tokenizers.Model.from_json(json)
+      assert Tokenizers.Model.info(decoded) == Tokenizers.Model.info(model)
+      assert {:ok, ^json} = Tokenizers.Model.to_json(decoded)
+    end
+  end
 end
@@ -2,6 +2,49 @@ defmodule Tokenizers.NormalizerTest do
   use ExUnit.Case, async: true
   doctest Tokenizers.Normalizer
 
+  describe "info/1" do
+    test "returns bert normalizer options" do
+      normalizer = Tokenizers.Normalizer.bert_normalizer(lowercase: false)
+
+      assert %{
+               "normalizer_type" => "BertNormalizer",
+               "clean_text" => true,
+               "handle_chinese_chars" => true,
+               "strip_accents" => nil,
+               "lowercase" => false
+             } = Tokenizers.Normalizer.info(normalizer)
+    end
+
+    test "returns replace options" do
+      normalizer = Tokenizers.Normalizer.replace_regex("\\s+", " ")
+
+      assert %{
+               "normalizer_type" => "Replace",
+               "pattern" => {:regex, "\\s+"},
+               "content" => " "
+             } = Tokenizers.Normalizer.info(normalizer)
+    end
+
+    test "returns sequence children recursively" do
+      normalizer =
+        Tokenizers.Normalizer.sequence([
+          Tokenizers.Normalizer.prepend("▁"),
+          Tokenizers.Normalizer.sequence([Tokenizers.Normalizer.nfc()])
+        ])
+
+      assert %{
+               "normalizer_type" => "Sequence",
+               "normalizers" => [
+                 %{"normalizer_type" => "Prepend", "prepend" => "▁"},
+                 %{
+                   "normalizer_type" => "Sequence",
+                   "normalizers" => [%{"normalizer_type" => "NFC"}]
+                 }
+               ]
+             } = Tokenizers.Normalizer.info(normalizer)
+    end
+  end
+
   describe "Bert" do
     test "accepts no parameters" do
       assert %Tokenizers.Normalizer{} = Tokenizers.Normalizer.bert_normalizer()
@@ -114,4 +157,19 @@ defmodule Tokenizers.NormalizerTest do
                {:ok, "Hello"}
     end
   end
+
+  describe "JSON serialization" do
+    test "round trips through JSON" do
+      {:ok, json} = Tokenizers.Normalizer.to_json(Tokenizers.Normalizer.lowercase())
+      assert json == ~s({"type":"Lowercase"})
+
+      assert {:ok, normalizer} = Tokenizers.Normalizer.from_json(json)
+      assert Tokenizers.Normalizer.normalize(normalizer, "HeLLo") == {:ok, "hello"}
+    end
+
+    test "returns an error for invalid JSON" do
+      assert {:error, %Tokenizers.Error{kind: :json_parse}} =
+               Tokenizers.Normalizer.from_json(~s({"type":"Unknown"}))
+    end
+  end
 end
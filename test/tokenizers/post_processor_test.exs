@@ -2,6 +2,56 @@ defmodule Tokenizers.PostProcessorTest do
   use ExUnit.Case, async: true
   doctest Tokenizers.PostProcessor
 
+  describe "info/1" do
+    test "returns roberta options" do
+      post_processor =
+        Tokenizers.PostProcessor.roberta({"</s>", 2}, {"<s>", 0}, trim_offsets: false)
+
+      assert %{
+               "post_processor_type" => "roberta",
+               "sep" => {"</s>", 2},
+               "cls" => {"<s>", 0},
+               "trim_offsets" => false,
+               "add_prefix_space" => true
+             } = Tokenizers.PostProcessor.info(post_processor)
+    end
+
+    test "returns templates and special tokens" do
+      post_processor =
+        Tokenizers.PostProcessor.template(
+          single: "[CLS] $A [SEP]",
+          pair: "[CLS] $A [SEP] $B:1 [SEP]:1",
+          special_tokens: [{"[CLS]", 1}, {"[SEP]", 0}]
+        )
+
+      assert %{
+               "post_processor_type" => "template",
+               "single" => "[CLS]:0 $A:0 [SEP]:0",
+               "pair" => "[CLS]:0 $A:0 [SEP]:0 $B:1 [SEP]:1",
+               "special_tokens" => %{
+                 "[CLS]" => %{"ids" => [1], "tokens" => ["[CLS]"]},
+                 "[SEP]" => %{"ids" => [0], "tokens" => ["[SEP]"]}
+               }
+             } = Tokenizers.PostProcessor.info(post_processor)
+    end
+
+    test "returns sequence children" do
+      post_processor =
+        Tokenizers.PostProcessor.sequence([
+          Tokenizers.PostProcessor.byte_level(trim_offsets: false),
+          Tokenizers.PostProcessor.bert({"[SEP]", 102}, {"[CLS]", 101})
+        ])
+
+      assert %{
+               "post_processor_type" => "sequence",
+               "processors" => [
+                 %{"post_processor_type" => "byte_level", "trim_offsets" => false},
+                 %{"post_processor_type" => "bert", "sep" => {"[SEP]", 102}}
+               ]
+             } = Tokenizers.PostProcessor.info(post_processor)
+    end
+  end
+
   describe "bertProcessing" do
     test "instantiates correctly with only two parameters" do
       assert %Tokenizers.PostProcessor{} =
@@ -67,6 +117,28 @@ defmodule Tokenizers.PostProcessorTest do
     end
   end
 
+  describe "templateProcessing" do
+    test "instantiates correctly" do
+      assert %Tokenizers.PostProcessor{} =
+               Tokenizers.PostProcessor.template(
+                 single: "[CLS] $A [SEP]",
+                 special_tokens: [{"[CLS]", 1}, {"[SEP]", 0}]
+               )
+    end
+
+    test "raises on invalid template" do
+      assert %Tokenizers.Error{kind: :invalid_template} =
+               catch_error(Tokenizers.PostProcessor.template(single: "$X"))
+    end
+
+    test "raises on missing special tokens" do
+      assert %Tokenizers.Error{kind: :unknown_token, message: message} =
+               catch_error(Tokenizers.PostProcessor.template(single: "[CLS] $A [SEP]"))
+
+      assert message =~ "[CLS], [SEP]"
+    end
+  end
+
   describe "byteLevelProcessing" do
     test "instantiates correctly with only two parameters" do
       assert %Tokenizers.PostProcessor{} =
@@ -75,4 +147,15 @@ defmodule Tokenizers.PostProcessorTest do
       assert %Tokenizers.PostProcessor{} = Tokenizers.PostProcessor.byte_level()
     end
   end
+
+  describe "JSON serialization" do
+    test "round trips through JSON" do
+      post_processor = Tokenizers.PostProcessor.bert({"[SEP]", 0}, {"[CLS]", 1})
+      {:ok, json} = Tokenizers.PostProcessor.to_json(post_processor, pretty: true)
+      assert json =~ "\n"
+
+      assert {:ok, decoded} = Tokenizers.PostProcessor.from_json(json)
+      assert {:ok, ^json} = Tokenizers.PostProcessor.to_json(decoded, pretty: true)
+    end
+  end
 end
@@ -2,6 +2,90 @@ defmodule Tokenizers.PreTokenizerTest do
   use ExUnit.Case, async: true
   doctest Tokenizers.PreTokenizer
 
+  describe "pre_tokenize/3" do
+    test "returns char offsets by default" do
+      pre_tokenizer = Tokenizers.PreTokenizer.whitespace_split()
+
+      assert {:ok, [{"héllo", {0, 5}}, {"wörld", {6, 11}}]} =
+               Tokenizers.PreTokenizer.pre_tokenize(pre_tokenizer, "héllo wörld")
+    end
+
+    test "can return byte offsets" do
+      pre_tokenizer = Tokenizers.PreTokenizer.whitespace_split()
+
+      assert {:ok, [{"héllo", {0, 6}}, {"wörld", {7, 13}}]} =
+               Tokenizers.PreTokenizer.pre_tokenize(pre_tokenizer, "héllo wörld",
+                 offset_type: :bytes
+               )
+    end
+
+    test "can return offsets in the normalized string" do
+      pre_tokenizer = Tokenizers.PreTokenizer.metaspace()
+
+      assert {:ok, [{"▁hello", {0, 8}}, {"▁world", {8, 16}}]} =
+               Tokenizers.PreTokenizer.pre_tokenize(pre_tokenizer, "hello world",
+                 offset_type: :bytes,
+                 offset_referential: :normalized
+               )
+    end
+  end
+
+  describe "info/1" do
+    test "returns byte level options" do
+      pre_tokenizer = Tokenizers.PreTokenizer.byte_level(add_prefix_space: false)
+
+      assert %{
+               "pre_tokenizer_type" => "ByteLevel",
+               "add_prefix_space" => false,
+               "trim_offsets" => true,
+               "use_regex" => true
+             } = Tokenizers.PreTokenizer.info(pre_tokenizer)
+    end
+
+    test "returns metaspace options" do
+      pre_tokenizer = Tokenizers.PreTokenizer.metaspace(replacement: ?_, prepend_scheme: :first)
+
+      assert %{
+               "pre_tokenizer_type" => "Metaspace",
+               "replacement" => "_",
+               "prepend_scheme" => :first,
+               "split" => true
+             } = Tokenizers.PreTokenizer.info(pre_tokenizer)
+    end
+
+    test "returns split options" do
+      pre_tokenizer = Tokenizers.PreTokenizer.split(" ", :merged_with_next, invert: true)
+
+      assert %{
+               "pre_tokenizer_type" => "Split",
+               "pattern" => {:string, " "},
+               "behavior" => :merged_with_next,
+               "invert" => true
+             } = Tokenizers.PreTokenizer.info(pre_tokenizer)
+    end
+
+    test "returns sequence children recursively" do
+      pre_tokenizer =
+        Tokenizers.PreTokenizer.sequence([
+          Tokenizers.PreTokenizer.punctuation(:isolated),
+          Tokenizers.PreTokenizer.sequence([Tokenizers.PreTokenizer.char_delimiter_split(?-)])
+        ])
+
+      assert %{
+               "pre_tokenizer_type" => "Sequence",
+               "pre_tokenizers" => [
+                 %{"pre_tokenizer_type" => "Punctuation", "behavior" => :isolated},
+                 %{
+                   "pre_tokenizer_type" => "Sequence",
+                   "pre_tokenizers" => [
+                     %{"pre_tokenizer_type" => "Delimiter", "delimiter" => "-"}
+                   ]
+                 }
+               ]
+             } = Tokenizers.PreTokenizer.info(pre_tokenizer)
+    end
+  end
+
   describe "Byte Level pretokenizer" do
     test "accepts no parameters" do
       assert %Tokenizers.PreTokenizer{} = Tokenizers.PreTokenizer.byte_level()
@@ -34,6 +118,12 @@ defmodule Tokenizers.PreTokenizerTest do
       assert %Tokenizers.PreTokenizer{} =
                Tokenizers.PreTokenizer.split_regex(".*", :removed, invert: true)
     end
+
+    test "raises on invalid regular expressions" do
+      assert_raise Tokenizers.Error, ~r/unmatched parenthesis/, fn ->
+        Tokenizers.PreTokenizer.split_regex("(", :removed)
+      end
+    end
   end
 
   describe "WhitespaceSplit pretokenizer" do
@@ -74,4 +164,26 @@ defmodule Tokenizers.PreTokenizerTest do
                ])
     end
   end
+
+  describe "UnicodeScripts pretokenizer" do
+    test "splits on script changes" do
+      pre_tokenizer = Tokenizers.PreTokenizer.unicode_scripts()
+
+      assert {:ok, [{"Hello", {0, 5}}, {"世界 ", {5, 8}}, {"мир", {8, 11}}]} =
+               Tokenizers.PreTokenizer.pre_tokenize(pre_tokenizer, "Hello世界 мир")
+    end
+  end
+
+  describe "JSON serialization" do
+    test "round trips through JSON" do
+      pre_tokenizer = Tokenizers.PreTokenizer.whitespace_split()
+      {:ok, json} = Tokenizers.PreTokenizer.to_json(pre_tokenizer)
+      assert json == ~s({"type":"WhitespaceSplit"})
+
+      assert {:ok, pre_tokenizer} = Tokenizers.PreTokenizer.from_json(json)
+
+      assert Tokenizers.PreTokenizer.pre_tokenize(pre_tokenizer, "Hello world") ==
+               {:ok, [{"Hello", {0, 5}}, {"world", {6, 11}}]}
+    end
+  end
 end
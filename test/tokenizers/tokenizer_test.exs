@@ -23,6 +23,284 @@ defmodule Tokenizers.TokenizerTest do
       {:ok, tokenizer} = Tokenizer.from_file(path)
       assert Tokenizer.get_vocab_size(tokenizer) == 28996
     end
+
+    test "can round trip through a buffer" do
+      {:ok, tokenizer} = Tokenizer.from_file("test/fixtures/bert-base-cased.json")
+      {:ok, buffer} = Tokenizer.to_buffer(tokenizer)
+      {:ok, loaded} = Tokenizer.from_buffer(buffer)
+
+      assert Tokenizer.get_vocab_size(loaded) == 28996
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "Hello world")
+      {:ok, loaded_encoding} = Tokenizer.encode(loaded, "Hello world")
+      assert Encoding.get_ids(loaded_encoding) == Encoding.get_ids(encoding)
+      assert {:ok, ^buffer} = Tokenizer.to_buffer(loaded)
+    end
+
+    test "can write a pretty buffer" do
+      {:ok, tokenizer} = Tokenizer.from_file("test/fixtures/bert-base-cased.json")
+      {:ok, compact} = Tokenizer.to_buffer(tokenizer)
+      {:ok, pretty} = Tokenizer.to_buffer(tokenizer, pretty: true)
+
+      refute compact =~ "\n"
+      assert pretty =~ "\n"
+    end
+
+    test "returns io error for missing file" do
+      assert {:error, %Tokenizers.Error{kind: :io}} =
+               Tokenizer.from_file("test/fixtures/missing.json")
+    end
+
+    test "returns parse error with position for invalid json" do
+      assert {:error, %Tokenizers.Error{kind: :json_parse, details: %{line: 2, column: 13}}} =
+               Tokenizer.from_buffer(~s({\n  "version": ))
+    end
+  end
+
+  describe "config files" do
+    setup do
+      {:ok, tokenizer} =
+        Tokenizer.from_file("test/fixtures/bert-base-cased.json",
+          tokenizer_config: "test/fixtures/tokenizer_config.json",
+          special_tokens_map: "test/fixtures/special_tokens_map.json"
+        )
+
+      {:ok, tokenizer: tokenizer}
+    end
+
+    test "loads special token roles", %{tokenizer: tokenizer} do
+      assert Tokenizer.get_special_tokens(tokenizer) == %{
+               "bos_token" => "<s>",
+               "pad_token" => "[PAD]",
+               "unk_token" => "[UNK]"
+             }
+
+      assert Tokenizer.token_to_id(tokenizer, "<s>") == 28996
+      assert Tokenizer.token_to_id(tokenizer, "<extra>") == 28997
+    end
+
+    test "keeps special token roles on derived tokenizers", %{tokenizer: tokenizer} do
+      tokenizer = Tokenizer.disable_padding(tokenizer)
+      assert %{"bos_token" => "<s>"} = Tokenizer.get_special_tokens(tokenizer)
+    end
+
+    test "replaces the BOS token and keeps the EOS side", %{tokenizer: tokenizer} do
+      # [SEP] is not the EOS token, so add_eos_token: false leaves it
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "This is a test <extra>")
+
+      assert Encoding.get_tokens(encoding) ==
+               ["<s>", "This", "is", "a", "test", "<extra>", "[SEP]"]
+    end
+
+    @tag :tmp_dir
+    test "removes the BOS token when add_bos_token is false", %{tmp_dir: tmp_dir} do
+      path = Path.join(tmp_dir, "tokenizer_config.json")
+      File.write!(path, ~s({"add_bos_token": false, "bos_token": "[CLS]"}))
+
+      {:ok, tokenizer} =
+        Tokenizer.from_file("test/fixtures/bert-base-cased.json", tokenizer_config: path)
+
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "This is a test")
+      assert Encoding.get_tokens(encoding) == ["This", "is", "a", "test", "[SEP]"]
+    end
+
+    @tag :tmp_dir
+    test "applies mixed BOS and EOS settings", %{tmp_dir: tmp_dir} do
+      path = Path.join(tmp_dir, "tokenizer_config.json")
+
+      File.write!(path, """
+      {"add_bos_token": false, "add_eos_token": true, "bos_token": "[CLS]", "eos_token": "</s>"}
+      """)
+
+      {:ok, tokenizer} =
+        Tokenizer.from_file("test/fixtures/bert-base-cased.json", tokenizer_config: path)
+
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "This is a test")
+      assert Encoding.get_tokens(encoding) == ["This", "is", "a", "test", "</s>"]
+
+      File.write!(path, ~s({"add_eos_token": true, "eos_token": "</s>"}))
+
+      {:ok, tokenizer} =
+        Tokenizer.from_file("test/fixtures/bert-base-cased.json", tokenizer_config: path)
+
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "This is a test")
+      assert Encoding.get_tokens(encoding) == ["[CLS]", "This", "is", "a", "test", "</s>"]
+    end
+
+    @tag :tmp_dir
+    test "keeps the post-processor when BOS and EOS are disabled", %{tmp_dir: tmp_dir} do
+      path = Path.join(tmp_dir, "tokenizer_config.json")
+      File.write!(path, ~s({"add_bos_token": false, "add_eos_token": false}))
+
+      {:ok, tokenizer} =
+        Tokenizer.from_file("test/fixtures/bert-base-cased.json", tokenizer_config: path)
+
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "This is a test")
+      assert Encoding.get_tokens(encoding) == ["[CLS]", "This", "is", "a", "test", "[SEP]"]
+    end
+
+    test "truncates to the model max length", %{tokenizer: tokenizer} do
+      {:ok, encoding} =
+        Tokenizer.encode(tokenizer, "This is a much longer test sentence than before")
+
+      assert Encoding.get_length(encoding) == 8
+    end
+
+    test "pads on the configured side", %{tokenizer: tokenizer} do
+      {:ok, [encoding, _]} = Tokenizer.encode_batch(tokenizer, ["Hi", "This is a test"])
+      assert Encoding.get_tokens(encoding) == ["[PAD]", "[PAD]", "[PAD]", "<s>", "Hi", "[SEP]"]
+    end
+
+    test "can disable the configured padding and truncation" do
+      {:ok, tokenizer} =
+        Tokenizer.from_file("test/fixtures/bert-base-cased.json",
+          tokenizer_config: "test/fixtures/tokenizer_config.json",
+          special_tokens_map: "test/fixtures/special_tokens_map.json",
+          padding: :none,
+          truncation: :none
+        )
+
+      {:ok, [encoding, _]} = Tokenizer.encode_batch(tokenizer, ["Hi", "This is a test"])
+      assert Encoding.get_tokens(encoding) == ["<s>", "Hi", "[SEP]"]
+    end
+
+    test "returns io error for missing config file" do
+      assert {:error, %Tokenizers.Error{kind: :io}} =
+               Tokenizer.from_file("test/fixtures/bert-base-cased.json",
+                 tokenizer_config: "test/fixtures/missing.json"
+               )
+    end
+  end
+
+  describe "SentencePiece" do
+    test "loads a Unigram model" do
+      {:ok, tokenizer} =
+        Tokenizer.from_sentencepiece_file("test/fixtures/unigram.model", add_eos_token: true)
+
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "hello  world")
+      assert Encoding.get_tokens(encoding) == ["▁hello", "▁world", "</s>"]
+      assert Encoding.get_ids(encoding) == [3, 4, 2]
+      assert {:ok, "hello world"} = Tokenizer.decode(tokenizer, Encoding.get_ids(encoding))
+
+      assert Tokenizer.token_to_id(tokenizer, "<sep>") == 6
+    end
+
+    test "loads a BPE model with byte fallback" do
+      {:ok, data} = File.read("test/fixtures/bpe.model")
+      {:ok, tokenizer} = Tokenizer.from_sentencepiece_buffer(data, add_bos_token: true)
+
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "ab ab")
+      assert Encoding.get_tokens(encoding) == ["<s>", "▁ab", "▁ab"]
+      assert {:ok, "ab ab"} = Tokenizer.decode(tokenizer, Encoding.get_ids(encoding))
+
+      model = Tokenizer.get_model(tokenizer)
+      assert {:ok, [{"a", "b"}, {"▁", "ab"}]} = Tokenizers.Model.BPE.merges(model)
+    end
+
+    test "loads a Llama model with the BOS token by default" do
+      # Truncated model with the Llama 2 trainer and normalizer specs: the
+      # control pieces, the 256 byte pieces, then a few merged pieces
+      {:ok, tokenizer} = Tokenizer.from_sentencepiece_file("test/fixtures/llama.model")
+
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "the hello\né")
+      assert Encoding.get_tokens(encoding) ==
+               ["<s>", "▁the", "▁hello", "<0x0A>", "<0xC3>", "<0xA9>"]
+      assert Encoding.get_ids(encoding) == [1, 261, 266, 13, 198, 172]
+      assert {:ok, "the hello\né"} = Tokenizer.decode(tokenizer, Encoding.get_ids(encoding))
+
+      # SentencePiece runs BPE over the whole text rather than word by word,
+      # so the "▁▁" piece spans the two spaces
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "the  hello  the")
+      assert Encoding.get_tokens(encoding) == ["<s>", "▁the", "▁▁", "he", "ll", "o", "▁", "▁the"]
+      assert Encoding.get_ids(encoding) == [1, 261, 273, 260, 262, 272, 267, 261]
+
+      {:ok, tokenizer} =
+        Tokenizer.from_sentencepiece_file("test/fixtures/llama.model", add_bos_token: false)
+
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "the hello")
+      assert Encoding.get_ids(encoding) == [261, 266]
+    end
+
+    test "returns an error for malformed files" do
+      assert {:error, %Tokenizers.Error{kind: :invalid_model_file}} =
+               Tokenizer.from_sentencepiece_buffer(<<0x0A, 0xFF>>)
+    end
+  end
+
+  describe "tiktoken" do
+    test "loads ranks with a named pattern" do
+      {:ok, tokenizer} =
+        Tokenizer.from_tiktoken_file("test/fixtures/ranks.tiktoken", :cl100k_base,
+          special_tokens: [{"<|endoftext|>", 10}]
+        )
+
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "ab abc<|endoftext|>")
+      assert Encoding.get_ids(encoding) == [4, 5, 2, 10]
+      assert Encoding.get_offsets(encoding) == [{0, 2}, {2, 5}, {5, 6}, {6, 19}]
+
+      assert {:ok, "ab abc<|endoftext|>"} =
+               Tokenizer.decode(tokenizer, Encoding.get_ids(encoding), skip_special_tokens: false)
+    end
+
+    test "loads ranks with a custom pattern" do
+      {:ok, data} = File.read("test/fixtures/ranks.tiktoken")
+      {:ok, tokenizer} = Tokenizer.from_tiktoken_buffer(data, {:regex, ~S/\S+/})
+
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "abc")
+      assert Encoding.get_ids(encoding) == [6]
+    end
+
+    test "matches the ids of tiktoken byte pair merges" do
+      # Ranks learned by BPE on this library sources, with the expected ids
+      # computed by a reference implementation of tiktoken's merge loop
+      {:ok, tokenizer} =
+        Tokenizer.from_tiktoken_file("test/fixtures/trained.tiktoken", :cl100k_base)
+
+      for {text, ids} <- [
+            {"Encode the given sequence to a tokenizer encoding.",
+             [541, 409, 282, 533, 563, 287, 271, 447, 548, 46]},
+            {"It's 2024, isn't it?\n  Tokens: 12345!",
+             [73, 116, 39, 115, 32, 50, 48, 50, 52, 44, 414, 110, 39, 116, 589, 63, 10, 32] ++
+               [302, 262, 115, 58, 32, 49, 50, 51, 52, 53, 33]},
+            {"qzx tokenizers'll  decode", [113, 122, 120, 259, 301, 39, 108, 108, 32, 284, 409]}
+          ] do
+        {:ok, encoding} = Tokenizer.encode(tokenizer, text)
+        assert Encoding.get_ids(encoding) == ids
+      end
+    end
+
+    test "returns an error for special tokens colliding with ranks" do
+      assert {:error, %Tokenizers.Error{kind: :other, message: message}} =
+               Tokenizer.from_tiktoken_file("test/fixtures/ranks.tiktoken", :cl100k_base,
+                 special_tokens: [{"<|endoftext|>", 3}]
+               )
+
+      assert message =~ "collides"
+    end
+
+    test "returns an error for malformed ranks" do
+      assert {:error, %Tokenizers.Error{kind: :invalid_model_file, message: message}} =
+               Tokenizer.from_tiktoken_buffer("YQ== 0\nYg==\n", :cl100k_base)
+
+      assert message =~ "line 2"
+    end
+
+    test "returns an error for duplicate ranks and tokens" do
+      assert {:error, %Tokenizers.Error{kind: :invalid_model_file, message: message}} =
+               Tokenizer.from_tiktoken_buffer("YQ== 0\nYg== 0\n", :cl100k_base)
+
+      assert message =~ "line 2: duplicate rank"
+
+      assert {:error, %Tokenizers.Error{kind: :invalid_model_file, message: message}} =
+               Tokenizer.from_tiktoken_buffer("YQ== 0\nYg== 1\nYQ== 2\n", :cl100k_base)
+
+      assert message =~ "line 3: duplicate token"
+    end
+
+    test "raises on unknown encodings" do
+      assert_raise ArgumentError, fn ->
+        Tokenizer.from_tiktoken_buffer("YQ== 0\n", :gpt5_base)
+      end
+    end
   end
 
   describe "modify tokenizer" do
@@ -116,6 +394,42 @@ defmodule Tokenizers.TokenizerTest do
       assert opts[:method] == :head
     end
 
+    defmodule ConfigHTTPClient do
+      def request(opts) do
+        send(self(), {:request, opts[:url]})
+
+        case Path.basename(opts[:url]) do
+          "special_tokens_map.json" ->
+            {:ok, %{body: "", headers: [], status: 404}}
+
+          filename ->
+            path =
+              if filename == "tokenizer.json",
+                do: "test/fixtures/bert-base-cased.json",
+                else: "test/fixtures/tokenizer_config.json"
+
+            {:ok, %{body: File.read!(path), headers: [{"etag", filename}], status: 200}}
+        end
+      end
+    end
+
+    @tag :tmp_dir
+    test "loads config files when requested", %{tmp_dir: tmp_dir} do
+      {:ok, tokenizer} =
+        Tokenizer.from_pretrained("bert-base-cased",
+          use_cache: false,
+          load_config: true,
+          cache_dir: tmp_dir,
+          http_client: {ConfigHTTPClient, []}
+        )
+
+      assert_received {:request, "/bert-base-cased/resolve/main/tokenizer.json"}
+      assert_received {:request, "/bert-base-cased/resolve/main/tokenizer_config.json"}
+      assert_received {:request, "/bert-base-cased/resolve/main/special_tokens_map.json"}
+
+      assert Tokenizer.get_special_tokens(tokenizer) == %{"bos_token" => "<s>"}
+    end
+
     @tag :tmp_dir
     test "returns error when status is not found", %{tmp_dir: tmp_dir} do
       assert {:error, :not_found} =
@@ -177,6 +491,102 @@ defmodule Tokenizers.TokenizerTest do
                Tokenizer.encode_batch(tokenizer, ["This is a test", {"Question?", "Answer"}])
     end
 
+    test "can encode a list of words", %{tokenizer: tokenizer} do
+      {:ok, encoding} =
+        Tokenizer.encode(tokenizer, ["New York", "is", "big"], add_special_tokens: false)
+
+      assert Encoding.get_tokens(encoding) == ["New", "York", "is", "big"]
+      assert Encoding.get_word_ids(encoding) == [0, 0, 1, 2]
+    end
+
+    test "can encode a pair of word lists", %{tokenizer: tokenizer} do
+      {:ok, encoding} = Tokenizer.encode(tokenizer, {["Question", "?"], ["Answer"]})
+
+      assert Encoding.get_tokens(encoding) ==
+               ["[CLS]", "Question", "?", "[SEP]", "Answer", "[SEP]"]
+
+      assert Encoding.get_word_ids(encoding) == [nil, 0, 1, nil, 0, nil]
+      assert Encoding.get_type_ids(encoding) == [0, 0, 0, 0, 1, 1]
+    end
+
+    test "can encode a batch of strings and word lists", %{tokenizer: tokenizer} do
+      {:ok, [from_string, from_words]} =
+        Tokenizer.encode_batch(tokenizer, ["This is a test", ["This", "is", "a", "test"]])
+
+      assert Encoding.get_ids(from_string) == Encoding.get_ids(from_words)
+    end
+
+    test "returns an error for invalid input", %{tokenizer: tokenizer} do
+      assert {:error, _} = Tokenizer.encode(tokenizer, {"Question?", 1})
+    end
+
+    test "returns byte offsets by default", %{tokenizer: tokenizer} do
+      text = "Café au lait"
+      {:ok, encoding} = Tokenizer.encode(tokenizer, text, add_special_tokens: false)
+
+      assert Encoding.get_offsets(encoding) == [{0, 5}, {6, 8}, {9, 11}, {11, 13}]
+      assert Encoding.char_to_token(encoding, 6, 0) == 1
+
+      assert Enum.map(Encoding.get_offsets(encoding), fn {start, stop} ->
+               binary_part(text, start, stop - start)
+             end) == ["Café", "au", "la", "it"]
+    end
+
+    test "can return char offsets", %{tokenizer: tokenizer} do
+      {:ok, encoding} =
+        Tokenizer.encode(tokenizer, "Café au lait",
+          add_special_tokens: false,
+          offset_type: :chars
+        )
+
+      assert Encoding.get_offsets(encoding) == [{0, 4}, {5, 7}, {8, 10}, {10, 12}]
+      assert Encoding.char_to_token(encoding, 5, 0) == 1
+
+      {:ok, [encoding]} =
+        Tokenizer.encode_batch(tokenizer, ["Café au lait"],
+          add_special_tokens: false,
+          offset_type: :chars
+        )
+
+      assert Encoding.get_offsets(encoding) == [{0, 4}, {5, 7}, {8, 10}, {10, 12}]
+    end
+
+    test "can return offsets in the normalized input", %{tokenizer: tokenizer} do
+      {:ok, encoding} =
+        Tokenizer.encode(tokenizer, "Café au lait",
+          add_special_tokens: false,
+          offset_referential: :normalized
+        )
+
+      assert Encoding.get_offsets(encoding) == [{0, 5}, {5, 7}, {7, 9}, {9, 11}]
+
+      {:ok, [encoding]} =
+        Tokenizer.encode_batch(tokenizer, ["Café au lait"],
+          add_special_tokens: false,
+          offset_referential: :normalized,
+          offset_type: :chars
+        )
+
+      assert Encoding.get_offsets(encoding) == [{0, 4}, {4, 6}, {6, 8}, {8, 10}]
+
+      normalizer =
+        Tokenizers.Normalizer.sequence([
+          Tokenizers.Normalizer.nfd(),
+          Tokenizers.Normalizer.strip_accents()
+        ])
+
+      tokenizer = Tokenizer.set_normalizer(tokenizer, normalizer)
+
+      {:ok, encoding} =
+        Tokenizer.encode(tokenizer, "Café au lait",
+          add_special_tokens: false,
+          offset_referential: :normalized
+        )
+
+      assert Encoding.get_tokens(encoding) == ["Cafe", "au", "la", "##it"]
+      assert Encoding.get_offsets(encoding) == [{0, 4}, {4, 6}, {6, 8}, {8, 10}]
+    end
+
     test "can apply transformations to batch of encodings", %{tokenizer: tokenizer} do
       assert {:ok, [%Tokenizers.Encoding{}, %Tokenizers.Encoding{}]} =
                Tokenizer.encode_batch(tokenizer, ["This is a test", "And so is this"],
@@ -217,6 +627,194 @@ defmodule Tokenizers.TokenizerTest do
 
       assert Enum.map(ids, &list_to_u32/1) == Enum.map(encodings, &Encoding.get_u32_ids/1)
     end
+
+    test "can decode with spans", %{tokenizer: tokenizer} do
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "This is a test!")
+      ids = Encoding.get_ids(encoding)
+
+      assert {:ok, {text, spans}} = Tokenizer.decode_with_spans(tokenizer, ids)
+      assert {:ok, ^text} = Tokenizer.decode(tokenizer, ids)
+      assert length(spans) == length(ids)
+
+      assert Enum.map(spans, fn {start, stop} -> binary_part(text, start, stop - start) end) ==
+               ["", "This", " is", " a", " test", "!", ""]
+    end
+
+    test "can decode with spans of partial characters" do
+      {:ok, model} = Tokenizers.Model.BPE.init(%{"a" => 0, "Ã" => 1, "©" => 2, "Ġb" => 3}, [])
+      {:ok, tokenizer} = Tokenizer.init(model)
+      tokenizer = Tokenizer.set_decoder(tokenizer, Tokenizers.Decoder.byte_level())
+
+      assert {:ok, {"aé bé", [{0, 1}, {1, 1}, {1, 3}, {3, 5}, {5, 5}, {5, 7}]}} =
+               Tokenizer.decode_with_spans(tokenizer, [0, 1, 2, 3, 1, 2])
+
+      assert {:ok, {"aé bé", [{0, 1}, {1, 1}, {1, 2}, {2, 4}, {4, 4}, {4, 5}]}} =
+               Tokenizer.decode_with_spans(tokenizer, [0, 1, 2, 3, 1, 2], offset_type: :chars)
+
+      assert {:ok, {"a�", [{0, 1}, {1, 4}]}} = Tokenizer.decode_with_spans(tokenizer, [0, 1])
+    end
+  end
+
+  describe "encode_windows/4" do
+    test "keeps offsets into the whole document", %{tokenizer: tokenizer} do
+      text = "This is a test of windows"
+      {:ok, windows} = Tokenizer.encode_windows(tokenizer, text, 5, stride: 1)
+
+      assert Enum.map(windows, fn {_, encoding} -> Encoding.get_offsets(encoding) end) == [
+               [{0, 0}, {0, 4}, {5, 7}, {8, 9}, {0, 0}],
+               [{0, 0}, {8, 9}, {10, 14}, {15, 17}, {0, 0}],
+               [{0, 0}, {15, 17}, {18, 25}, {0, 0}]
+             ]
+    end
+
+    test "ignores the tokenizer truncation and padding", %{tokenizer: tokenizer} do
+      tokenizer = Tokenizer.set_truncation(tokenizer, max_length: 3)
+      tokenizer = Tokenizer.set_padding(tokenizer, strategy: {:fixed, 10})
+
+      {:ok, windows} =
+        Tokenizer.encode_windows(tokenizer, "This is a test", 3, add_special_tokens: false)
+
+      assert [{0, first}, {1, second}] = windows
+      assert Encoding.get_tokens(first) == ["This", "is", "a"]
+      assert Encoding.get_tokens(second) == ["test"]
+    end
+
+    test "can encode a batch", %{tokenizer: tokenizer} do
+      {:ok, [first, second]} =
+        Tokenizer.encode_windows_batch(tokenizer, ["Hi", "This is a test"], 4)
+
+      assert Enum.map(first, fn {index, _} -> index end) == [0]
+      assert Enum.map(second, fn {index, _} -> index end) == [0, 1]
+    end
+
+    test "returns an error when the stride doesn't fit", %{tokenizer: tokenizer} do
+      assert {:error, %Tokenizers.Error{}} =
+               Tokenizer.encode_windows(tokenizer, "This is a test", 4, stride: 2)
+
+      assert {:error, %Tokenizers.Error{}} = Tokenizer.encode_windows(tokenizer, "This", 2)
+    end
+  end
+
+  describe "count_tokens/3" do
+    test "matches the encoding length", %{tokenizer: tokenizer} do
+      text = ["This is a test", "Tokenizers count words, subwords and [MASK] tokens!"]
+      {:ok, encodings} = Tokenizer.encode_batch(tokenizer, text)
+
+      assert {:ok, counts} = Tokenizer.count_tokens_batch(tokenizer, text)
+      assert counts == Enum.map(encodings, &Encoding.get_length/1)
+    end
+
+    test "can skip special tokens", %{tokenizer: tokenizer} do
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "Hello world!", add_special_tokens: false)
+      length = Encoding.get_length(encoding)
+
+      assert {:ok, ^length} =
+               Tokenizer.count_tokens(tokenizer, "Hello world!", add_special_tokens: false)
+    end
+
+    test "applies truncation", %{tokenizer: tokenizer} do
+      tokenizer = Tokenizer.set_truncation(tokenizer, max_length: 5)
+      text = ["This is a test", "Hi"]
+
+      for add_special_tokens <- [true, false] do
+        opts = [add_special_tokens: add_special_tokens]
+        {:ok, encodings} = Tokenizer.encode_batch(tokenizer, text, opts)
+
+        assert {:ok, counts} = Tokenizer.count_tokens_batch(tokenizer, text, opts)
+        assert counts == Enum.map(encodings, &Encoding.get_length/1)
+      end
+
+      assert {:ok, 6} = Tokenizer.count_tokens(tokenizer, "This is a test", truncate: false)
+    end
+  end
+
+  describe "encode_batch_binaries/3" do
+    test "returns contiguous binaries for the whole batch", %{tokenizer: tokenizer} do
+      tokenizer = Tokenizer.set_padding(tokenizer, strategy: :batch_longest)
+      text = ["Hello world", "Yes sir hello indeed"]
+      {:ok, encodings} = Tokenizer.encode_batch(tokenizer, text)
+      {:ok, batch} = Tokenizer.encode_batch_binaries(tokenizer, text)
+
+      assert batch.shape == {2, 6}
+
+      for {field, getter} <- [
+            ids: &Encoding.get_ids/1,
+            type_ids: &Encoding.get_type_ids/1,
+            attention_mask: &Encoding.get_attention_mask/1,
+            special_tokens_mask: &Encoding.get_special_tokens_mask/1
+          ] do
+        assert Map.fetch!(batch, field) == encodings |> Enum.flat_map(getter) |> list_to_u32()
+      end
+
+      offsets =
+        for encoding <- encodings,
+            {start, stop} <- Encoding.get_offsets(encoding),
+            do: [start, stop]
+
+      assert batch.offsets == offsets |> List.flatten() |> list_to_u32()
+    end
+
+    test "returns an error when lengths differ", %{tokenizer: tokenizer} do
+      assert {:error, _} =
+               Tokenizer.encode_batch_binaries(tokenizer, ["Hello world", "Yes sir hello indeed"])
+    end
+
+    test "supports custom types and endianness", %{tokenizer: tokenizer} do
+      tokenizer = Tokenizer.set_padding(tokenizer, strategy: :batch_longest)
+      text = ["Hello world", "Yes sir hello indeed"]
+      {:ok, encodings} = Tokenizer.encode_batch(tokenizer, text)
+
+      {:ok, batch} =
+        Tokenizer.encode_batch_binaries(tokenizer, text,
+          types: [ids: {:s, 64}, attention_mask: {:u, 8}],
+          endianness: :big
+        )
+
+      ids = Enum.flat_map(encodings, &Encoding.get_ids/1)
+      assert batch.ids == for(id <- ids, into: <<>>, do: <<id::big-signed-64>>)
+
+      mask = Enum.flat_map(encodings, &Encoding.get_attention_mask/1)
+      assert batch.attention_mask == for(value <- mask, into: <<>>, do: <<value::8>>)
+
+      type_ids = Enum.flat_map(encodings, &Encoding.get_type_ids/1)
+      assert batch.type_ids == for(id <- type_ids, into: <<>>, do: <<id::big-unsigned-32>>)
+    end
+  end
+
+  describe "encoding binaries" do
+    test "can export ids with a custom type", %{tokenizer: tokenizer} do
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "Hello world")
+      ids = Encoding.get_ids(encoding)
+
+      assert {:ok, binary} =
+               Encoding.get_ids_binary(encoding, type: {:s, 64}, endianness: :little)
+
+      assert binary == for(id <- ids, into: <<>>, do: <<id::little-signed-64>>)
+
+      assert {:ok, binary} = Encoding.get_ids_binary(encoding, type: {:u, 16}, endianness: :big)
+      assert binary == for(id <- ids, into: <<>>, do: <<id::big-unsigned-16>>)
+
+      assert Encoding.get_ids_binary(encoding) == {:ok, Encoding.get_u32_ids(encoding)}
+    end
+
+    test "can export masks as bytes", %{tokenizer: tokenizer} do
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "This is a test")
+
+      assert {:ok, binary} = Encoding.get_attention_mask_binary(encoding, type: {:u, 8})
+      assert binary == :binary.list_to_bin(Encoding.get_attention_mask(encoding))
+
+      assert {:ok, binary} = Encoding.get_special_tokens_mask_binary(encoding, type: {:u, 8})
+      assert binary == :binary.list_to_bin(Encoding.get_special_tokens_mask(encoding))
+
+      assert {:ok, binary} = Encoding.get_type_ids_binary(encoding, type: {:u, 8})
+      assert binary == :binary.list_to_bin(Encoding.get_type_ids(encoding))
+    end
+
+    test "returns an error when ids don't fit the type", %{tokenizer: tokenizer} do
+      {:ok, encoding} = Tokenizer.encode(tokenizer, "Hello world")
+      assert Enum.any?(Encoding.get_ids(encoding), &(&1 > 255))
+      assert {:error, _} = Encoding.get_ids_binary(encoding, type: {:u, 8})
+    end
   end
 
   describe "encode metadata" do
@@ -265,6 +863,353 @@ defmodule Tokenizers.TokenizerTest do
     end
   end
 
+  describe "chat templates" do
+    setup do
+      {:ok, tokenizer} =
+        Tokenizer.from_file("test/fixtures/bert-base-cased.json",
+          tokenizer_config: "test/fixtures/tokenizer_config.json",
+          special_tokens_map: "test/fixtures/special_tokens_map.json"
+        )
+
+      messages = [
+        %{"role" => "system", "content" => "Be brief."},
+        %{"role" => "user", "content" => " Hi "}
+      ]
+
+      {:ok, chat_tokenizer: tokenizer, messages: messages}
+    end
+
+    test "renders the loaded template", %{chat_tokenizer: tokenizer, messages: messages} do
+      assert Tokenizer.apply_chat_template(tokenizer, messages) ==
+               {:ok, "<s>system: Be brief.\nuser: Hi\n"}
+
+      assert Tokenizer.apply_chat_template(tokenizer, messages, add_generation_prompt: true) ==
+               {:ok, "<s>system: Be brief.\nuser: Hi\nassistant:"}
+    end
+
+    test "renders a given template with whitespace control", %{messages: messages} do
+      template = """
+      {%- set ns = namespace(system="") %}
+      {%- for message in messages %}
+        {%- if message.role == 'system' %}
+          {%- set ns.system = message.content ~ ' ' %}
+        {%- else %}
+      [INST] {{ ns.system }}{{ message.content.strip() }} [/INST]
+        {%- endif %}
+      {%- endfor %}
+      """
+
+      {:ok, tokenizer} = Tokenizer.from_file("test/fixtures/bert-base-cased.json")
+
+      assert Tokenizer.apply_chat_template(tokenizer, messages, chat_template: template) ==
+               {:ok, "[INST] Be brief. Hi [/INST]"}
+    end
+
+    test "encodes the prompt without adding special tokens", %{
+      chat_tokenizer: tokenizer,
+      messages: messages
+    } do
+      {:ok, encoding} = Tokenizer.encode_chat(tokenizer, Enum.take(messages, -1))
+      assert Encoding.get_tokens(encoding) == ["<s>", "user", ":", "Hi"]
+    end
+
+    test "returns template errors", %{chat_tokenizer: tokenizer, messages: messages} do
+      assert {:error, %Tokenizers.Error{kind: :invalid_template}} =
+               Tokenizer.apply_chat_template(tokenizer, messages, chat_template: "{% if %}")
+
+      template = "{{ raise_exception('Conversation roles must alternate') }}"
+
+      assert {:error, %Tokenizers.Error{kind: :invalid_template, message: message}} =
+               Tokenizer.apply_chat_template(tokenizer, messages, chat_template: template)
+
+      assert message =~ "Conversation roles must alternate"
+    end
+
+    test "returns an error without a template", %{tokenizer: tokenizer, messages: messages} do
+      assert {:error, %Tokenizers.Error{kind: :other}} =
+               Tokenizer.apply_chat_template(tokenizer, messages)
+    end
+
+    test "renders filters, macros and ranges", %{tokenizer: tokenizer, messages: messages} do
+      template = """
+      {%- macro turn(message, prefix='> ') -%}
+        {{ prefix ~ message.role }}: {{ message.content | trim | indent(2) }}
+      {%- endmacro -%}
+      {%- for i in range(messages | length - 1, -1, -1) %}{{ turn(messages[i]) }};{% endfor %}
+      {{- messages | selectattr('role', 'equalto', 'user') | map(attribute='content') | list }}
+      {{- messages | rejectattr('role', 'eq', 'user') | map(attribute='role') | unique | list }}
+      {{- {'role': 'tool', 'content': 'x'} | tojson }}
+      """
+
+      assert Tokenizer.apply_chat_template(tokenizer, messages, chat_template: template) ==
+               {:ok,
+                "> user: Hi;> system: Be brief.;[' Hi ']['system']" <>
+                  ~s({"role": "tool", "content": "x"})}
+    end
+
+    test "limits recursive macros", %{tokenizer: tokenizer, messages: messages} do
+      template = "{% macro f() %}{{ f() }}{% endmacro %}{{ f() }}"
+
+      assert {:error, %Tokenizers.Error{kind: :invalid_template, message: message}} =
+               Tokenizer.apply_chat_template(tokenizer, messages, chat_template: template)
+
+      assert message =~ "nested too deeply"
+
+      template = """
+      {%- macro f(n) %}{% if n > 0 %}{{ f(n - 1) }}{% else %}done{% endif %}{% endmacro %}
+      {{- f(50) }}
+      """
+
+      assert Tokenizer.apply_chat_template(tokenizer, messages, chat_template: template) ==
+               {:ok, "done"}
+    end
+
+    test "limits nested expressions", %{tokenizer: tokenizer, messages: messages} do
+      for template <- [
+            "{{ #{String.duplicate("(", 10_000)}1#{String.duplicate(")", 10_000)} }}",
+            "{{ #{String.duplicate("not ", 10_000)}1 }}",
+            "{{ 1#{String.duplicate(" + 1", 10_000)} }}",
+            String.duplicate("{% if true %}", 10_000) <> String.duplicate("{% endif %}", 10_000),
+            "{% set ns = namespace(x=1) %}{% for i in range(10000) %}{% set ns.x = [ns.x] %}" <>
+              "{% endfor %}"
+          ] do
+        assert {:error, %Tokenizers.Error{kind: :invalid_template, message: message}} =
+                 Tokenizer.apply_chat_template(tokenizer, messages, chat_template: template)
+
+        assert message =~ "nested too deeply"
+      end
+
+      template = "{{ #{String.duplicate("(", 100)}1#{String.duplicate(")", 100)} }}"
+
+      assert Tokenizer.apply_chat_template(tokenizer, messages, chat_template: template) ==
+               {:ok, "1"}
+    end
+
+    test "limits repeated strings", %{tokenizer: tokenizer, messages: messages} do
+      assert {:error, %Tokenizers.Error{kind: :invalid_template, message: message}} =
+               Tokenizer.apply_chat_template(tokenizer, messages,
+                 chat_template: "{{ 'a' * 10 ** 12 }}"
+               )
+
+      assert message =~ "string too long"
+
+      template = "{{ 'ab' * 3 }}"
+
+      assert Tokenizer.apply_chat_template(tokenizer, messages, chat_template: template) ==
+               {:ok, "ababab"}
+    end
+
+    test "returns integer overflow errors", %{tokenizer: tokenizer, messages: messages} do
+      for template <- [
+            "{{ (-9223372036854775807 - 1) // -1 }}",
+            "{{ -(-9223372036854775807 - 1) }}",
+            "{{ 9223372036854775807 + 1 }}",
+            "{{ 2 ** 64 }}"
+          ] do
+        assert {:error, %Tokenizers.Error{kind: :invalid_template, message: "integer overflow"}} =
+                 Tokenizer.apply_chat_template(tokenizer, messages, chat_template: template)
+      end
+    end
+  end
+
+  describe "Hub chat templates" do
+    # Configurations with the chat templates published on the Hub, the
+    # expected prompts are the ones rendered by transformers
+    defp hub_tokenizer(name) do
+      {:ok, tokenizer} =
+        Tokenizer.from_file("test/fixtures/bert-base-cased.json",
+          tokenizer_config: "test/fixtures/chat_templates/#{name}.json"
+        )
+
+      tokenizer
+    end
+
+    setup do
+      system = %{"role" => "system", "content" => "You are a helpful assistant."}
+
+      chat = [
+        %{"role" => "user", "content" => "Hello!"},
+        %{"role" => "assistant", "content" => "Hi there. "},
+        %{"role" => "user", "content" => "How are you?"}
+      ]
+
+      tool_call = %{
+        "function" => %{"arguments" => %{"city" => "Paris"}, "name" => "get_weather"},
+        "id" => "abcdef123",
+        "type" => "function"
+      }
+
+      tool_use = [
+        %{"role" => "user", "content" => "Weather in Paris?"},
+        %{"role" => "assistant", "tool_calls" => [tool_call]},
+        %{"role" => "tool", "content" => "22C", "tool_call_id" => "abcdef123"}
+      ]
+
+      {:ok, system: system, chat: chat, tool_use: tool_use}
+    end
+
+    test "Llama 3.1", %{system: system, chat: chat, tool_use: tool_use} do
+      tokenizer = hub_tokenizer("llama3.1")
+
+      header =
+        "<|begin_of_text|><|start_header_id|>system<|end_header_id|>\n\n" <>
+          "Cutting Knowledge Date: December 2023\nToday Date: 26 Jul 2024\n\n"
+
+      assert Tokenizer.apply_chat_template(tokenizer, [system, hd(chat)],
+               add_generation_prompt: true
+             ) ==
+               {:ok,
+                header <>
+                  "You are a helpful assistant.<|eot_id|>" <>
+                  "<|start_header_id|>user<|end_header_id|>\n\nHello!<|eot_id|>" <>
+                  "<|start_header_id|>assistant<|end_header_id|>\n\n"}
+
+      assert Tokenizer.apply_chat_template(tokenizer, chat) ==
+               {:ok,
+                header <>
+                  "<|eot_id|><|start_header_id|>user<|end_header_id|>\n\nHello!<|eot_id|>" <>
+                  "<|start_header_id|>assistant<|end_header_id|>\n\nHi there.<|eot_id|>" <>
+                  "<|start_header_id|>user<|end_header_id|>\n\nHow are you?<|eot_id|>"}
+
+      assert Tokenizer.apply_chat_template(tokenizer, tool_use) ==
+               {:ok,
+                header <>
+                  "<|eot_id|><|start_header_id|>user<|end_header_id|>\n\n" <>
+                  "Weather in Paris?<|eot_id|>" <>
+                  "<|start_header_id|>assistant<|end_header_id|>\n\n" <>
+                  ~s({"name": "get_weather", "parameters": {"city": "Paris"}}<|eot_id|>) <>
+                  ~s(<|start_header_id|>ipython<|end_header_id|>\n\n"22C"<|eot_id|>)}
+    end
+
+    test "Mistral v0.3", %{system: system, chat: chat, tool_use: tool_use} do
+      tokenizer = hub_tokenizer("mistral-v0.3")
+
+      assert Tokenizer.apply_chat_template(tokenizer, [system | chat]) ==
+               {:ok,
+                "<s>[INST] Hello![/INST] Hi there.</s>" <>
+                  "[INST] You are a helpful assistant.\n\nHow are you?[/INST]"}
+
+      assert Tokenizer.apply_chat_template(tokenizer, tool_use) ==
+               {:ok,
+                "<s>[INST] Weather in Paris?[/INST]" <>
+                  ~s([TOOL_CALLS] [{"arguments": {"city": "Paris"}, "name": "get_weather", ) <>
+                  ~s("id": "abcdef123"}]</s>) <>
+                  ~s([TOOL_RESULTS] {"content": 22C, "call_id": "abcdef123"}[/TOOL_RESULTS])}
+
+      assert {:error, %Tokenizers.Error{message: message}} =
+               Tokenizer.apply_chat_template(tokenizer, Enum.drop(chat, 1))
+
+      assert message =~ "conversation roles must alternate"
+    end
+
+    test "Qwen 2.5", %{system: system, chat: chat, tool_use: tool_use} do
+      tokenizer = hub_tokenizer("qwen2.5")
+      default_system = "You are Qwen, created by Alibaba Cloud. You are a helpful assistant."
+
+      assert Tokenizer.apply_chat_template(tokenizer, [system, hd(chat)],
+               add_generation_prompt: true
+             ) ==
+               {:ok,
+                "<|im_start|>system\nYou are a helpful assistant.<|im_end|>\n" <>
+                  "<|im_start|>user\nHello!<|im_end|>\n<|im_start|>assistant\n"}
+
+      assert Tokenizer.apply_chat_template(tokenizer, chat) ==
+               {:ok,
+                "<|im_start|>system\n#{default_system}<|im_end|>\n" <>
+                  "<|im_start|>user\nHello!<|im_end|>\n" <>
+                  "<|im_start|>assistant\nHi there. <|im_end|>\n" <>
+                  "<|im_start|>user\nHow are you?<|im_end|>\n"}
+
+      assert Tokenizer.apply_chat_template(tokenizer, tool_use) ==
+               {:ok,
+                "<|im_start|>system\n#{default_system}<|im_end|>\n" <>
+                  "<|im_start|>user\nWeather in Paris?<|im_end|>\n" <>
+                  "<|im_start|>assistant\n<tool_call>\n" <>
+                  ~s({"name": "get_weather", "arguments": {"city": "Paris"}}\n) <>
+                  "</tool_call><|im_end|>\n" <>
+                  "<|im_start|>user\n<tool_response>\n22C\n</tool_response><|im_end|>\n"}
+    end
+
+    test "Gemma 2", %{system: system, chat: chat} do
+      tokenizer = hub_tokenizer("gemma2")
+
+      assert Tokenizer.apply_chat_template(tokenizer, chat, add_generation_prompt: true) ==
+               {:ok,
+                "<bos><start_of_turn>user\nHello!<end_of_turn>\n" <>
+                  "<start_of_turn>model\nHi there.<end_of_turn>\n" <>
+                  "<start_of_turn>user\nHow are you?<end_of_turn>\n" <>
+                  "<start_of_turn>model\n"}
+
+      assert {:error, %Tokenizers.Error{message: "System role not supported"}} =
+               Tokenizer.apply_chat_template(tokenizer, [system | chat])
+    end
+  end
+
+  describe "training" do
+    setup do
+      {:ok, model} = Tokenizers.Model.BPE.empty()
+      {:ok, tokenizer} = Tokenizer.init(model)
+      tokenizer = Tokenizer.set_pre_tokenizer(tokenizer, Tokenizers.PreTokenizer.whitespace())
+      {:ok, trainer} = Tokenizers.Trainer.bpe(show_progress: false)
+
+      {:ok, tokenizer: tokenizer, trainer: trainer}
+    end
+
+    test "can train from a list of sequences", %{tokenizer: tokenizer, trainer: trainer} do
+      {:ok, trained} =
+        Tokenizer.train_from_iterator(tokenizer, ["hello world", "hello there"],
+          trainer: trainer
+        )
+
+      assert Tokenizer.token_to_id(trained, "hello") != nil
+      assert Tokenizer.get_vocab_size(tokenizer) == 0
+    end
+
+    test "can train from a lazy stream in chunks", %{tokenizer: tokenizer, trainer: trainer} do
+      parent = self()
+
+      stream =
+        Stream.map(1..100, fn i ->
+          send(parent, {:consumed, i})
+          "sequence number #{i}"
+        end)
+
+      {:ok, trained} =
+        Tokenizer.train_from_iterator(tokenizer, stream, trainer: trainer, chunk_size: 10)
+
+      assert Tokenizer.token_to_id(trained, "sequence") != nil
+      assert_received {:consumed, 100}
+    end
+
+    test "reraises errors from the stream", %{tokenizer: tokenizer, trainer: trainer} do
+      stream =
+        Stream.map(1..100, fn
+          50 -> raise "invalid sequence"
+          i -> "sequence number #{i}"
+        end)
+
+      assert_raise RuntimeError, "invalid sequence", fn ->
+        Tokenizer.train_from_iterator(tokenizer, stream, trainer: trainer, chunk_size: 10)
+      end
+    end
+
+    test "reports progress to the given process", %{tokenizer: tokenizer, trainer: trainer} do
+      {:ok, trained} =
+        Tokenizer.train_from_iterator(tokenizer, ["hello world", "hello there"],
+          trainer: trainer,
+          progress: self()
+        )
+
+      assert_receive {:training_progress, %{phase: :pre_processing, sequences: 2, words: 4}}
+      assert_receive {:training_progress, %{phase: :training} = event}
+      refute Map.has_key?(event, :vocab_size)
+
+      assert_receive {:training_progress, %{phase: :done, vocab_size: vocab_size, merges: merges}}
+      assert vocab_size == Tokenizer.get_vocab_size(trained, with_added_tokens: false)
+      assert merges > 0
+    end
+  end
+
   defp list_to_u32(list) do
     for x <- list, into: <<>>, do: <<x::native-unsigned-32>>
   end
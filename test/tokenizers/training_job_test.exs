@@ -0,0 +1,119 @@
+defmodule Tokenizers.TrainingJobTest do
+  use ExUnit.Case, async: true
+  doctest Tokenizers.TrainingJob
+
+  alias Tokenizers.Tokenizer
+  alias Tokenizers.TrainingJob
+
+  setup do
+    {:ok, model} = Tokenizers.Model.BPE.empty()
+    {:ok, tokenizer} = Tokenizer.init(model)
+    tokenizer = Tokenizer.set_pre_tokenizer(tokenizer, Tokenizers.PreTokenizer.whitespace())
+    {:ok, trainer} = Tokenizers.Trainer.bpe(show_progress: false)
+
+    {:ok, tokenizer: tokenizer, trainer: trainer}
+  end
+
+  test "trains in the background", %{tokenizer: tokenizer, trainer: trainer} do
+    job =
+      TrainingJob.start_from_iterator(tokenizer, ["hello world", "hello there"], trainer: trainer)
+
+    assert {:ok, trained} = TrainingJob.await(job)
+    assert Tokenizer.token_to_id(trained, "hello") != nil
+    assert {:ok, ^trained} = TrainingJob.status(job)
+  end
+
+  test "consumes the sequences without blocking the caller", %{
+    tokenizer: tokenizer,
+    trainer: trainer
+  } do
+    parent = self()
+
+    sequences =
+      Stream.map(1..100, fn i ->
+        send(parent, {:consumed_by, self()})
+        "sequence number #{i}"
+      end)
+
+    job = TrainingJob.start_from_iterator(tokenizer, sequences, trainer: trainer, chunk_size: 10)
+
+    assert {:ok, trained} = TrainingJob.await(job)
+    assert Tokenizer.token_to_id(trained, "sequence") != nil
+    assert_received {:consumed_by, pid} when pid != parent
+  end
+
+  test "is cancelled when the sequences raise", %{tokenizer: tokenizer, trainer: trainer} do
+    sequences = Stream.map(1..100, fn _ -> raise "invalid sequence" end)
+
+    ExUnit.CaptureLog.capture_log(fn ->
+      job = TrainingJob.start_from_iterator(tokenizer, sequences, trainer: trainer)
+      assert {:error, :cancelled} = TrainingJob.await(job)
+    end)
+  end
+
+  test "is cancelled when the caller exits", %{tokenizer: tokenizer, trainer: trainer} do
+    parent = self()
+
+    sequences =
+      Stream.repeatedly(fn ->
+        send(parent, {:consumed_by, self()})
+        "sequence number"
+      end)
+
+    caller =
+      spawn(fn ->
+        job = TrainingJob.start_from_iterator(tokenizer, sequences, trainer: trainer)
+        send(parent, {:job, job})
+        Process.sleep(:infinity)
+      end)
+
+    assert_receive {:job, job}
+    assert_receive {:consumed_by, feeder}
+    feeder_ref = Process.monitor(feeder)
+    Process.exit(caller, :kill)
+
+    assert_receive {:DOWN, ^feeder_ref, :process, _, :killed}
+    assert_job_status(job, {:error, :cancelled})
+  end
+
+  @tag :tmp_dir
+  test "trains from files in the background", %{
+    tokenizer: tokenizer,
+    trainer: trainer,
+    tmp_dir: tmp_dir
+  } do
+    path = Path.join(tmp_dir, "corpus.txt")
+    File.write!(path, "hello world\nhello there\n")
+
+    job = TrainingJob.start_from_files(tokenizer, [path], trainer: trainer)
+
+    assert {:ok, trained} = TrainingJob.await(job)
+    assert Tokenizer.token_to_id(trained, "hello") != nil
+  end
+
+  test "returns an error for missing files", %{tokenizer: tokenizer, trainer: trainer} do
+    job = TrainingJob.start_from_files(tokenizer, ["non/existent/file.txt"], trainer: trainer)
+
+    assert {:error, _} = TrainingJob.await(job)
+  end
+
+  test "can be cancelled", %{tokenizer: tokenizer, trainer: trainer} do
+    sequences = Stream.map(1..200_000, fn i -> "sequence number #{i}" end)
+    job = TrainingJob.start_from_iterator(tokenizer, sequences, trainer: trainer)
+
+    assert :ok = TrainingJob.cancel(job)
+    assert {:error, :cancelled} = TrainingJob.await(job)
+    assert {:error, :cancelled} = TrainingJob.status(job)
+  end
+
+  defp assert_job_status(job, expected, attempts \\ 100) do
+    case TrainingJob.status(job) do
+      :running when attempts > 0 ->
+        Process.sleep(10)
+        assert_job_status(job, expected, attempts - 1)
+
+      status ->
+        assert status == expected
+    end
+  end
+end